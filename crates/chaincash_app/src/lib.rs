@@ -1,11 +1,20 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
-use chaincash_offchain::node::node_from_config;
+use chaincash_offchain::{
+    bundle::{BundleError, NoteBundle},
+    node::node_from_config,
+};
 use chaincash_predicate::predicates::Predicate;
 use chaincash_server::Server;
-use chaincash_services::{scanner::start_scanner, ServerState};
+use chaincash_services::{
+    acceptance::{evaluate_note, AcceptanceDecision, NoteSource},
+    parameters::{ParametersClient, ParametersError},
+    scanner::start_scanner,
+    ServerState,
+};
 use chaincash_store::{ChainCashStore, Update};
 use config::{Environment, File};
+use ergo_lib::{ergo_chain_types::Digest32, ergotree_ir::chain::token::TokenId};
 use thiserror::Error;
 use tracing::info;
 
@@ -20,11 +29,60 @@ pub enum Error {
     #[error(transparent)]
     Node(#[from] chaincash_offchain::node::NodeError),
 
+    #[error(transparent)]
+    Parameters(#[from] ParametersError),
+
     #[error("Failed to load chaincash predicates specified in config file")]
     LoadPredicate(#[from] chaincash_predicate::Error),
 
     #[error(transparent)]
     Config(#[from] config::ConfigError),
+
+    #[error("Failed to derive contracts for configured network")]
+    Contract(#[from] chaincash_offchain::contracts::ContractError),
+
+    #[error(transparent)]
+    Acceptance(#[from] chaincash_services::acceptance::AcceptanceError),
+
+    #[error("Invalid note token id {0}")]
+    InvalidTokenId(String),
+
+    #[error("Failed to read note bundle: {0}")]
+    ReadBundle(#[from] std::io::Error),
+
+    #[error("Invalid note bundle: {0}")]
+    Bundle(#[from] BundleError),
+}
+
+/// Note evaluated by [`ChainCashApp::evaluate_note`]
+#[derive(Debug)]
+pub enum EvaluateNote {
+    /// Note tracked in store, by primary key
+    NoteId(i32),
+    /// Note tracked in store, by hex encoded note token id
+    TokenId(String),
+    /// File with a note bundle as returned by note export, in JSON or binary form
+    BundleFile(PathBuf),
+}
+
+impl EvaluateNote {
+    fn into_source(self) -> Result<NoteSource, Error> {
+        Ok(match self {
+            EvaluateNote::NoteId(note_id) => NoteSource::NoteId(note_id),
+            EvaluateNote::TokenId(token_id) => NoteSource::TokenId(TokenId::from(
+                Digest32::try_from(token_id.clone())
+                    .map_err(|_| Error::InvalidTokenId(token_id))?,
+            )),
+            EvaluateNote::BundleFile(path) => {
+                let bytes = std::fs::read(path)?;
+                let bundle = match serde_json::from_slice(&bytes) {
+                    Ok(value) => NoteBundle::from_json(value)?,
+                    Err(_) => NoteBundle::from_bytes(&bytes)?,
+                };
+                NoteSource::Bundle(bundle)
+            }
+        })
+    }
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -33,6 +91,12 @@ pub struct ChainCashConfig {
     store: chaincash_store::Config,
     node: chaincash_offchain::node::Config,
     acceptance: chaincash_predicate::Config,
+    #[serde(default)]
+    network: chaincash_offchain::network::Config,
+    #[serde(default)]
+    fee: chaincash_offchain::transactions::fee::FeePolicy,
+    #[serde(default)]
+    box_selection: chaincash_offchain::transactions::box_selection::Config,
 }
 
 impl ChainCashConfig {
@@ -57,7 +121,8 @@ impl ChainCashApp {
         Self { config }
     }
 
-    pub async fn run(&self) -> Result<(), Error> {
+    // Open store and node and load predicates configured for acceptance
+    fn state(&self) -> Result<ServerState, Error> {
         let store = ChainCashStore::open(&self.config.store.url)?;
 
         if store.has_updates()? {
@@ -74,6 +139,22 @@ impl ChainCashApp {
 
         info!("loaded {} predicates from files", predicates.len());
 
+        let node = node_from_config(&self.config.node)?;
+        let parameters = ParametersClient::new(self.config.node.url())?;
+
+        Ok(ServerState::new(
+            node,
+            parameters,
+            store,
+            predicates,
+            self.config.acceptance.combine,
+            self.config.network.clone(),
+            self.config.fee.clone(),
+            self.config.box_selection,
+        )?)
+    }
+
+    pub async fn run(&self) -> Result<(), Error> {
         let listener = listenfd::ListenFd::from_env()
             .take_tcp_listener(0)
             .unwrap()
@@ -85,10 +166,15 @@ impl ChainCashApp {
                 .unwrap()
             });
 
-        let node = node_from_config(&self.config.node)?;
-
-        let state = Arc::new(ServerState::new(node, store, predicates));
+        let state = Arc::new(self.state()?);
         start_scanner(state.clone()).await.unwrap();
         Ok(Server::serve(listener, state).await?)
     }
+
+    /// Evaluate configured predicates against a note tracked in store or offered in a bundle without starting the server
+    pub async fn evaluate_note(&self, note: EvaluateNote) -> Result<AcceptanceDecision, Error> {
+        let source = note.into_source()?;
+        let state = self.state()?;
+        Ok(evaluate_note(&state, source).await?)
+    }
 }
@@ -1,6 +1,8 @@
 //! ChainCash server CLI.
-use anyhow::Result;
-use chaincash_app::{ChainCashApp, ChainCashConfig};
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use chaincash_app::{ChainCashApp, ChainCashConfig, EvaluateNote};
 use clap::{Parser, Subcommand};
 use directories::BaseDirs;
 use tracing::info;
@@ -10,6 +12,19 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 enum Command {
     /// Runs the chaincash server
     Run,
+    /// Evaluates configured acceptance predicates against a note tracked by the server or offered in a bundle and explains
+    /// the result
+    Evaluate {
+        /// ID of the note in the store
+        #[arg(required_unless_present_any = ["token_id", "bundle"], conflicts_with_all = ["token_id", "bundle"])]
+        note_id: Option<i32>,
+        /// Token ID of a note tracked by the server, hex encoded
+        #[arg(long, conflicts_with = "bundle")]
+        token_id: Option<String>,
+        /// File with a note bundle, JSON or binary as returned by note export. The bundle is verified but not imported
+        #[arg(long)]
+        bundle: Option<PathBuf>,
+    },
 }
 
 #[derive(Debug, Parser)]
@@ -60,6 +75,38 @@ impl Cli {
 
         match &self.command {
             Command::Run => Ok(ChainCashApp::new(ChainCashConfig::new()?).run().await?),
+            Command::Evaluate {
+                note_id,
+                token_id,
+                bundle,
+            } => {
+                let note = match (note_id, token_id, bundle) {
+                    (Some(note_id), _, _) => EvaluateNote::NoteId(*note_id),
+                    (_, Some(token_id), _) => EvaluateNote::TokenId(token_id.clone()),
+                    (_, _, Some(bundle)) => EvaluateNote::BundleFile(bundle.clone()),
+                    (None, None, None) => bail!("note id, --token-id or --bundle is required"),
+                };
+                let label = match &note {
+                    EvaluateNote::NoteId(note_id) => format!("note {note_id}"),
+                    EvaluateNote::TokenId(token_id) => format!("note {token_id}"),
+                    EvaluateNote::BundleFile(bundle) => format!("note in {}", bundle.display()),
+                };
+                let decision = ChainCashApp::new(ChainCashConfig::new()?)
+                    .evaluate_note(note)
+                    .await?;
+                println!(
+                    "{label} {}",
+                    if decision.accepted {
+                        "accepted"
+                    } else {
+                        "rejected"
+                    }
+                );
+                for (i, trace) in decision.traces.iter().enumerate() {
+                    print!("predicate {i}: {trace}");
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -0,0 +1,395 @@
+//! In-memory chain state backing the mock node
+use std::collections::HashMap;
+
+use ergo_lib::{
+    chain::{
+        ergo_box::box_builder::ErgoBoxCandidateBuilder,
+        ergo_state_context::ErgoStateContext,
+        parameters::Parameters,
+        transaction::{unsigned::UnsignedTransaction, Transaction, TxId},
+    },
+    ergo_chain_types::{
+        blake2b256_hash, ADDigest, AutolykosSolution, BlockId, Digest32, EcPoint, Header,
+        PreHeader, Votes,
+    },
+    ergotree_interpreter::sigma_protocol::private_input::DlogProverInput,
+    ergotree_ir::{
+        chain::{
+            address::{Address, NetworkAddress, NetworkPrefix},
+            ergo_box::{box_value::BoxValue, BoxId, ErgoBox, ErgoBoxCandidate},
+            token::Token,
+        },
+        ergo_tree::ErgoTree,
+    },
+    wallet::{secret_key::SecretKey, tx_context::TransactionContext, Wallet},
+};
+use sigma_ser::ScorexSerializable;
+use thiserror::Error;
+
+/// Height of the first block of a new mock chain
+pub const GENESIS_HEIGHT: u32 = 1_000;
+/// Milliseconds between mock blocks
+const BLOCK_INTERVAL: u64 = 120_000;
+
+#[derive(Debug, Error)]
+pub enum MockNodeError {
+    #[error("Box {0} not found")]
+    BoxNotFound(BoxId),
+    #[error("Box {0} is already spent")]
+    BoxSpent(BoxId),
+    #[error("Transaction {0} not found")]
+    TransactionNotFound(TxId),
+    #[error("Block {0:?} not found")]
+    BlockNotFound(BlockId),
+    #[error("Scan {0} not found")]
+    ScanNotFound(u32),
+    #[error("Address {0} does not belong to wallet")]
+    UnknownAddress(String),
+    #[error("Failed to sign transaction: {0}")]
+    Signing(String),
+}
+
+/// Box known to the node, whether spent or not
+#[derive(Clone, Debug)]
+pub struct BoxEntry {
+    pub ergo_box: ErgoBox,
+    pub inclusion_height: u32,
+    pub global_index: u64,
+    pub spent_by: Option<TxId>,
+}
+
+/// Transaction included in a block
+#[derive(Clone, Debug)]
+pub struct TransactionEntry {
+    pub transaction: Transaction,
+    pub block_id: BlockId,
+    pub inclusion_height: u32,
+    pub index: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Block {
+    pub header: Header,
+    pub transactions: Vec<Transaction>,
+}
+
+/// Scan registered through `/scan/register`, tracking rule is kept in node JSON format
+#[derive(Clone, Debug)]
+pub struct RegisteredScan {
+    pub scan_id: u32,
+    pub request: serde_json::Value,
+}
+
+pub struct MockChain {
+    pub network: NetworkPrefix,
+    secrets: Vec<DlogProverInput>,
+    blocks: Vec<Block>,
+    boxes: HashMap<BoxId, BoxEntry>,
+    transactions: HashMap<TxId, TransactionEntry>,
+    scans: Vec<RegisteredScan>,
+    // Counter for ids of boxes created outside of transactions
+    funding_nonce: u64,
+}
+
+impl MockChain {
+    /// Create chain with 10 empty blocks and a wallet holding a single random key
+    pub fn new(network: NetworkPrefix) -> Self {
+        let mut chain = Self {
+            network,
+            secrets: vec![DlogProverInput::random()],
+            blocks: vec![],
+            boxes: HashMap::new(),
+            transactions: HashMap::new(),
+            scans: vec![],
+            funding_nonce: 0,
+        };
+        for _ in 0..10 {
+            chain.mine_block(vec![]);
+        }
+        chain
+    }
+
+    pub fn height(&self) -> u32 {
+        self.blocks.last().map(|b| b.header.height).unwrap_or(0)
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    pub fn block(&self, block_id: &BlockId) -> Result<&Block, MockNodeError> {
+        self.blocks
+            .iter()
+            .find(|block| block.header.id == *block_id)
+            .ok_or_else(|| MockNodeError::BlockNotFound(block_id.clone()))
+    }
+
+    /// Public key of the first wallet key, funds are received and change is sent to it
+    pub fn wallet_public_key(&self) -> EcPoint {
+        *self.secrets[0].public_image().h
+    }
+
+    pub fn wallet_public_keys(&self) -> Vec<EcPoint> {
+        self.secrets
+            .iter()
+            .map(|secret| *secret.public_image().h)
+            .collect()
+    }
+
+    pub fn wallet_addresses(&self) -> Vec<String> {
+        self.wallet_public_keys()
+            .into_iter()
+            .map(|pk| NetworkAddress::new(self.network, &Address::P2Pk(pk.into())).to_base58())
+            .collect()
+    }
+
+    pub fn change_address(&self) -> String {
+        self.wallet_addresses().swap_remove(0)
+    }
+
+    /// Add a new key to the wallet, returning its public key
+    pub fn add_wallet_key(&mut self) -> EcPoint {
+        let secret = DlogProverInput::random();
+        let public_key = *secret.public_image().h;
+        self.secrets.push(secret);
+        public_key
+    }
+
+    pub fn secret_for_address(&self, address: &str) -> Result<&DlogProverInput, MockNodeError> {
+        self.wallet_addresses()
+            .iter()
+            .position(|wallet_address| wallet_address == address)
+            .map(|i| &self.secrets[i])
+            .ok_or_else(|| MockNodeError::UnknownAddress(address.to_owned()))
+    }
+
+    fn wallet_trees(&self) -> Vec<ErgoTree> {
+        self.wallet_public_keys()
+            .into_iter()
+            .filter_map(|pk| Address::P2Pk(pk.into()).script().ok())
+            .collect()
+    }
+
+    /// Add an unspent box at the current height without a transaction creating it, e.g. oracle boxes or initial wallet funds
+    pub fn add_box(&mut self, candidate: ErgoBoxCandidate) -> ErgoBox {
+        self.funding_nonce += 1;
+        let tx_id = TxId(blake2b256_hash(
+            &[b"funding".as_slice(), &self.funding_nonce.to_be_bytes()].concat(),
+        ));
+        let ergo_box = ErgoBox::from_box_candidate(&candidate, tx_id, 0).unwrap();
+        self.insert_box(ergo_box.clone(), self.height());
+        ergo_box
+    }
+
+    /// Fund wallet with a box holding `value` nanoERG and `tokens`
+    pub fn add_wallet_box(&mut self, value: u64, tokens: Vec<Token>) -> ErgoBox {
+        let tree = Address::P2Pk(self.wallet_public_key().into())
+            .script()
+            .unwrap();
+        let mut builder =
+            ErgoBoxCandidateBuilder::new(BoxValue::new(value).unwrap(), tree, self.height());
+        for token in tokens {
+            builder.add_token(token);
+        }
+        self.add_box(builder.build().unwrap())
+    }
+
+    fn insert_box(&mut self, ergo_box: ErgoBox, inclusion_height: u32) {
+        let global_index = self.boxes.len() as u64;
+        self.boxes.insert(
+            ergo_box.box_id(),
+            BoxEntry {
+                ergo_box,
+                inclusion_height,
+                global_index,
+                spent_by: None,
+            },
+        );
+    }
+
+    pub fn box_by_id(&self, box_id: &BoxId) -> Result<&BoxEntry, MockNodeError> {
+        self.boxes
+            .get(box_id)
+            .ok_or_else(|| MockNodeError::BoxNotFound(*box_id))
+    }
+
+    pub fn transaction(&self, tx_id: &TxId) -> Result<&TransactionEntry, MockNodeError> {
+        self.transactions
+            .get(tx_id)
+            .ok_or_else(|| MockNodeError::TransactionNotFound(*tx_id))
+    }
+
+    /// Unspent boxes ordered by creation
+    pub fn unspent_boxes(&self) -> Vec<&BoxEntry> {
+        let mut boxes: Vec<_> = self
+            .boxes
+            .values()
+            .filter(|entry| entry.spent_by.is_none())
+            .collect();
+        boxes.sort_by_key(|entry| entry.global_index);
+        boxes
+    }
+
+    pub fn wallet_unspent_boxes(&self) -> Vec<&BoxEntry> {
+        let trees = self.wallet_trees();
+        self.unspent_boxes()
+            .into_iter()
+            .filter(|entry| trees.contains(&entry.ergo_box.ergo_tree))
+            .collect()
+    }
+
+    pub fn register_scan(&mut self, request: serde_json::Value) -> u32 {
+        let scan_id = self.scans.len() as u32 + 1;
+        self.scans.push(RegisteredScan { scan_id, request });
+        scan_id
+    }
+
+    pub fn scans(&self) -> &[RegisteredScan] {
+        &self.scans
+    }
+
+    pub fn scan(&self, scan_id: u32) -> Result<&RegisteredScan, MockNodeError> {
+        self.scans
+            .iter()
+            .find(|scan| scan.scan_id == scan_id)
+            .ok_or(MockNodeError::ScanNotFound(scan_id))
+    }
+
+    /// State context of the next block, made from the last 10 headers
+    pub fn state_context(&self) -> ErgoStateContext {
+        let headers: Vec<Header> = self
+            .blocks
+            .iter()
+            .rev()
+            .take(10)
+            .map(|block| block.header.clone())
+            .collect();
+        let last = headers[0].clone();
+        let mut pre_header = PreHeader::from(last.clone());
+        pre_header.parent_id = last.id;
+        pre_header.height = last.height + 1;
+        pre_header.timestamp = last.timestamp + BLOCK_INTERVAL;
+        ErgoStateContext::new(
+            pre_header,
+            headers.try_into().unwrap(),
+            Parameters::default(),
+        )
+    }
+
+    /// Sign transaction with wallet keys. Inputs and data inputs must be unspent boxes
+    pub fn sign(&self, transaction: UnsignedTransaction) -> Result<Transaction, MockNodeError> {
+        let input_boxes = transaction
+            .inputs
+            .iter()
+            .map(|input| self.unspent_box(&input.box_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let data_boxes = transaction
+            .data_inputs
+            .iter()
+            .flat_map(|data_inputs| data_inputs.iter())
+            .map(|data_input| self.unspent_box(&data_input.box_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let tx_context = TransactionContext::new(transaction, input_boxes, data_boxes)
+            .map_err(|e| MockNodeError::Signing(e.to_string()))?;
+        let wallet = Wallet::from_secrets(
+            self.secrets
+                .iter()
+                .cloned()
+                .map(SecretKey::DlogSecretKey)
+                .collect(),
+        );
+        wallet
+            .sign_transaction(tx_context, &self.state_context(), None)
+            .map_err(|e| MockNodeError::Signing(e.to_string()))
+    }
+
+    fn unspent_box(&self, box_id: &BoxId) -> Result<ErgoBox, MockNodeError> {
+        let entry = self.box_by_id(box_id)?;
+        match entry.spent_by {
+            Some(_) => Err(MockNodeError::BoxSpent(*box_id)),
+            None => Ok(entry.ergo_box.clone()),
+        }
+    }
+
+    /// Include transaction in a new block. Scripts are not verified, only that inputs exist and are unspent
+    pub fn submit(&mut self, transaction: Transaction) -> Result<TxId, MockNodeError> {
+        for input in transaction.inputs.iter() {
+            self.unspent_box(&input.box_id)?;
+        }
+        if let Some(data_inputs) = transaction.data_inputs.as_ref() {
+            for data_input in data_inputs.iter() {
+                self.unspent_box(&data_input.box_id)?;
+            }
+        }
+        let tx_id = transaction.id();
+        self.mine_block(vec![transaction]);
+        Ok(tx_id)
+    }
+
+    /// Mine a block including `transactions`, spending their inputs and adding their outputs
+    pub fn mine_block(&mut self, transactions: Vec<Transaction>) -> BlockId {
+        let header = self.next_header(&transactions);
+        let height = header.height;
+        for (index, transaction) in transactions.iter().enumerate() {
+            let tx_id = transaction.id();
+            for input in transaction.inputs.iter() {
+                if let Some(entry) = self.boxes.get_mut(&input.box_id) {
+                    entry.spent_by = Some(tx_id);
+                }
+            }
+            for output in transaction.outputs.iter() {
+                self.insert_box(output.clone(), height);
+            }
+            self.transactions.insert(
+                tx_id,
+                TransactionEntry {
+                    transaction: transaction.clone(),
+                    block_id: header.id.clone(),
+                    inclusion_height: height,
+                    index,
+                },
+            );
+        }
+        let block_id = header.id.clone();
+        self.blocks.push(Block {
+            header,
+            transactions,
+        });
+        block_id
+    }
+
+    fn next_header(&self, transactions: &[Transaction]) -> Header {
+        let parent = self.blocks.last().map(|block| &block.header);
+        let tx_ids: Vec<u8> = transactions
+            .iter()
+            .flat_map(|tx| tx.id().0 .0.to_vec())
+            .collect();
+        let mut header = Header {
+            version: 2,
+            id: BlockId(Digest32::zero()),
+            parent_id: parent
+                .map(|header| header.id.clone())
+                .unwrap_or(BlockId(Digest32::zero())),
+            ad_proofs_root: Digest32::zero(),
+            state_root: ADDigest::zero(),
+            transaction_root: blake2b256_hash(&tx_ids),
+            timestamp: parent
+                .map(|header| header.timestamp + BLOCK_INTERVAL)
+                .unwrap_or(1_600_000_000_000),
+            n_bits: 117_707_945,
+            height: parent
+                .map(|header| header.height + 1)
+                .unwrap_or(GENESIS_HEIGHT),
+            extension_root: Digest32::zero(),
+            autolykos_solution: AutolykosSolution {
+                miner_pk: Box::new(self.wallet_public_key()),
+                pow_onetime_pk: None,
+                nonce: vec![0; 8],
+                pow_distance: None,
+            },
+            votes: Votes([0; 3]),
+        };
+        header.id = BlockId(blake2b256_hash(&header.scorex_serialize_bytes().unwrap()));
+        header
+    }
+}
@@ -0,0 +1,222 @@
+//! chaincash-mock-node
+//!
+//! In-process Ergo node serving the subset of the node REST API used by ChainCash (node info, wallet, scans, blockchain
+//! indexer and blocks) from an in-memory UTXO set. Every submitted transaction is included in a new block immediately,
+//! so mint, spend, redeem and rescan flows can be tested end to end without a running node.
+//!
+//! Signing only checks wallet keys, scripts of submitted transactions are not verified. Use
+//! `chaincash_offchain::transactions::validation` to check contract conditions.
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use ergo_client::node::NodeClient;
+use ergo_lib::ergotree_ir::chain::address::NetworkPrefix;
+use tokio::{net::TcpListener, task::JoinHandle};
+
+pub mod chain;
+pub(crate) mod routes;
+
+pub use chain::{MockChain, MockNodeError};
+
+/// API key accepted by the mock node. Any key is accepted, this is what [`MockNode::client`] uses
+pub const API_KEY: &str = "mock";
+
+pub struct MockNode {
+    chain: Arc<Mutex<MockChain>>,
+    addr: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl MockNode {
+    /// Start mock node on a random local port. The node is stopped when dropped
+    pub async fn start(network: NetworkPrefix) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let chain = Arc::new(Mutex::new(MockChain::new(network)));
+        let router = routes::router().with_state(chain.clone());
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router.into_make_service()).await {
+                tracing::error!("mock node stopped: {e}");
+            }
+        });
+        Ok(Self {
+            chain,
+            addr,
+            handle,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Node client connected to this node
+    pub fn client(&self) -> NodeClient {
+        NodeClient::from_url_str(&self.url(), API_KEY.to_owned(), Duration::from_secs(5))
+            .expect("mock node url is valid")
+    }
+
+    /// Chain state, used to fund wallet, add boxes and mine blocks from tests.
+    /// Guard must be dropped before making requests to node
+    pub fn chain(&self) -> MutexGuard<'_, MockChain> {
+        self.chain.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ergo_client::node::endpoints::scan::{Scan, TrackingRule};
+    use ergo_lib::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use ergo_lib::chain::transaction::ergo_transaction::ErgoTransaction;
+    use ergo_lib::ergotree_ir::chain::address::{Address, AddressEncoder};
+    use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergo_lib::ergotree_ir::chain::ergo_box::RegisterId;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+    use ergo_lib::wallet::box_selector::{BoxSelector, SimpleBoxSelector};
+    use ergo_lib::wallet::tx_builder::TxBuilder;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_info_and_wallet() {
+        let node = MockNode::start(NetworkPrefix::Mainnet).await.unwrap();
+        let client = node.client();
+        let funding = node.chain().add_wallet_box(1_000_000_000, vec![]);
+
+        let info = client.endpoints().root().unwrap().info().await.unwrap();
+        assert_eq!(info.full_height as u32, node.chain().height());
+
+        let status = client.endpoints().wallet().unwrap().status().await.unwrap();
+        assert_eq!(status.change_address, node.chain().change_address());
+
+        let utxos = client.extensions().get_utxos().await.unwrap();
+        assert_eq!(utxos, vec![funding]);
+
+        let headers = client
+            .endpoints()
+            .blocks()
+            .unwrap()
+            .last_headers(10)
+            .await
+            .unwrap();
+        assert_eq!(headers.len(), 10);
+        assert_eq!(headers[9].height, node.chain().height());
+        assert_eq!(headers[9].parent_id, headers[8].id);
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_scan() {
+        let node = MockNode::start(NetworkPrefix::Mainnet).await.unwrap();
+        let client = node.client();
+        let funding = node.chain().add_wallet_box(10_000_000_000, vec![]);
+        let (height, change_address) = {
+            let chain = node.chain();
+            (chain.height(), chain.change_address())
+        };
+        let tracked_tree = ergo_lib::ergoscript_compiler::compiler::compile(
+            "sigmaProp(HEIGHT > 0)",
+            Default::default(),
+        )
+        .unwrap();
+
+        let scan_id = client
+            .endpoints()
+            .scan()
+            .unwrap()
+            .register(&Scan {
+                scan_name: "test".into(),
+                wallet_interaction: "off".into(),
+                tracking_rule: TrackingRule::And {
+                    args: vec![TrackingRule::Contains {
+                        register: Some(RegisterId::R1),
+                        value: tracked_tree.sigma_serialize_bytes().unwrap().into(),
+                    }],
+                },
+                remove_offchain: true,
+            })
+            .await
+            .unwrap();
+        let scans = client.endpoints().scan().unwrap().list_all().await.unwrap();
+        assert_eq!(scans.len(), 1);
+
+        let output = ErgoBoxCandidateBuilder::new(
+            BoxValue::new(1_000_000_000).unwrap(),
+            tracked_tree,
+            height,
+        )
+        .build()
+        .unwrap();
+        let selection = SimpleBoxSelector::new()
+            .select(
+                vec![funding.clone()],
+                BoxValue::new(1_000_000_000 + BoxValue::SAFE_USER_MIN.as_u64()).unwrap(),
+                &[],
+            )
+            .unwrap();
+        let transaction = TxBuilder::new(
+            selection,
+            vec![output],
+            height,
+            BoxValue::SAFE_USER_MIN,
+            AddressEncoder::unchecked_parse_address_from_str(&change_address).unwrap(),
+        )
+        .build()
+        .unwrap();
+        let signed = client
+            .extensions()
+            .sign_and_submit(transaction.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(node.chain().height(), height + 1);
+        let scan_boxes = client
+            .extensions()
+            .get_all_unspent_boxes(scan_id as u32, false)
+            .await
+            .unwrap();
+        assert_eq!(scan_boxes.len(), 1);
+        assert_eq!(scan_boxes[0].ergo_box, signed.outputs.first().clone());
+
+        let indexer = client.endpoints().blockchain().unwrap();
+        let block_id = indexer
+            .get_transaction_by_id(&signed.id())
+            .await
+            .unwrap()
+            .block_id;
+        let block_transactions = client
+            .endpoints()
+            .blocks()
+            .unwrap()
+            .transactions(&block_id)
+            .await
+            .unwrap();
+        assert_eq!(block_transactions, vec![signed.clone()]);
+        let spent = indexer.get_box_by_id(&funding.box_id()).await.unwrap();
+        assert_eq!(spent.ergo_box, funding);
+
+        // Only change is left in wallet
+        let wallet_tree = Address::P2Pk(node.chain().wallet_public_key().into())
+            .script()
+            .unwrap();
+        let utxos = client.extensions().get_utxos().await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].ergo_tree, wallet_tree);
+
+        // Spending the same box twice is rejected
+        assert!(client
+            .extensions()
+            .sign_and_submit(transaction)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,448 @@
+//! Subset of the Ergo node REST API used by ChainCash, served from a [`MockChain`]
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use ergo_lib::{
+    chain::{
+        parameters::Parameters,
+        transaction::{unsigned::UnsignedTransaction, Transaction, TxId},
+    },
+    ergo_chain_types::{Base16EncodedBytes, BlockId, Digest32},
+    ergotree_ir::{
+        chain::{
+            address::{Address, NetworkAddress, NetworkPrefix},
+            ergo_box::{BoxId, ErgoBox, NonMandatoryRegisterId},
+            token::TokenId,
+        },
+        serialization::SigmaSerializable,
+    },
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::chain::{BoxEntry, MockChain, MockNodeError};
+
+pub type MockState = Arc<Mutex<MockChain>>;
+
+impl IntoResponse for MockNodeError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            MockNodeError::BoxNotFound(_)
+            | MockNodeError::TransactionNotFound(_)
+            | MockNodeError::BlockNotFound(_)
+            | MockNodeError::ScanNotFound(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let body = json!({
+            "error": status.as_u16(),
+            "reason": status.canonical_reason(),
+            "detail": self.to_string(),
+        });
+        (status, Json(body)).into_response()
+    }
+}
+
+fn bad_request(detail: impl ToString) -> Response {
+    let body = json!({
+        "error": 400,
+        "reason": "bad.request",
+        "detail": detail.to_string(),
+    });
+    (StatusCode::BAD_REQUEST, Json(body)).into_response()
+}
+
+fn lock(state: &MockState) -> MutexGuard<'_, MockChain> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn router() -> Router<MockState> {
+    Router::new()
+        .route("/info", get(info))
+        .route("/wallet/status", get(wallet_status))
+        .route("/wallet/addresses", get(wallet_addresses))
+        .route("/wallet/rescan", post(wallet_rescan))
+        .route("/wallet/boxes/unspent", get(wallet_unspent_boxes))
+        .route("/wallet/transaction/sign", post(wallet_sign))
+        .route("/wallet/getPrivateKey", post(wallet_private_key))
+        .route("/transactions", post(submit_transaction))
+        .route("/scan/register", post(scan_register))
+        .route("/scan/listAll", get(scan_list_all))
+        .route("/scan/unspentBoxes/:scan_id", get(scan_unspent_boxes))
+        .route("/blockchain/indexedHeight", get(indexed_height))
+        .route("/blockchain/box/byId/:box_id", get(box_by_id))
+        .route(
+            "/blockchain/box/unspent/byTokenId/:token_id",
+            get(unspent_boxes_by_token_id),
+        )
+        .route(
+            "/blockchain/transaction/byId/:tx_id",
+            get(transaction_by_id),
+        )
+        .route("/blocks/lastHeaders/:count", get(last_headers))
+        .route("/blocks/:block_id/transactions", get(block_transactions))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    Base16EncodedBytes::new(bytes).into()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_digest(s: &str) -> Result<Digest32, Response> {
+    Digest32::try_from(s.to_owned()).map_err(bad_request)
+}
+
+fn address(chain: &MockChain, ergo_box: &ErgoBox) -> Value {
+    Address::recreate_from_ergo_tree(&ergo_box.ergo_tree)
+        .map(|address| NetworkAddress::new(chain.network, &address).to_base58())
+        .map(Value::String)
+        .unwrap_or(Value::Null)
+}
+
+fn merge(mut value: Value, fields: Value) -> Value {
+    if let (Value::Object(object), Value::Object(fields)) = (&mut value, fields) {
+        object.extend(fields);
+    }
+    value
+}
+
+/// Box in the format returned by wallet and scan endpoints
+fn wallet_box(chain: &MockChain, entry: &BoxEntry, scans: Vec<u32>) -> Value {
+    json!({
+        "box": entry.ergo_box,
+        "confirmationsNum": chain.height() - entry.inclusion_height,
+        "address": address(chain, &entry.ergo_box),
+        "creationTransaction": entry.ergo_box.transaction_id,
+        "spendingTransaction": entry.spent_by,
+        "spendingHeight": Value::Null,
+        "inclusionHeight": entry.inclusion_height,
+        "onchain": true,
+        "spent": entry.spent_by.is_some(),
+        "creationOutIndex": entry.ergo_box.index,
+        "scans": scans,
+    })
+}
+
+/// Box in the format returned by blockchain (indexer) endpoints
+fn indexed_box(chain: &MockChain, entry: &BoxEntry) -> Value {
+    merge(
+        json!(entry.ergo_box),
+        json!({
+            "globalIndex": entry.global_index,
+            "inclusionHeight": entry.inclusion_height,
+            "address": address(chain, &entry.ergo_box),
+            "spentTransactionId": entry.spent_by,
+        }),
+    )
+}
+
+async fn info(State(state): State<MockState>) -> Json<Value> {
+    let chain = lock(&state);
+    let best = chain.blocks().last().map(|block| block.header.id.clone());
+    let genesis = chain.blocks().first().map(|block| block.header.id.clone());
+    let height = chain.height();
+    Json(json!({
+        "name": "chaincash-mock-node",
+        "appVersion": "5.0.0",
+        "network": if chain.network == NetworkPrefix::Mainnet { "mainnet" } else { "testnet" },
+        "fullHeight": height,
+        "headersHeight": height,
+        "maxPeerHeight": height,
+        "bestFullHeaderId": best,
+        "bestHeaderId": best,
+        "previousFullHeaderId": Value::Null,
+        "genesisBlockId": genesis,
+        "stateRoot": hex(&[0; 33]),
+        "stateType": "utxo",
+        "stateVersion": best,
+        "isMining": false,
+        "peersCount": 0,
+        "unconfirmedCount": 0,
+        "difficulty": 1,
+        "headersScore": height,
+        "fullBlocksScore": height,
+        "currentTime": chain.blocks().last().map(|block| block.header.timestamp),
+        "launchTime": chain.blocks().first().map(|block| block.header.timestamp),
+        "lastSeenMessageTime": Value::Null,
+        "isExplorer": true,
+        "restApiUrl": Value::Null,
+        "lastMemPoolUpdateTime": Value::Null,
+        "eip27Supported": true,
+        "eip37Supported": true,
+        "parameters": parameters_json(&Parameters::default(), height),
+    }))
+}
+
+// Parameters chain validates transactions with, in node's JSON format
+fn parameters_json(parameters: &Parameters, height: u32) -> Value {
+    json!({
+        "height": height,
+        "blockVersion": parameters.block_version,
+        "storageFeeFactor": parameters.storage_fee_factor(),
+        "minValuePerByte": parameters.min_value_per_byte(),
+        "maxBlockSize": parameters.max_block_size(),
+        "maxBlockCost": parameters.max_block_cost(),
+        "tokenAccessCost": parameters.token_access_cost(),
+        "inputCost": parameters.input_cost(),
+        "dataInputCost": parameters.data_input_cost(),
+        "outputCost": parameters.output_cost(),
+    })
+}
+
+async fn wallet_status(State(state): State<MockState>) -> Json<Value> {
+    let chain = lock(&state);
+    Json(json!({
+        "isInitialized": true,
+        "isUnlocked": true,
+        "changeAddress": chain.change_address(),
+        "walletHeight": chain.height(),
+        "error": "",
+    }))
+}
+
+async fn wallet_addresses(State(state): State<MockState>) -> Json<Vec<String>> {
+    Json(lock(&state).wallet_addresses())
+}
+
+/// Boxes are never forgotten by the mock wallet, so rescanning only has to be acknowledged
+async fn wallet_rescan() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn wallet_unspent_boxes(State(state): State<MockState>) -> Json<Vec<Value>> {
+    let chain = lock(&state);
+    Json(
+        chain
+            .wallet_unspent_boxes()
+            .into_iter()
+            .map(|entry| wallet_box(&chain, entry, vec![]))
+            .collect(),
+    )
+}
+
+#[derive(Deserialize)]
+struct SignRequest {
+    tx: UnsignedTransaction,
+}
+
+async fn wallet_sign(
+    State(state): State<MockState>,
+    Json(request): Json<SignRequest>,
+) -> Result<Json<Transaction>, MockNodeError> {
+    lock(&state).sign(request.tx).map(Json)
+}
+
+#[derive(Deserialize)]
+struct PrivateKeyRequest {
+    address: String,
+}
+
+async fn wallet_private_key(
+    State(state): State<MockState>,
+    Json(request): Json<PrivateKeyRequest>,
+) -> Result<Json<String>, MockNodeError> {
+    let chain = lock(&state);
+    let secret = chain.secret_for_address(&request.address)?;
+    Ok(Json(hex(&secret.to_bytes())))
+}
+
+async fn submit_transaction(
+    State(state): State<MockState>,
+    Json(transaction): Json<Transaction>,
+) -> Result<Json<TxId>, MockNodeError> {
+    lock(&state).submit(transaction).map(Json)
+}
+
+async fn scan_register(State(state): State<MockState>, Json(request): Json<Value>) -> Json<Value> {
+    let scan_id = lock(&state).register_scan(request);
+    Json(json!({ "scanId": scan_id }))
+}
+
+async fn scan_list_all(State(state): State<MockState>) -> Json<Vec<Value>> {
+    let chain = lock(&state);
+    Json(
+        chain
+            .scans()
+            .iter()
+            .map(|scan| merge(scan.request.clone(), json!({ "scanId": scan.scan_id })))
+            .collect(),
+    )
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Paging {
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+impl Paging {
+    fn apply<T>(&self, items: Vec<T>) -> Vec<T> {
+        items
+            .into_iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+fn register_bytes(ergo_box: &ErgoBox, register: &str) -> Option<Vec<u8>> {
+    let register_id = match register {
+        "R1" => return ergo_box.ergo_tree.sigma_serialize_bytes().ok(),
+        "R4" => NonMandatoryRegisterId::R4,
+        "R5" => NonMandatoryRegisterId::R5,
+        "R6" => NonMandatoryRegisterId::R6,
+        "R7" => NonMandatoryRegisterId::R7,
+        "R8" => NonMandatoryRegisterId::R8,
+        "R9" => NonMandatoryRegisterId::R9,
+        _ => return None,
+    };
+    ergo_box
+        .get_register(register_id.into())
+        .ok()
+        .flatten()
+        .and_then(|constant| constant.sigma_serialize_bytes().ok())
+}
+
+/// Evaluate a scan tracking rule in node JSON format against `ergo_box`
+fn matches_rule(rule: &Value, ergo_box: &ErgoBox) -> bool {
+    let args = || rule["args"].as_array().into_iter().flatten();
+    let value = || rule["value"].as_str().and_then(decode_hex);
+    let register =
+        || register_bytes(ergo_box, rule["register"].as_str().unwrap_or("R1")).unwrap_or_default();
+    match rule["predicate"].as_str() {
+        Some("and") => args().all(|arg| matches_rule(arg, ergo_box)),
+        Some("or") => args().any(|arg| matches_rule(arg, ergo_box)),
+        Some("not") => !matches_rule(&rule["args"][0], ergo_box),
+        Some("contains") => value().is_some_and(|value| {
+            let register = register();
+            value.is_empty() || register.windows(value.len()).any(|w| w == value)
+        }),
+        Some("equals") => value().is_some_and(|value| register() == value),
+        Some("containsAsset") => rule["assetId"].as_str().is_some_and(|asset_id| {
+            ergo_box
+                .tokens
+                .iter()
+                .flat_map(|tokens| tokens.iter())
+                .any(|token| String::from(token.token_id) == asset_id)
+        }),
+        _ => false,
+    }
+}
+
+async fn scan_unspent_boxes(
+    State(state): State<MockState>,
+    Path(scan_id): Path<u32>,
+    Query(paging): Query<Paging>,
+) -> Result<Json<Vec<Value>>, MockNodeError> {
+    let chain = lock(&state);
+    let rule = &chain.scan(scan_id)?.request["trackingRule"];
+    let boxes = chain
+        .unspent_boxes()
+        .into_iter()
+        .filter(|entry| matches_rule(rule, &entry.ergo_box))
+        .map(|entry| wallet_box(&chain, entry, vec![scan_id]))
+        .collect();
+    Ok(Json(paging.apply(boxes)))
+}
+
+async fn indexed_height(State(state): State<MockState>) -> Json<Value> {
+    let height = lock(&state).height();
+    Json(json!({ "indexedHeight": height, "fullHeight": height }))
+}
+
+async fn box_by_id(
+    State(state): State<MockState>,
+    Path(box_id): Path<String>,
+) -> Result<Json<Value>, Response> {
+    let box_id = BoxId::from(parse_digest(&box_id)?);
+    let chain = lock(&state);
+    let entry = chain
+        .box_by_id(&box_id)
+        .map_err(IntoResponse::into_response)?;
+    Ok(Json(indexed_box(&chain, entry)))
+}
+
+async fn unspent_boxes_by_token_id(
+    State(state): State<MockState>,
+    Path(token_id): Path<String>,
+    Query(paging): Query<Paging>,
+) -> Result<Json<Vec<Value>>, Response> {
+    let token_id = TokenId::from(parse_digest(&token_id)?);
+    let chain = lock(&state);
+    let boxes = chain
+        .unspent_boxes()
+        .into_iter()
+        .filter(|entry| {
+            entry
+                .ergo_box
+                .tokens
+                .iter()
+                .flat_map(|tokens| tokens.iter())
+                .any(|token| token.token_id == token_id)
+        })
+        .map(|entry| indexed_box(&chain, entry))
+        .collect();
+    Ok(Json(paging.apply(boxes)))
+}
+
+async fn transaction_by_id(
+    State(state): State<MockState>,
+    Path(tx_id): Path<String>,
+) -> Result<Json<Value>, Response> {
+    let tx_id = TxId(parse_digest(&tx_id)?);
+    let chain = lock(&state);
+    let entry = chain
+        .transaction(&tx_id)
+        .map_err(IntoResponse::into_response)?;
+    Ok(Json(merge(
+        json!(entry.transaction),
+        json!({
+            "blockId": entry.block_id,
+            "inclusionHeight": entry.inclusion_height,
+            "numConfirmations": chain.height() - entry.inclusion_height,
+            "index": entry.index,
+        }),
+    )))
+}
+
+async fn last_headers(State(state): State<MockState>, Path(count): Path<usize>) -> Json<Value> {
+    let chain = lock(&state);
+    let blocks = chain.blocks();
+    let headers: Vec<_> = blocks[blocks.len().saturating_sub(count)..]
+        .iter()
+        .map(|block| &block.header)
+        .collect();
+    Json(json!(headers))
+}
+
+async fn block_transactions(
+    State(state): State<MockState>,
+    Path(block_id): Path<String>,
+) -> Result<Json<Value>, Response> {
+    let block_id = BlockId(parse_digest(&block_id)?);
+    let chain = lock(&state);
+    let block = chain
+        .block(&block_id)
+        .map_err(IntoResponse::into_response)?;
+    Ok(Json(json!({
+        "headerId": block.header.id,
+        "transactions": block.transactions,
+        "blockVersion": block.header.version,
+        "size": 0,
+    })))
+}
@@ -0,0 +1,89 @@
+//! Benchmarks for NoteHistory operations on long histories
+//!
+//! `rebuild` is what every operation used to cost before provers were cached. `load_lookup_proof` loads a history with the
+//! digest committed to by its box and generates a proof, which is what redeeming a note loaded from the store does. It is
+//! measured for a short and a long history to show it doesn't depend on history length. "cloned" benchmarks run the operation
+//! on a clone of a loaded history, which is what spending a note does
+
+use chaincash_offchain::note_history::{NoteHistory, OwnershipEntry, Signature};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use ergo_lib::ergo_chain_types::ec_point::generator;
+use ergo_lib::ergotree_ir::chain::token::TokenId;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+const HISTORY_LENGTH: usize = 1_000;
+
+fn ownership_entry(i: usize) -> OwnershipEntry {
+    // Signatures aren't verified by NoteHistory, so any well-formed signature will do
+    let mut signature_bytes = generator().sigma_serialize_bytes().unwrap();
+    signature_bytes.extend_from_slice(&[1u8; 32]);
+    let mut reserve_id = [0u8; 32];
+    reserve_id[..8].copy_from_slice(&(i as u64).to_be_bytes());
+    OwnershipEntry {
+        reserve_id: TokenId::from(ergo_lib::ergo_chain_types::Digest32::from(reserve_id)),
+        amount: 1_000,
+        signature: Signature::try_from(&signature_bytes[..]).unwrap(),
+        signer: generator(),
+    }
+}
+
+// History whose prover is built and cached
+fn loaded_history(entries: &[OwnershipEntry]) -> NoteHistory {
+    let history = NoteHistory::from_entries(entries.to_vec());
+    history.digest();
+    history
+}
+
+fn note_history(c: &mut Criterion) {
+    let entries: Vec<OwnershipEntry> = (0..HISTORY_LENGTH).map(ownership_entry).collect();
+    let history = loaded_history(&entries);
+    let new_entry = ownership_entry(HISTORY_LENGTH);
+
+    let mut group = c.benchmark_group("note_history_1000");
+    group.bench_function("rebuild", |b| {
+        b.iter(|| NoteHistory::from_entries(entries.clone()).digest())
+    });
+    group.bench_function("add_commitment/cloned", |b| {
+        // Adding a commitment moves the cached prover to the new digest, so it's cached again for every iteration
+        b.iter_batched(
+            || loaded_history(&entries).clone(),
+            |mut history| history.add_commitment(new_entry.clone()).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("lookup_proof", |b| {
+        b.iter(|| history.lookup_proof(entries[500].reserve_id, 500).unwrap())
+    });
+    group.bench_function("lookup_proof/cloned", |b| {
+        b.iter_batched(
+            || history.clone(),
+            |history| history.lookup_proof(entries[500].reserve_id, 500).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("digest", |b| b.iter(|| history.digest()));
+    group.finish();
+
+    let mut group = c.benchmark_group("note_history_load_lookup_proof");
+    for length in [10, HISTORY_LENGTH] {
+        let entries = &entries[..length];
+        let digest = loaded_history(entries).digest();
+        let position = length / 2;
+        group.bench_with_input(BenchmarkId::from_parameter(length), &length, |b, _| {
+            b.iter_batched(
+                || entries.to_vec(),
+                |entries| {
+                    let reserve_id = entries[position].reserve_id;
+                    NoteHistory::from_entries_with_digest(entries, digest)
+                        .lookup_proof(reserve_id, position as i64)
+                        .unwrap()
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, note_history);
+criterion_main!(benches);
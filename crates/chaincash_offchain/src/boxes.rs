@@ -1,4 +1,4 @@
-use crate::note_history::{sign, NoteHistory, OwnershipEntry};
+use crate::note_history::{sign, signing_message, NoteHistory, OwnershipEntry};
 use ergo_lib::{
     ergo_chain_types::{
         ec_point::{exponentiate, generator},
@@ -11,7 +11,6 @@ use ergo_lib::{
             token::{Token, TokenAmount, TokenId},
         },
         mir::{avl_tree_data::AvlTreeData, constant::TryExtractInto},
-        serialization::SigmaSerializable,
         types::stype::SType,
     },
 };
@@ -57,6 +56,22 @@ pub struct Note {
 }
 
 impl Note {
+    /// Digest of the note history committed to in R4 register of note box
+    pub fn history_digest(note_box: &ErgoBox) -> Result<ADDigest, Error> {
+        note_box
+            .get_register(NonMandatoryRegisterId::R4.into())?
+            .ok_or_else(|| Error::FieldNotSet("avl tree"))
+            .and_then(|reg| {
+                if reg.tpe == SType::SAvlTree {
+                    Ok(reg.v.try_extract_into::<AvlTreeData>().unwrap().digest)
+                } else {
+                    Err(Error::InvalidType {
+                        field: "avl tree".to_owned(),
+                        tpe: reg.tpe,
+                    })
+                }
+            })
+    }
     pub fn new(note_box: ErgoBox, history: NoteHistory) -> Result<Self, Error> {
         let owner = note_box
             .get_register(NonMandatoryRegisterId::R5.into())?
@@ -94,22 +109,10 @@ impl Note {
                 found: history.ownership_entries().len() as u64,
             });
         }
-        let box_avltree = note_box
-            .get_register(NonMandatoryRegisterId::R4.into())?
-            .ok_or_else(|| Error::FieldNotSet("avl tree"))
-            .and_then(|reg| {
-                if reg.tpe == SType::SAvlTree {
-                    Ok(reg.v.try_extract_into::<AvlTreeData>().unwrap())
-                } else {
-                    Err(Error::InvalidType {
-                        field: "avl tree".to_owned(),
-                        tpe: reg.tpe,
-                    })
-                }
-            })?;
-        if box_avltree.digest != history.digest() {
+        let box_digest = Self::history_digest(&note_box)?;
+        if box_digest != history.digest() {
             return Err(Error::InvalidAVLDigest {
-                box_digest: box_avltree.digest,
+                box_digest,
                 history_digest: history.digest(),
             });
         }
@@ -133,11 +136,7 @@ impl Note {
     }
 
     fn bytes_to_sign(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(48);
-        buf.extend_from_slice(&self.length.to_be_bytes());
-        buf.extend_from_slice(&self.amount.as_u64().to_be_bytes());
-        buf.extend_from_slice(&self.note_id.sigma_serialize_bytes().unwrap());
-        buf
+        signing_message(self.length, *self.amount.as_u64(), self.note_id)
     }
 
     // Sign a note against reserve id, returning a new ownership entry
@@ -160,6 +159,7 @@ impl Note {
             reserve_id,
             amount: self.amount.into(),
             signature,
+            signer: self.owner.clone(),
         })
     }
     pub fn ergo_box(&self) -> &ErgoBox {
@@ -170,6 +170,7 @@ impl Note {
 #[derive(Serialize)]
 pub struct ReserveBoxSpec {
     pub owner: EcPoint,
+    /// Height refund was initiated at (R5), if reserve is currently being refunded
     pub refund_height: Option<i64>,
     pub identifier: TokenId,
     #[serde(skip)]
@@ -231,3 +232,129 @@ impl TryFrom<&ErgoBox> for ReserveBoxSpec {
         })
     }
 }
+
+/// Receipt box created when a note is redeemed against a reserve. Receipts can be presented to reserves of earlier signers in the note's history
+pub struct ReceiptBoxSpec {
+    /// Token id of the redeemed note
+    pub note_id: TokenId,
+    /// Amount of note tokens redeemed
+    pub amount: TokenAmount,
+    /// History tree of the note at the time of redemption
+    pub history: AvlTreeData,
+    /// Position in history of the ownership entry that was last redeemed
+    pub position: i64,
+    /// Height the receipt was created at
+    pub height: i32,
+    /// Owner of the reserve the receipt was redeemed against
+    pub reserve_owner: EcPoint,
+    inner: ErgoBox,
+}
+
+impl ReceiptBoxSpec {
+    pub fn box_id(&self) -> BoxId {
+        self.inner.box_id()
+    }
+    pub fn ergo_box(&self) -> &ErgoBox {
+        &self.inner
+    }
+}
+
+impl TryFrom<&ErgoBox> for ReceiptBoxSpec {
+    type Error = Error;
+
+    fn try_from(value: &ErgoBox) -> Result<Self, Self::Error> {
+        let history = value
+            .get_register(NonMandatoryRegisterId::R4.into())?
+            .ok_or_else(|| Error::FieldNotSet("avl tree"))
+            .and_then(|reg| {
+                if reg.tpe == SType::SAvlTree {
+                    Ok(reg.v.try_extract_into::<AvlTreeData>().unwrap())
+                } else {
+                    Err(Error::InvalidType {
+                        field: "avl tree".to_owned(),
+                        tpe: reg.tpe,
+                    })
+                }
+            })?;
+        let position = value
+            .get_register(NonMandatoryRegisterId::R5.into())?
+            .ok_or_else(|| Error::FieldNotSet("position"))
+            .and_then(|reg| {
+                if reg.tpe == SType::SLong {
+                    Ok(reg.v.try_extract_into::<i64>().unwrap())
+                } else {
+                    Err(Error::InvalidType {
+                        field: "position".to_owned(),
+                        tpe: reg.tpe,
+                    })
+                }
+            })?;
+        let height = value
+            .get_register(NonMandatoryRegisterId::R6.into())?
+            .ok_or_else(|| Error::FieldNotSet("height"))
+            .and_then(|reg| {
+                if reg.tpe == SType::SInt {
+                    Ok(reg.v.try_extract_into::<i32>().unwrap())
+                } else {
+                    Err(Error::InvalidType {
+                        field: "height".to_owned(),
+                        tpe: reg.tpe,
+                    })
+                }
+            })?;
+        let reserve_owner = value
+            .get_register(NonMandatoryRegisterId::R7.into())?
+            .ok_or_else(|| Error::FieldNotSet("reserve owner"))
+            .and_then(|reg| {
+                if reg.tpe == SType::SGroupElement {
+                    Ok(reg.v.try_extract_into::<EcPoint>().unwrap())
+                } else {
+                    Err(Error::InvalidType {
+                        field: "reserve owner".to_owned(),
+                        tpe: reg.tpe,
+                    })
+                }
+            })?;
+        let Token { token_id, amount } = value
+            .tokens
+            .as_ref()
+            .ok_or_else(|| Error::FieldNotSet("receipt box missing note token"))?
+            .get(0)
+            .ok_or_else(|| Error::FieldNotSet("token at index 0 missing, no note token"))?
+            .clone();
+
+        Ok(Self {
+            note_id: token_id,
+            amount,
+            history,
+            position,
+            height,
+            reserve_owner,
+            inner: value.clone(),
+        })
+    }
+}
+
+/// Receipt box with the note history committed to in its R4 register
+pub struct Receipt {
+    pub receipt_box: ReceiptBoxSpec,
+    pub history: NoteHistory,
+}
+
+impl Receipt {
+    pub fn new(receipt_box: ReceiptBoxSpec, history: NoteHistory) -> Result<Self, Error> {
+        if receipt_box.history.digest != history.digest() {
+            return Err(Error::InvalidAVLDigest {
+                box_digest: receipt_box.history.digest,
+                history_digest: history.digest(),
+            });
+        }
+        Ok(Receipt {
+            receipt_box,
+            history,
+        })
+    }
+    pub fn ergo_box(&self) -> &ErgoBox {
+        self.receipt_box.ergo_box()
+    }
+}
@@ -0,0 +1,379 @@
+//! Portable note bundles, used to hand a note and its history to another server without it rescanning the chain
+//!
+//! Binary format (version 1), all integers big-endian:
+//! ```text
+//! magic "CCNB" | version: u8
+//! note box length: u32 | note box (sigma-serialized ErgoBox)
+//! entry count: u32 | entries: (reserve id: 32 bytes, amount: u64, signature: 65 bytes, signer: 33 bytes)*
+//! reserve count: u32 | reserves: (reserve box length: u32, reserve box (sigma-serialized ErgoBox))*
+//! ```
+
+use ergo_lib::{
+    ergo_chain_types::{Base16DecodedBytes, Base16EncodedBytes, EcPoint},
+    ergotree_ir::{
+        chain::{ergo_box::ErgoBox, token::TokenId},
+        serialization::{SigmaParsingError, SigmaSerializable},
+    },
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    boxes::{self, Note, ReserveBoxSpec},
+    note_history::{NoteHistory, NoteHistoryError, OwnershipEntry, Signature},
+};
+
+const MAGIC: &[u8; 4] = b"CCNB";
+/// Current version of bundle format
+pub const BUNDLE_VERSION: u8 = 1;
+
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("Not a note bundle, magic bytes missing")]
+    InvalidMagic,
+    #[error("Unsupported note bundle version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Note bundle ended unexpectedly")]
+    UnexpectedEnd,
+    #[error("Failed to parse box or key: {0}")]
+    Parsing(#[from] SigmaParsingError),
+    #[error("Failed to parse signature: {0}")]
+    InvalidSignature(String),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Box error: {0}")]
+    Box(#[from] boxes::Error),
+    #[error("Note history error: {0}")]
+    NoteHistory(#[from] NoteHistoryError),
+    #[error("Reserve {0:?} referenced by note history is missing from bundle")]
+    MissingReserve(TokenId),
+    #[error("Ownership entry at position {position} is signed by an agent other than the owner of reserve {reserve_id:?}")]
+    ReserveOwnerMismatch {
+        position: usize,
+        reserve_id: TokenId,
+    },
+    #[error("Note bundle has {0} unexpected trailing bytes")]
+    TrailingBytes(usize),
+}
+
+/// Note box together with its ownership entries and the reserve boxes they reference
+#[derive(Clone)]
+pub struct NoteBundle {
+    pub note_box: ErgoBox,
+    pub ownership_entries: Vec<OwnershipEntry>,
+    pub reserve_boxes: Vec<ErgoBox>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonOwnershipEntry {
+    reserve_id: TokenId,
+    amount: u64,
+    /// Hex encoded serialized signature
+    signature: String,
+    signer: EcPoint,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonNoteBundle {
+    version: u8,
+    note_box: ErgoBox,
+    ownership_entries: Vec<JsonOwnershipEntry>,
+    reserve_boxes: Vec<ErgoBox>,
+}
+
+impl NoteBundle {
+    pub fn new(note: &Note, reserves: &[ReserveBoxSpec]) -> Self {
+        NoteBundle {
+            note_box: note.ergo_box().clone(),
+            ownership_entries: note.history.ownership_entries().to_vec(),
+            reserve_boxes: reserves
+                .iter()
+                .map(|reserve| reserve.ergo_box().clone())
+                .collect(),
+        }
+    }
+
+    /// Rebuild note from bundle. Checks history against note box (digest in R4 and length in R6), signatures of all
+    /// ownership entries and that every reserve referenced by history is included in the bundle and owned by the entry's signer.
+    /// Boxes are not checked against the chain
+    pub fn into_note(self) -> Result<(Note, Vec<ReserveBoxSpec>), BundleError> {
+        let reserves = self
+            .reserve_boxes
+            .iter()
+            .map(ReserveBoxSpec::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        for (position, entry) in self.ownership_entries.iter().enumerate() {
+            let reserve = reserves
+                .iter()
+                .find(|reserve| reserve.identifier == entry.reserve_id)
+                .ok_or(BundleError::MissingReserve(entry.reserve_id))?;
+            if reserve.owner != entry.signer {
+                return Err(BundleError::ReserveOwnerMismatch {
+                    position,
+                    reserve_id: entry.reserve_id,
+                });
+            }
+        }
+        let history = NoteHistory::from_entries(self.ownership_entries);
+        let note = Note::new(self.note_box, history)?;
+        note.history.verify(note.note_id)?;
+        Ok((note, reserves))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(MAGIC);
+        buf.push(BUNDLE_VERSION);
+        // Unwraps are safe since serializing ErgoBox/EcPoint to a Vec can't fail
+        write_bytes(&mut buf, &self.note_box.sigma_serialize_bytes().unwrap());
+        buf.extend_from_slice(&(self.ownership_entries.len() as u32).to_be_bytes());
+        for entry in &self.ownership_entries {
+            buf.extend_from_slice(&entry.reserve_id.sigma_serialize_bytes().unwrap());
+            buf.extend_from_slice(&entry.amount.to_be_bytes());
+            buf.extend_from_slice(&entry.signature.serialize());
+            buf.extend_from_slice(&entry.signer.sigma_serialize_bytes().unwrap());
+        }
+        buf.extend_from_slice(&(self.reserve_boxes.len() as u32).to_be_bytes());
+        for reserve_box in &self.reserve_boxes {
+            write_bytes(&mut buf, &reserve_box.sigma_serialize_bytes().unwrap());
+        }
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BundleError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(BundleError::InvalidMagic);
+        }
+        let version = reader.take(1)?[0];
+        if version != BUNDLE_VERSION {
+            return Err(BundleError::UnsupportedVersion(version));
+        }
+        let note_box = ErgoBox::sigma_parse_bytes(reader.take_sized()?)?;
+        let entry_count = reader.read_u32()?;
+        let ownership_entries = (0..entry_count)
+            .map(|_| {
+                let reserve_id = TokenId::sigma_parse_bytes(reader.take(32)?)?;
+                let amount = u64::from_be_bytes(reader.take(8)?.try_into().unwrap());
+                let signature = Signature::try_from(reader.take(Signature::SERIALIZED_SIZE)?)
+                    .map_err(BundleError::InvalidSignature)?;
+                let signer = EcPoint::sigma_parse_bytes(reader.take(EcPoint::GROUP_SIZE)?)?;
+                Ok(OwnershipEntry {
+                    reserve_id,
+                    amount,
+                    signature,
+                    signer,
+                })
+            })
+            .collect::<Result<Vec<_>, BundleError>>()?;
+        let reserve_count = reader.read_u32()?;
+        let reserve_boxes = (0..reserve_count)
+            .map(|_| Ok(ErgoBox::sigma_parse_bytes(reader.take_sized()?)?))
+            .collect::<Result<Vec<_>, BundleError>>()?;
+        if !reader.0.is_empty() {
+            return Err(BundleError::TrailingBytes(reader.0.len()));
+        }
+        Ok(NoteBundle {
+            note_box,
+            ownership_entries,
+            reserve_boxes,
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let bundle = JsonNoteBundle {
+            version: BUNDLE_VERSION,
+            note_box: self.note_box.clone(),
+            ownership_entries: self
+                .ownership_entries
+                .iter()
+                .map(|entry| JsonOwnershipEntry {
+                    reserve_id: entry.reserve_id,
+                    amount: entry.amount,
+                    signature: Base16EncodedBytes::new(&entry.signature.serialize()).into(),
+                    signer: entry.signer.clone(),
+                })
+                .collect(),
+            reserve_boxes: self.reserve_boxes.clone(),
+        };
+        // Unwrap is safe since all fields serialize to JSON
+        serde_json::to_value(bundle).unwrap()
+    }
+
+    pub fn from_json(value: serde_json::Value) -> Result<Self, BundleError> {
+        let bundle: JsonNoteBundle = serde_json::from_value(value)?;
+        if bundle.version != BUNDLE_VERSION {
+            return Err(BundleError::UnsupportedVersion(bundle.version));
+        }
+        let ownership_entries = bundle
+            .ownership_entries
+            .into_iter()
+            .map(|entry| {
+                let signature_bytes = Base16DecodedBytes::try_from(entry.signature)
+                    .map_err(|e| BundleError::InvalidSignature(e.to_string()))?;
+                Ok(OwnershipEntry {
+                    reserve_id: entry.reserve_id,
+                    amount: entry.amount,
+                    signature: Signature::try_from(&signature_bytes.0[..])
+                        .map_err(BundleError::InvalidSignature)?,
+                    signer: entry.signer,
+                })
+            })
+            .collect::<Result<Vec<_>, BundleError>>()?;
+        Ok(NoteBundle {
+            note_box: bundle.note_box,
+            ownership_entries,
+            reserve_boxes: bundle.reserve_boxes,
+        })
+    }
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BundleError> {
+        if self.0.len() < len {
+            return Err(BundleError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+    fn read_u32(&mut self) -> Result<u32, BundleError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    // Read length-prefixed bytes
+    fn take_sized(&mut self) -> Result<&'a [u8], BundleError> {
+        let len = self.read_u32()?;
+        self.take(len as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergo_lib::ergotree_ir::chain::address::{Address, NetworkAddress, NetworkPrefix};
+    use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+
+    use crate::{
+        test_util::{
+            create_note, create_reserve, create_reserve_with_identifier, create_wallet_box,
+        },
+        transactions::{
+            box_selection::SimpleSelector,
+            notes::{spend_note_transaction, NoteOutput, SpendNoteResponse},
+            TxContext,
+        },
+    };
+
+    use super::{BundleError, NoteBundle};
+
+    fn bundle() -> NoteBundle {
+        let private_key = DlogProverInput::random();
+        let public_key = *private_key.public_image().h;
+        let note = create_note(&public_key, 1000);
+        let reserve = create_reserve(public_key.clone(), 1_000_000_000);
+        let SpendNoteResponse { recipient_note, .. } = spend_note_transaction(
+            &note,
+            &reserve,
+            private_key.w.clone(),
+            &NoteOutput {
+                recipient_pubkey: *DlogProverInput::random().public_image().h,
+                amount: 1000,
+            },
+            None,
+            vec![create_wallet_box(public_key, 1_000_000_000)],
+            &TxContext {
+                current_height: 0,
+                change_address: NetworkAddress::new(
+                    NetworkPrefix::Mainnet,
+                    &Address::P2Pk(private_key.public_image()),
+                )
+                .to_base58(),
+                fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+                box_selector: Arc::new(SimpleSelector),
+            },
+        )
+        .unwrap();
+        NoteBundle::new(&recipient_note, &[reserve])
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let bundle = bundle();
+        let (note, _) = NoteBundle::from_bytes(&bundle.to_bytes())
+            .unwrap()
+            .into_note()
+            .unwrap();
+        assert_eq!(note.ergo_box(), &bundle.note_box);
+        assert_eq!(note.history.ownership_entries().len(), 1);
+        let (note, reserves) = NoteBundle::from_json(bundle.to_json())
+            .unwrap()
+            .into_note()
+            .unwrap();
+        assert_eq!(note.ergo_box(), &bundle.note_box);
+        assert_eq!(reserves[0].ergo_box(), &bundle.reserve_boxes[0]);
+    }
+
+    #[test]
+    fn test_bundle_rejects_invalid() {
+        let bundle = bundle();
+        let mut bytes = bundle.to_bytes();
+        bytes[4] = 2;
+        assert!(matches!(
+            NoteBundle::from_bytes(&bytes),
+            Err(BundleError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            NoteBundle::from_bytes(&bundle.to_bytes()[..20]),
+            Err(BundleError::UnexpectedEnd)
+        ));
+        let mut trailing = bundle.to_bytes();
+        trailing.extend_from_slice(&[0, 0]);
+        assert!(matches!(
+            NoteBundle::from_bytes(&trailing),
+            Err(BundleError::TrailingBytes(2))
+        ));
+
+        let mut other_reserve = bundle.clone();
+        other_reserve.reserve_boxes = vec![create_reserve_with_identifier(
+            *DlogProverInput::random().public_image().h,
+            1_000_000_000,
+            bundle.ownership_entries[0].reserve_id,
+        )
+        .ergo_box()
+        .clone()];
+        assert!(matches!(
+            other_reserve.into_note(),
+            Err(BundleError::ReserveOwnerMismatch { position: 0, .. })
+        ));
+
+        let mut missing_reserve = bundle.clone();
+        missing_reserve.reserve_boxes.clear();
+        assert!(matches!(
+            missing_reserve.into_note(),
+            Err(BundleError::MissingReserve(_))
+        ));
+
+        let mut forged = bundle.clone();
+        forged.ownership_entries[0].signer = *DlogProverInput::random().public_image().h;
+        assert!(matches!(
+            forged.into_note(),
+            Err(BundleError::NoteHistory(_))
+        ));
+
+        let mut truncated_history = bundle;
+        truncated_history.ownership_entries.clear();
+        assert!(matches!(
+            truncated_history.into_note(),
+            Err(BundleError::Box(_))
+        ));
+    }
+}
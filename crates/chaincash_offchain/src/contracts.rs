@@ -1,18 +1,383 @@
-use ergo_lib::ergoscript_compiler::compiler::compile;
-use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
-use once_cell::sync::Lazy;
+use crate::network;
+use crate::oracle::{BUYBACK_NFT, GOLD_ORACLE_NFT};
+use ergo_lib::ergo_chain_types::{
+    blake2b256_hash, Base16DecodedBytes, Base16EncodedBytes, Digest32,
+};
+use ergo_lib::ergotree_ir::ergo_tree::{ErgoTree, ErgoTreeError};
+use ergo_lib::ergotree_ir::mir::constant::Constant;
+use ergo_lib::ergotree_ir::serialization::{
+    SigmaParsingError, SigmaSerializable, SigmaSerializationError,
+};
+use thiserror::Error;
 
-pub static RESERVE_CONTRACT: &str =
+pub const RESERVE_CONTRACT: &str =
     include_str!("../../../contracts/chaincash/contracts/onchain/reserve.es");
-// Not currently able to compile with sigma-rust, using node api instead
-pub static RESERVE_ERGO_TREE: Lazy<ErgoTree> =
-    Lazy::new(|| compile(RESERVE_CONTRACT, Default::default()).unwrap());
 
-pub static RECEIPT_CONTRACT: &str =
+pub const RECEIPT_CONTRACT: &str =
     include_str!("../../../contracts/chaincash/contracts/onchain/receipt.es");
 
-pub static NOTE_CONTRACT: &str =
+pub const NOTE_CONTRACT: &str =
     include_str!("../../../contracts/chaincash/contracts/onchain/note.es");
-// Not currently able to compile with sigma-rust, using node api instead
-pub static NOTE_ERGO_TREE: Lazy<ErgoTree> =
-    Lazy::new(|| compile(NOTE_CONTRACT, Default::default()).unwrap());
+
+// sigma-rust is not currently able to compile the contracts, so trees compiled by the node for mainnet are checked in
+// and template parameters are substituted into their segregated constants
+/// Reserve contract compiled with mainnet oracle and buyback NFTs
+pub const RESERVE_ERGO_TREE_HEX: &str = include_str!("../contracts/reserve.hex");
+/// Receipt contract compiled with mainnet reserve contract hash
+pub const RECEIPT_ERGO_TREE_HEX: &str = include_str!("../contracts/receipt.hex");
+/// Note contract compiled with mainnet reserve and receipt contract hashes
+pub const NOTE_ERGO_TREE_HEX: &str = include_str!("../contracts/note.hex");
+
+/// Expected hashes of precompiled contracts, see [contract_hash]
+pub const RESERVE_CONTRACT_HASH: &str =
+    "e0ef5f2e7cb56ad7b0a00d5374c42a71b6e17ff78545a98c99e2ce7ea743fccc";
+pub const RECEIPT_CONTRACT_HASH: &str =
+    "c76929ea33f9a0030b667e9589b09a754174fe6c6856c7b70e246135f13fbb29";
+pub const NOTE_CONTRACT_HASH: &str =
+    "5181f7e2ab875fea4b70a3c84eb5084fc53aec93b383825432d201e8e0404550";
+
+pub static RESERVE_TEMPLATE: ContractTemplate = ContractTemplate {
+    name: "reserve",
+    source: RESERVE_CONTRACT,
+    ergo_tree_hex: RESERVE_ERGO_TREE_HEX,
+    hash: RESERVE_CONTRACT_HASH,
+    parameters: &[
+        (TemplateParameter::OracleNft, 11),
+        (TemplateParameter::BuybackNft, 17),
+    ],
+};
+
+pub static RECEIPT_TEMPLATE: ContractTemplate = ContractTemplate {
+    name: "receipt",
+    source: RECEIPT_CONTRACT,
+    ergo_tree_hex: RECEIPT_ERGO_TREE_HEX,
+    hash: RECEIPT_CONTRACT_HASH,
+    parameters: &[(TemplateParameter::ReserveContractHash, 4)],
+};
+
+pub static NOTE_TEMPLATE: ContractTemplate = ContractTemplate {
+    name: "note",
+    source: NOTE_CONTRACT,
+    ergo_tree_hex: NOTE_ERGO_TREE_HEX,
+    hash: NOTE_CONTRACT_HASH,
+    parameters: &[
+        (TemplateParameter::ReserveContractHash, 9),
+        (TemplateParameter::ReceiptContractHash, 11),
+    ],
+};
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("Precompiled {contract} contract is not valid hex: {reason}")]
+    InvalidHex {
+        contract: &'static str,
+        reason: String,
+    },
+    #[error("Failed to parse precompiled {contract} contract: {source}")]
+    Parsing {
+        contract: &'static str,
+        source: SigmaParsingError,
+    },
+    #[error("Precompiled {contract} contract has hash {found}, expected {expected}")]
+    HashMismatch {
+        contract: &'static str,
+        expected: &'static str,
+        found: String,
+    },
+    #[error("Parameter {parameter:?} of {contract} contract was not supplied")]
+    MissingParameter {
+        contract: &'static str,
+        parameter: TemplateParameter,
+    },
+    #[error("Parameter {parameter:?} is not used by {contract} contract")]
+    UnusedParameter {
+        contract: &'static str,
+        parameter: TemplateParameter,
+    },
+    #[error("Parameter {parameter:?} of {contract} contract was supplied more than once")]
+    DuplicateParameter {
+        contract: &'static str,
+        parameter: TemplateParameter,
+    },
+    #[error("Failed to substitute constant in {contract} contract: {source}")]
+    Constant {
+        contract: &'static str,
+        source: ErgoTreeError,
+    },
+    #[error("Failed to serialize contract: {0}")]
+    Serialization(#[from] SigmaSerializationError),
+}
+
+/// Hash of contract tree without its header byte, which is how contracts refer to each other
+pub fn contract_hash(tree: &ErgoTree) -> Result<Digest32, ContractError> {
+    let bytes = tree.sigma_serialize_bytes()?;
+    Ok(blake2b256_hash(&bytes[1..]))
+}
+
+/// Value contracts are specialized with. All parameters are 32 byte ids or hashes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TemplateParameter {
+    /// NFT of gold oracle pool box
+    OracleNft,
+    /// NFT of buyback box
+    BuybackNft,
+    /// Hash of reserve contract
+    ReserveContractHash,
+    /// Hash of receipt contract
+    ReceiptContractHash,
+}
+
+impl TemplateParameter {
+    /// Text replaced by the parameter in ErgoScript source
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            TemplateParameter::OracleNft => GOLD_ORACLE_NFT,
+            TemplateParameter::BuybackNft => BUYBACK_NFT,
+            TemplateParameter::ReserveContractHash => "$reserveContractHash",
+            TemplateParameter::ReceiptContractHash => "$receiptContractHash",
+        }
+    }
+}
+
+/// Precompiled contract and the parameters it must be specialized with
+pub struct ContractTemplate {
+    pub name: &'static str,
+    /// ErgoScript source contract was compiled from
+    pub source: &'static str,
+    ergo_tree_hex: &'static str,
+    hash: &'static str,
+    /// Parameters and indices of the segregated constants they are substituted into
+    parameters: &'static [(TemplateParameter, usize)],
+}
+
+impl ContractTemplate {
+    pub fn parameters(&self) -> impl Iterator<Item = TemplateParameter> {
+        self.parameters.iter().map(|(parameter, _)| *parameter)
+    }
+
+    /// Index of segregated constant holding `parameter`
+    pub fn constant_index(&self, parameter: TemplateParameter) -> Option<usize> {
+        self.parameters
+            .iter()
+            .find(|(p, _)| *p == parameter)
+            .map(|(_, index)| *index)
+    }
+
+    /// Parse precompiled contract, checking it wasn't modified
+    pub fn precompiled(&self) -> Result<ErgoTree, ContractError> {
+        let contract = self.name;
+        let bytes = Base16DecodedBytes::try_from(self.ergo_tree_hex.trim().to_owned())
+            .map_err(|e| ContractError::InvalidHex {
+                contract,
+                reason: e.to_string(),
+            })?
+            .0;
+        let tree = ErgoTree::sigma_parse_bytes(&bytes)
+            .map_err(|source| ContractError::Parsing { contract, source })?;
+        let found: String = Base16EncodedBytes::new(&contract_hash(&tree)?.0).into();
+        if found != self.hash {
+            return Err(ContractError::HashMismatch {
+                contract,
+                expected: self.hash,
+                found,
+            });
+        }
+        Ok(tree)
+    }
+
+    /// Specialize contract with `values`. Every parameter of the template must be supplied exactly once and no others
+    pub fn instantiate(
+        &self,
+        values: &[(TemplateParameter, Digest32)],
+    ) -> Result<ErgoTree, ContractError> {
+        let contract = self.name;
+        for (i, (parameter, _)) in values.iter().enumerate() {
+            if self.constant_index(*parameter).is_none() {
+                return Err(ContractError::UnusedParameter {
+                    contract,
+                    parameter: *parameter,
+                });
+            }
+            if values[..i].iter().any(|(p, _)| p == parameter) {
+                return Err(ContractError::DuplicateParameter {
+                    contract,
+                    parameter: *parameter,
+                });
+            }
+        }
+        self.parameters
+            .iter()
+            .try_fold(self.precompiled()?, |tree, (parameter, index)| {
+                let (_, value) = values.iter().find(|(p, _)| p == parameter).ok_or(
+                    ContractError::MissingParameter {
+                        contract,
+                        parameter: *parameter,
+                    },
+                )?;
+                tree.with_constant(*index, Constant::from(value.0.to_vec()))
+                    .map_err(|source| ContractError::Constant { contract, source })
+            })
+    }
+}
+
+/// Reserve, receipt and note contracts for a network
+#[derive(Clone, Debug)]
+pub struct ContractTrees {
+    pub reserve: ErgoTree,
+    pub receipt: ErgoTree,
+    pub note: ErgoTree,
+}
+
+impl ContractTrees {
+    /// Derive contracts for `network` from precompiled contracts, without a node.
+    /// Receipt contract depends on hash of reserve contract and note contract on hashes of both
+    pub fn new(network: &network::Config) -> Result<Self, ContractError> {
+        let reserve = RESERVE_TEMPLATE.instantiate(&[
+            (TemplateParameter::OracleNft, network.oracle_nft.into()),
+            (TemplateParameter::BuybackNft, network.buyback_nft.into()),
+        ])?;
+        let reserve_hash = contract_hash(&reserve)?;
+        let receipt = RECEIPT_TEMPLATE
+            .instantiate(&[(TemplateParameter::ReserveContractHash, reserve_hash)])?;
+        let receipt_hash = contract_hash(&receipt)?;
+        let note = NOTE_TEMPLATE.instantiate(&[
+            (TemplateParameter::ReserveContractHash, reserve_hash),
+            (TemplateParameter::ReceiptContractHash, receipt_hash),
+        ])?;
+        Ok(Self {
+            reserve,
+            receipt,
+            note,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ergo_lib::ergo_chain_types::Digest32;
+    use ergo_lib::ergotree_ir::chain::address::{AddressEncoder, NetworkPrefix};
+    use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+    use ergo_lib::ergotree_ir::mir::constant::Constant;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+    use super::{
+        contract_hash, ContractError, ContractTemplate, ContractTrees, TemplateParameter,
+        NOTE_TEMPLATE, RECEIPT_TEMPLATE, RESERVE_TEMPLATE,
+    };
+    use crate::network;
+    use crate::test_util::{NOTE_ADDRESS, RECEIPT_ADDRESS, RESERVE_ADDRESS};
+
+    fn tree_bytes(address: &str) -> Vec<u8> {
+        AddressEncoder::new(NetworkPrefix::Mainnet)
+            .parse_address_from_str(address)
+            .unwrap()
+            .script()
+            .unwrap()
+            .sigma_serialize_bytes()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mainnet_contracts() {
+        let contracts = ContractTrees::new(&network::Config::mainnet()).unwrap();
+        assert_eq!(
+            contracts.reserve.sigma_serialize_bytes().unwrap(),
+            tree_bytes(RESERVE_ADDRESS)
+        );
+        assert_eq!(
+            contracts.receipt.sigma_serialize_bytes().unwrap(),
+            tree_bytes(RECEIPT_ADDRESS)
+        );
+        assert_eq!(
+            contracts.note.sigma_serialize_bytes().unwrap(),
+            tree_bytes(NOTE_ADDRESS)
+        );
+    }
+
+    #[test]
+    fn test_testnet_contracts() {
+        let mainnet = ContractTrees::new(&network::Config::mainnet()).unwrap();
+        let testnet = ContractTrees::new(&network::Config::testnet()).unwrap();
+        assert_ne!(testnet.reserve, mainnet.reserve);
+        // Receipt and note contracts only change through the reserve contract hash they refer to
+        assert_ne!(testnet.receipt, mainnet.receipt);
+        assert_ne!(testnet.note, mainnet.note);
+        let reserve_hash = Constant::from(contract_hash(&testnet.reserve).unwrap().0.to_vec());
+        let receipt_hash = Constant::from(contract_hash(&testnet.receipt).unwrap().0.to_vec());
+        let constant = |template: &ContractTemplate, tree: &ErgoTree, parameter| {
+            tree.get_constant(template.constant_index(parameter).unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            constant(
+                &RECEIPT_TEMPLATE,
+                &testnet.receipt,
+                TemplateParameter::ReserveContractHash
+            ),
+            Some(reserve_hash.clone())
+        );
+        assert_eq!(
+            constant(
+                &NOTE_TEMPLATE,
+                &testnet.note,
+                TemplateParameter::ReserveContractHash
+            ),
+            Some(reserve_hash)
+        );
+        assert_eq!(
+            constant(
+                &NOTE_TEMPLATE,
+                &testnet.note,
+                TemplateParameter::ReceiptContractHash
+            ),
+            Some(receipt_hash)
+        );
+    }
+
+    #[test]
+    fn test_template_parameters() {
+        let hash = Digest32::zero();
+        assert!(matches!(
+            RECEIPT_TEMPLATE.instantiate(&[]),
+            Err(ContractError::MissingParameter {
+                parameter: TemplateParameter::ReserveContractHash,
+                ..
+            })
+        ));
+        assert!(matches!(
+            RECEIPT_TEMPLATE.instantiate(&[
+                (TemplateParameter::ReserveContractHash, hash),
+                (TemplateParameter::ReceiptContractHash, hash)
+            ]),
+            Err(ContractError::UnusedParameter {
+                parameter: TemplateParameter::ReceiptContractHash,
+                ..
+            })
+        ));
+        assert!(matches!(
+            RECEIPT_TEMPLATE.instantiate(&[
+                (TemplateParameter::ReserveContractHash, hash),
+                (TemplateParameter::ReserveContractHash, hash)
+            ]),
+            Err(ContractError::DuplicateParameter { .. })
+        ));
+        assert!(RECEIPT_TEMPLATE
+            .instantiate(&[(TemplateParameter::ReserveContractHash, hash)])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_template_sources() {
+        // Placeholders must still exist in the ErgoScript sources the trees were compiled from
+        for template in [&RESERVE_TEMPLATE, &RECEIPT_TEMPLATE, &NOTE_TEMPLATE] {
+            for parameter in template.parameters() {
+                assert!(
+                    template.source.contains(parameter.placeholder()),
+                    "{:?} not found in {} contract source",
+                    parameter,
+                    template.name
+                );
+            }
+        }
+    }
+}
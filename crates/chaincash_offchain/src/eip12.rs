@@ -0,0 +1,247 @@
+//! Unsigned transactions in the format used by EIP-12 wallet connectors, so transactions can be signed by an external wallet
+use std::collections::BTreeMap;
+
+use ergo_lib::{
+    chain::transaction::{ergo_transaction::ErgoTransaction, unsigned::UnsignedTransaction},
+    ergo_chain_types::Base16EncodedBytes,
+    ergotree_interpreter::sigma_protocol::prover::ContextExtension,
+    ergotree_ir::{
+        chain::{
+            ergo_box::{BoxId, ErgoBox, NonMandatoryRegisterId, RegisterValueError},
+            token::Token,
+        },
+        serialization::{SigmaSerializable, SigmaSerializationError},
+    },
+};
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Eip12Error {
+    #[error("Box {0} spent or read by transaction was not provided")]
+    MissingBox(BoxId),
+    #[error("Failed to access register: {0}")]
+    BadRegister(#[from] RegisterValueError),
+    #[error("Failed to serialize box: {0}")]
+    Serialization(#[from] SigmaSerializationError),
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip12Asset {
+    pub token_id: String,
+    pub amount: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip12Box {
+    pub box_id: String,
+    pub transaction_id: String,
+    pub index: u16,
+    pub value: String,
+    pub ergo_tree: String,
+    pub creation_height: u32,
+    pub assets: Vec<Eip12Asset>,
+    /// Serialized register constants keyed by register name (R4-R9)
+    pub additional_registers: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip12Input {
+    #[serde(flatten)]
+    pub ergo_box: Eip12Box,
+    /// Context extension variables of input, keyed by variable id
+    pub extension: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip12Output {
+    pub value: String,
+    pub ergo_tree: String,
+    pub creation_height: u32,
+    pub assets: Vec<Eip12Asset>,
+    pub additional_registers: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip12UnsignedTransaction {
+    /// ID of transaction. Signing does not change the ID, so this can be used to match the signed transaction
+    pub id: String,
+    pub inputs: Vec<Eip12Input>,
+    pub data_inputs: Vec<Eip12Box>,
+    pub outputs: Vec<Eip12Output>,
+}
+
+impl Eip12UnsignedTransaction {
+    /// Convert `transaction` to EIP-12 format. `boxes` must contain every box spent or used as a data input by transaction
+    pub fn new(transaction: &UnsignedTransaction, boxes: &[ErgoBox]) -> Result<Self, Eip12Error> {
+        let find_box = |box_id: BoxId| {
+            boxes
+                .iter()
+                .find(|b| b.box_id() == box_id)
+                .ok_or(Eip12Error::MissingBox(box_id))
+        };
+        let inputs = transaction
+            .inputs
+            .iter()
+            .map(|input| {
+                Ok(Eip12Input {
+                    ergo_box: eip12_box(find_box(input.box_id)?)?,
+                    extension: extension(&input.extension)?,
+                })
+            })
+            .collect::<Result<_, Eip12Error>>()?;
+        let data_inputs = transaction
+            .data_inputs
+            .iter()
+            .flat_map(|data_inputs| data_inputs.iter())
+            .map(|data_input| eip12_box(find_box(data_input.box_id)?))
+            .collect::<Result<_, _>>()?;
+        let outputs = transaction
+            .outputs()
+            .iter()
+            .map(|output| {
+                Ok(Eip12Output {
+                    value: output.value.as_u64().to_string(),
+                    ergo_tree: hex(output.ergo_tree.sigma_serialize_bytes()?),
+                    creation_height: output.creation_height,
+                    assets: assets(output),
+                    additional_registers: registers(output)?,
+                })
+            })
+            .collect::<Result<_, Eip12Error>>()?;
+        Ok(Self {
+            id: transaction.id().to_string(),
+            inputs,
+            data_inputs,
+            outputs,
+        })
+    }
+}
+
+fn hex(bytes: Vec<u8>) -> String {
+    Base16EncodedBytes::new(&bytes).into()
+}
+
+fn eip12_box(ergo_box: &ErgoBox) -> Result<Eip12Box, Eip12Error> {
+    Ok(Eip12Box {
+        box_id: ergo_box.box_id().to_string(),
+        transaction_id: ergo_box.transaction_id.to_string(),
+        index: ergo_box.index,
+        value: ergo_box.value.as_u64().to_string(),
+        ergo_tree: hex(ergo_box.ergo_tree.sigma_serialize_bytes()?),
+        creation_height: ergo_box.creation_height,
+        assets: assets(ergo_box),
+        additional_registers: registers(ergo_box)?,
+    })
+}
+
+fn assets(ergo_box: &ErgoBox) -> Vec<Eip12Asset> {
+    ergo_box
+        .tokens
+        .iter()
+        .flat_map(|tokens| tokens.iter())
+        .map(|Token { token_id, amount }| Eip12Asset {
+            token_id: String::from(*token_id),
+            amount: amount.as_u64().to_string(),
+        })
+        .collect()
+}
+
+fn registers(ergo_box: &ErgoBox) -> Result<BTreeMap<String, String>, Eip12Error> {
+    let mut registers = BTreeMap::new();
+    for (name, register_id) in [
+        ("R4", NonMandatoryRegisterId::R4),
+        ("R5", NonMandatoryRegisterId::R5),
+        ("R6", NonMandatoryRegisterId::R6),
+        ("R7", NonMandatoryRegisterId::R7),
+        ("R8", NonMandatoryRegisterId::R8),
+        ("R9", NonMandatoryRegisterId::R9),
+    ] {
+        if let Some(constant) = ergo_box.get_register(register_id.into())? {
+            registers.insert(name.to_owned(), hex(constant.sigma_serialize_bytes()?));
+        }
+    }
+    Ok(registers)
+}
+
+fn extension(extension: &ContextExtension) -> Result<BTreeMap<String, String>, Eip12Error> {
+    extension
+        .values
+        .iter()
+        .map(|(id, constant)| Ok((id.to_string(), hex(constant.sigma_serialize_bytes()?))))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergo_lib::ergotree_ir::chain::address::{Address, NetworkAddress, NetworkPrefix};
+    use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+
+    use crate::{
+        test_util::{create_reserve, create_wallet_box},
+        transactions::{
+            box_selection::SimpleSelector, reserves::top_up_reserve_transaction, TxContext,
+        },
+    };
+
+    use super::{Eip12Error, Eip12UnsignedTransaction};
+
+    #[test]
+    fn test_eip12_transaction() {
+        let public_key = *DlogProverInput::random().public_image().h;
+        let reserve = create_reserve(public_key.clone(), 1_000_000_000);
+        let wallet_box = create_wallet_box(public_key.clone(), 10_000_000_000);
+        let context = TxContext {
+            current_height: 1000,
+            change_address: NetworkAddress::new(
+                NetworkPrefix::Mainnet,
+                &Address::P2Pk(public_key.into()),
+            )
+            .to_base58(),
+            fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+            box_selector: Arc::new(SimpleSelector),
+        };
+        let response =
+            top_up_reserve_transaction(&reserve, vec![wallet_box.clone()], 1_000_000_000, &context)
+                .unwrap();
+
+        let boxes = [reserve.ergo_box().clone(), wallet_box.clone()];
+        let eip12 = Eip12UnsignedTransaction::new(&response.transaction, &boxes).unwrap();
+        assert_eq!(eip12.id, response.transaction.id().to_string());
+        assert_eq!(eip12.inputs.len(), response.transaction.inputs.len());
+        assert_eq!(
+            eip12.inputs[0].ergo_box.box_id,
+            reserve.box_id().to_string()
+        );
+        assert_eq!(
+            eip12.inputs[0]
+                .ergo_box
+                .additional_registers
+                .keys()
+                .collect::<Vec<_>>(),
+            ["R4"]
+        );
+        assert!(eip12.inputs[0].extension.contains_key("0"));
+        assert_eq!(
+            eip12.outputs.len(),
+            response.transaction.output_candidates.len()
+        );
+        assert_eq!(
+            eip12.outputs[0].value,
+            (reserve.ergo_box().value.as_u64() + 1_000_000_000).to_string()
+        );
+
+        assert!(matches!(
+            Eip12UnsignedTransaction::new(&response.transaction, &boxes[..1]),
+            Err(Eip12Error::MissingBox(box_id)) if box_id == wallet_box.box_id()
+        ));
+    }
+}
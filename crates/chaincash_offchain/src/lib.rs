@@ -1,5 +1,8 @@
 pub mod boxes;
+pub mod bundle;
 pub mod contracts;
+pub mod eip12;
+pub mod network;
 pub mod node;
 pub mod note_history;
 pub mod oracle;
@@ -0,0 +1,90 @@
+//! Network specific parameters: oracle deployment, scan start height and address prefix
+
+use ergo_lib::{
+    ergo_chain_types::Digest32,
+    ergotree_ir::chain::{address::NetworkPrefix, token::TokenId},
+};
+
+use crate::oracle::{BUYBACK_NFT, BUYBACK_NFT_TESTNET, GOLD_ORACLE_NFT, GOLD_ORACLE_NFT_TESTNET};
+
+/// Height wallet is rescanned from on mainnet, this can be increased later when chaincash is deployed
+pub const MAINNET_SCAN_START_HEIGHT: u32 = 1_318_639;
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressPrefix {
+    Mainnet,
+    Testnet,
+}
+
+impl From<AddressPrefix> for NetworkPrefix {
+    fn from(prefix: AddressPrefix) -> Self {
+        match prefix {
+            AddressPrefix::Mainnet => NetworkPrefix::Mainnet,
+            AddressPrefix::Testnet => NetworkPrefix::Testnet,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct Config {
+    /// NFT held by gold oracle pool box
+    pub oracle_nft: TokenId,
+    /// NFT held by buyback box that receives oracle fees on redemption
+    pub buyback_nft: TokenId,
+    /// Height wallet is rescanned from when chaincash scans are registered
+    pub scan_start_height: u32,
+    /// Network wallet addresses are expected to be on
+    pub address_prefix: AddressPrefix,
+}
+
+impl Config {
+    pub fn mainnet() -> Self {
+        Self {
+            oracle_nft: token_id(GOLD_ORACLE_NFT),
+            buyback_nft: token_id(BUYBACK_NFT),
+            scan_start_height: MAINNET_SCAN_START_HEIGHT,
+            address_prefix: AddressPrefix::Mainnet,
+        }
+    }
+
+    pub fn testnet() -> Self {
+        Self {
+            oracle_nft: token_id(GOLD_ORACLE_NFT_TESTNET),
+            buyback_nft: token_id(BUYBACK_NFT_TESTNET),
+            scan_start_height: 0,
+            address_prefix: AddressPrefix::Testnet,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+fn token_id(hex: &str) -> TokenId {
+    Digest32::try_from(hex.to_string()).unwrap().into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AddressPrefix, Config};
+
+    #[test]
+    fn test_deserialize_config() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "oracle_nft": "a7271cbaea40c8718ef568ebbda125b195207c597b7d53d14873f0b521d4f6d1",
+            "buyback_nft": "8fc353ac5bf8411b757180001e15fb87840a37ebd2e133fe70dce857fc526a19",
+            "scan_start_height": 0,
+            "address_prefix": "testnet"
+        }))
+        .unwrap();
+        let testnet = Config::testnet();
+        assert_eq!(config.oracle_nft, testnet.oracle_nft);
+        assert_eq!(config.buyback_nft, testnet.buyback_nft);
+        assert_eq!(config.address_prefix, AddressPrefix::Testnet);
+        assert_ne!(config.oracle_nft, Config::mainnet().oracle_nft);
+    }
+}
@@ -4,16 +4,25 @@ use ergo_client::node::NodeClient;
 
 pub use ergo_client::node::NodeError;
 
+/// Timeout of requests made to node
+pub const NODE_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(serde::Deserialize, Debug)]
 pub struct Config {
     url: String,
     api_key: String,
 }
 
+impl Config {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
 pub fn node_from_config(cfg: &Config) -> Result<NodeClient, NodeError> {
     Ok(NodeClient::from_url_str(
         &cfg.url,
         cfg.api_key.clone(),
-        Duration::from_secs(5),
+        NODE_TIMEOUT,
     )?)
 }
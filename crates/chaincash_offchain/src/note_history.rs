@@ -22,10 +22,17 @@ use ergo_lib::{
         serialization::{sigma_byte_writer::SigmaByteWriter, SigmaSerializable},
     },
 };
+use std::{
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, OnceLock},
+};
+
 use k256::{
-    elliptic_curve::{ops::Reduce, Field, PrimeField},
-    FieldBytes,
+    elliptic_curve::{ops::Reduce, Curve, Field, FieldBytesEncoding, PrimeField},
+    FieldBytes, Secp256k1,
 };
+use sha2::{Digest as _, Sha256};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -36,6 +43,10 @@ pub enum NoteHistoryError {
     DuplicateReserveKey,
     #[error("Couldn't restore ownership entry from ContextExtension")]
     InvalidContextExtension,
+    #[error("Invalid signature for ownership entry at position {0}")]
+    InvalidSignature(u64),
+    #[error("Ownership entries don't match digest of history")]
+    DigestMismatch,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -77,6 +88,13 @@ impl Signature {
         self.write_z_bytes(&mut buf);
         buf
     }
+
+    /// Verify signature of `message` against public key. Checks g^z = a * pk^e
+    pub fn verify(&self, message: &[u8], public_key: &EcPoint) -> bool {
+        let e = challenge(&self.a, message, public_key);
+        let g = generator();
+        exponentiate(&g, self.z.as_scalar_ref()) == self.a.clone() * &exponentiate(public_key, &e)
+    }
 }
 
 impl TryFrom<&[u8]> for Signature {
@@ -118,12 +136,24 @@ pub struct OwnershipEntry {
     pub reserve_id: TokenId,
     pub amount: u64,
     pub signature: Signature,
+    /// Owner of the note at this position in history, who signed the entry
+    pub signer: EcPoint,
 }
 
 impl OwnershipEntry {
+    /// Verify that entry at `position` in history of note `note_id` was signed by its signer
+    pub fn verify(&self, note_id: TokenId, position: u64) -> Result<(), NoteHistoryError> {
+        let message = signing_message(position, self.amount, note_id);
+        if self.signature.verify(&message, &self.signer) {
+            Ok(())
+        } else {
+            Err(NoteHistoryError::InvalidSignature(position))
+        }
+    }
     pub fn from_context_extension(
         note_amount: u64,
         reserve_id: TokenId,
+        signer: EcPoint,
         context_extension: &ContextExtension,
     ) -> Result<Self, NoteHistoryError> {
         let a = context_extension
@@ -147,35 +177,143 @@ impl OwnershipEntry {
             reserve_id,
             amount: note_amount,
             signature: Signature { a, z },
+            signer,
         })
     }
 }
 
+/// Maximum number of provers kept by prover thread
+const PROVER_CACHE_SIZE: usize = 64;
+
+/// Provers of recently used histories keyed by digest, least recently used first
+#[derive(Default)]
+struct ProverCache {
+    provers: VecDeque<(Digest<33>, BatchAVLProver)>,
+}
+
+impl ProverCache {
+    fn take(&mut self, digest: &Digest<33>) -> Option<BatchAVLProver> {
+        let i = self.provers.iter().position(|(d, _)| d == digest)?;
+        self.provers.remove(i).map(|(_, prover)| prover)
+    }
+
+    fn put(&mut self, digest: Digest<33>, prover: BatchAVLProver) {
+        // Replace a prover of the same history built by another caller
+        self.take(&digest);
+        if self.provers.len() >= PROVER_CACHE_SIZE {
+            self.provers.pop_front();
+        }
+        self.provers.push_back((digest, prover));
+    }
+}
+
+type ProverTask = Box<dyn FnOnce(&mut ProverCache) + Send>;
+
+// BatchAVLProver can't be sent between threads (tree nodes are Rc), so provers are owned by a single thread that runs operations
+// on behalf of histories used on any thread. Provers are kept between operations so adding commitments and generating proofs
+// doesn't rebuild the tree. Digest commits to every entry of the tree, so a history, its clones and the same history loaded
+// again share a prover
+fn prover_thread() -> &'static mpsc::Sender<ProverTask> {
+    static PROVER_THREAD: OnceLock<mpsc::Sender<ProverTask>> = OnceLock::new();
+    PROVER_THREAD.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<ProverTask>();
+        std::thread::Builder::new()
+            .name("note-history-prover".to_owned())
+            .spawn(move || {
+                let mut cache = ProverCache::default();
+                for task in receiver {
+                    // Provers may be left inconsistent by a panicking operation, drop all of them so they are rebuilt
+                    if panic::catch_unwind(AssertUnwindSafe(|| task(&mut cache))).is_err() {
+                        cache = ProverCache::default();
+                    }
+                }
+            })
+            .expect("Failed to spawn note history prover thread");
+        sender
+    })
+}
+
+// Run task on prover thread, blocking until it completes
+fn run_on_prover_thread<T: Send + 'static>(
+    task: impl FnOnce(&mut ProverCache) -> T + Send + 'static,
+) -> T {
+    let (result_sender, result_receiver) = mpsc::sync_channel(1);
+    prover_thread()
+        .send(Box::new(move |cache: &mut ProverCache| {
+            // Sending only fails if caller stopped waiting for result
+            let _ = result_sender.send(task(cache));
+        }))
+        .expect("Note history prover thread stopped");
+    result_receiver
+        .recv()
+        .expect("Note history prover operation panicked")
+}
+
 #[derive(Clone)]
 pub struct NoteHistory {
-    ownership_entries: Vec<OwnershipEntry>,
+    ownership_entries: Arc<Vec<OwnershipEntry>>,
+    // Computed on first use unless known when history is created, shared by clones so it's computed once
+    digest: Arc<OnceLock<Digest<33>>>,
 }
 
 impl NoteHistory {
     pub fn new() -> Self {
+        Self::from_entries(vec![])
+    }
+    /// History of ownership entries ordered by position. The tree isn't built until history is first used
+    pub fn from_entries(ownership_entries: impl IntoIterator<Item = OwnershipEntry>) -> Self {
         NoteHistory {
-            ownership_entries: vec![],
+            ownership_entries: Arc::new(ownership_entries.into_iter().collect()),
+            digest: Arc::default(),
+        }
+    }
+    /// History of ownership entries ordered by position whose tree has `digest`, as committed to by the box holding it.
+    /// Operations use a prover cached under `digest` as is, a tree that has to be built is checked against `digest` first,
+    /// failing with [`NoteHistoryError::DigestMismatch`]
+    pub fn from_entries_with_digest(
+        ownership_entries: impl IntoIterator<Item = OwnershipEntry>,
+        digest: Digest<33>,
+    ) -> Self {
+        NoteHistory {
+            ownership_entries: Arc::new(ownership_entries.into_iter().collect()),
+            digest: Arc::new(OnceLock::from(digest)),
         }
     }
     pub fn ownership_entries(&self) -> &[OwnershipEntry] {
         &self.ownership_entries
     }
-    // Return prover built from signatures. Since BatchAVLProver isn't thread-safe we have to rebuild it each time
-    // TODO: make BatchAVLProver thread-safe, then prover can be memoized, otherwise NoteHistory slows down significantly with more signatures
-    fn prover(&self) -> Result<BatchAVLProver, NoteHistoryError> {
-        build_prover(&self.ownership_entries)
+    // Run operation against cached prover of history, building it first if needed. Prover is cached again under its digest
+    // after the operation, so a history that was changed by it should be the one used next
+    fn with_prover<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut BatchAVLProver) -> Result<T, NoteHistoryError> + Send + 'static,
+    ) -> Result<T, NoteHistoryError> {
+        let digest = self.digest();
+        let ownership_entries = self.ownership_entries.clone();
+        run_on_prover_thread(move |cache| {
+            let mut prover = match cache.take(&digest) {
+                Some(prover) => prover,
+                None => {
+                    let prover = build_prover(ownership_entries.iter());
+                    if prover_digest(&prover) != digest {
+                        return Err(NoteHistoryError::DigestMismatch);
+                    }
+                    prover
+                }
+            };
+            let res = f(&mut prover);
+            // Failed operations may leave prover in an inconsistent state, drop it so it is rebuilt on next use
+            if res.is_ok() {
+                cache.put(prover_digest(&prover), prover);
+            }
+            res
+        })
     }
     /// Add a signature and generate insertion proof
     pub fn add_commitment(
         &mut self,
         commitment: OwnershipEntry,
     ) -> Result<SerializedAdProof, NoteHistoryError> {
-        let mut prover = self.prover()?;
         let key = [
             &(self.ownership_entries().len() as i64).to_be_bytes()[..],
             &commitment.reserve_id.sigma_serialize_bytes().unwrap()[..],
@@ -184,35 +322,57 @@ impl NoteHistory {
         .into();
         let value = commitment.signature.serialize().into();
         let insert_op = Operation::Insert(KeyValue { key, value });
-        prover
-            .perform_one_operation(&insert_op)
-            .map_err(|_| NoteHistoryError::DuplicateReserveKey)?;
-        self.ownership_entries.push(commitment);
-        Ok(prover.generate_proof())
+        let (proof, digest) = self.with_prover(move |prover| {
+            prover
+                .perform_one_operation(&insert_op)
+                .map_err(|_| NoteHistoryError::DuplicateReserveKey)?;
+            Ok((prover.generate_proof(), prover_digest(prover)))
+        })?;
+        Arc::make_mut(&mut self.ownership_entries).push(commitment);
+        self.digest = Arc::new(OnceLock::from(digest));
+        Ok(proof)
     }
+    /// Digest of the tree of ownership entries. If it wasn't known when history was created the tree is built, and kept for
+    /// later operations
     pub fn digest(&self) -> Digest<33> {
-        self.prover().unwrap().digest().unwrap()[..]
-            .try_into()
-            .unwrap()
+        *self.digest.get_or_init(|| {
+            let ownership_entries = self.ownership_entries.clone();
+            run_on_prover_thread(move |cache| {
+                let prover = build_prover(ownership_entries.iter());
+                let digest = prover_digest(&prover);
+                cache.put(digest, prover);
+                digest
+            })
+        })
+    }
+    /// Verify signatures of all ownership entries against their signers
+    pub fn verify(&self, note_id: TokenId) -> Result<(), NoteHistoryError> {
+        self.ownership_entries
+            .iter()
+            .enumerate()
+            .try_for_each(|(position, entry)| entry.verify(note_id, position as u64))
     }
     pub fn lookup_proof(
         &self,
         reserve_id: TokenId,
         position: i64,
     ) -> Result<SerializedAdProof, NoteHistoryError> {
-        let mut prover = self.prover()?;
         let key = [
             &(position).to_be_bytes()[..],
             &reserve_id.sigma_serialize_bytes().unwrap()[..],
         ]
         .concat()
         .into();
-        prover
-            .perform_one_operation(&Operation::Lookup(key))
-            .transpose()
-            .ok_or(NoteHistoryError::KeyNotFound)?
-            .map_err(|_| NoteHistoryError::KeyNotFound)?;
-        Ok(prover.generate_proof())
+        self.with_prover(move |prover| {
+            let found = prover.perform_one_operation(&Operation::Lookup(key));
+            // Generate proof even if lookup failed to reset prover's batch
+            let proof = prover.generate_proof();
+            found
+                .transpose()
+                .ok_or(NoteHistoryError::KeyNotFound)?
+                .map_err(|_| NoteHistoryError::KeyNotFound)?;
+            Ok(proof)
+        })
     }
     pub fn to_avltree(&self) -> AvlTreeData {
         let tree_flags = AvlTreeFlags::new(true, false, false);
@@ -225,9 +385,12 @@ impl NoteHistory {
     }
 }
 
-fn build_prover<'a>(
-    signatures: impl IntoIterator<Item = &'a OwnershipEntry>,
-) -> Result<BatchAVLProver, NoteHistoryError> {
+fn prover_digest(prover: &BatchAVLProver) -> Digest<33> {
+    prover.digest().unwrap()[..].try_into().unwrap()
+}
+
+// Keys start with position of entry, so they are unique and inserting them can't fail
+fn build_prover<'a>(signatures: impl IntoIterator<Item = &'a OwnershipEntry>) -> BatchAVLProver {
     let mut prover = BatchAVLProver::new(
         AVLTree::new(
             |digest| Node::LabelOnly(NodeHeader::new(Some(*digest), None)),
@@ -259,24 +422,26 @@ fn build_prover<'a>(
                 Operation::Insert(KeyValue { key, value })
             },
         )
-        .try_for_each(|op| {
+        .for_each(|op| {
             prover
                 .perform_one_operation(&op)
-                .map_err(|_| NoteHistoryError::DuplicateReserveKey)
-                .map(|_| ())
-        })?;
+                .expect("Duplicate key in note history");
+        });
     prover.generate_proof();
-    Ok(prover)
+    prover
 }
 
-pub(crate) fn sign(message: &[u8], private_key: Wscalar) -> Signature {
-    let rng = rand::thread_rng();
-    let private_key: k256::Scalar = private_key.into();
-    let g = generator();
+/// Message signed by owner of note at `position` in its history: position, note amount and note id
+pub fn signing_message(position: u64, amount: u64, note_id: TokenId) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(48);
+    buf.extend_from_slice(&position.to_be_bytes());
+    buf.extend_from_slice(&amount.to_be_bytes());
+    buf.extend_from_slice(&note_id.sigma_serialize_bytes().unwrap());
+    buf
+}
 
-    let public_key = exponentiate(&g, &private_key);
-    let r = k256::Scalar::random(rng);
-    let a = exponentiate(&g, &r);
+// Fiat-Shamir challenge e = H(a || message || pk)
+fn challenge(a: &EcPoint, message: &[u8], public_key: &EcPoint) -> k256::Scalar {
     let commitment = [
         &a.sigma_serialize_bytes().unwrap()[..],
         message,
@@ -284,13 +449,63 @@ pub(crate) fn sign(message: &[u8], private_key: Wscalar) -> Signature {
     ]
     .concat();
     let hash: FieldBytes = FieldBytes::clone_from_slice(&blake2b256_hash(&commitment).0);
-    let e = <k256::Scalar as Reduce<k256::U256>>::reduce_bytes(&hash);
+    <k256::Scalar as Reduce<k256::U256>>::reduce_bytes(&hash)
+}
+
+/// Source of the nonce `r` used when signing a note
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonceMode {
+    /// Derive nonce from private key and message as in RFC 6979 (HMAC-SHA256), signatures are reproducible and don't depend on a RNG
+    #[default]
+    Deterministic,
+    /// Draw nonce from thread-local RNG
+    Random,
+}
 
-    let z = r + e * private_key;
-    if z.shr_vartime(255) == k256::Scalar::ONE || e.shr_vartime(255) == k256::Scalar::ONE {
-        sign(message, Wscalar::from(private_key))
+// RFC 6979 nonce over private key and SHA-256 of message. `attempt` is passed as additional data so that a fresh nonce is derived when a signature has to be retried
+fn deterministic_nonce(private_key: &k256::Scalar, message: &[u8], attempt: u32) -> k256::Scalar {
+    let h = Sha256::digest(message);
+    let extra: &[u8] = if attempt == 0 {
+        &[]
     } else {
-        Signature { a, z: z.into() }
+        &attempt.to_be_bytes()
+    };
+    let k = rfc6979::generate_k::<Sha256, _>(
+        &private_key.to_bytes(),
+        &Secp256k1::ORDER.encode_field_bytes(),
+        &h,
+        extra,
+    );
+    // generate_k only returns values in [1, n)
+    Option::from(k256::Scalar::from_repr(k)).expect("RFC 6979 nonce out of range")
+}
+
+/// Sign `message` with a deterministic nonce, see [`NonceMode::Deterministic`]
+pub fn sign(message: &[u8], private_key: Wscalar) -> Signature {
+    sign_with_nonce_mode(message, private_key, NonceMode::Deterministic)
+}
+
+/// Sign `message`, drawing the nonce according to `mode`
+pub fn sign_with_nonce_mode(message: &[u8], private_key: Wscalar, mode: NonceMode) -> Signature {
+    let private_key: k256::Scalar = private_key.into();
+    let g = generator();
+    let public_key = exponentiate(&g, &private_key);
+
+    // z and e are serialized as 32 byte unsigned values with high bit unset, retry with a fresh nonce until that holds
+    let mut attempt = 0;
+    loop {
+        let r = match mode {
+            NonceMode::Deterministic => deterministic_nonce(&private_key, message, attempt),
+            NonceMode::Random => k256::Scalar::random(rand::thread_rng()),
+        };
+        let a = exponentiate(&g, &r);
+        let e = challenge(&a, message, &public_key);
+
+        let z = r + e * private_key;
+        if z.shr_vartime(255) != k256::Scalar::ONE && e.shr_vartime(255) != k256::Scalar::ONE {
+            return Signature { a, z: z.into() };
+        }
+        attempt += 1;
     }
 }
 
@@ -300,12 +515,19 @@ mod test {
     use ergo_avltree_rust::batch_avl_verifier::BatchAVLVerifier;
     use ergo_avltree_rust::batch_node::{AVLTree, Node, NodeHeader};
     use ergo_avltree_rust::operation::{KeyValue, Operation};
+    use ergo_lib::ergo_chain_types::{Base16DecodedBytes, Digest, Digest32, EcPoint};
+    use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergo_lib::ergotree_ir::chain::token::TokenId;
     use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
     use proptest::arbitrary::any;
     use proptest::collection::vec;
     use proptest::proptest;
 
-    use crate::note_history::{build_prover, NoteHistory, OwnershipEntry, Signature};
+    use crate::note_history::{
+        build_prover, sign, sign_with_nonce_mode, signing_message, NonceMode, NoteHistory,
+        NoteHistoryError, OwnershipEntry, Signature,
+    };
+    use crate::test_util::force_any_val;
     proptest! {
         #[test]
         fn test_signature_roundtrip(signature in any::<Signature>()) {
@@ -313,8 +535,34 @@ mod test {
             assert_eq!(Signature::try_from(&serialized[..]).unwrap(), signature);
         }
         #[test]
+        fn test_sign_verify(message in vec(any::<u8>(), 0..100)) {
+            let private_key = DlogProverInput::random();
+            let public_key = *private_key.public_image().h;
+            let signature = sign(&message, private_key.w.clone());
+            assert!(signature.verify(&message, &public_key));
+            assert!(!signature.verify(&[&message[..], &[0]].concat(), &public_key));
+            assert!(!signature.verify(&message, &DlogProverInput::random().public_image().h));
+        }
+        #[test]
+        fn test_deterministic_nonce(message in vec(any::<u8>(), 0..100)) {
+            let private_key = DlogProverInput::random();
+            let signature = sign(&message, private_key.w.clone());
+            assert_eq!(sign(&message, private_key.w.clone()), signature);
+            assert_ne!(sign(&[&message[..], &[0]].concat(), private_key.w.clone()), signature);
+        }
+        #[test]
+        fn test_random_nonce(message in vec(any::<u8>(), 0..100)) {
+            let private_key = DlogProverInput::random();
+            let public_key = *private_key.public_image().h;
+            let signature = sign_with_nonce_mode(&message, private_key.w.clone(), NonceMode::Deterministic);
+            assert_eq!(sign(&message, private_key.w.clone()), signature);
+            let random_signature = sign_with_nonce_mode(&message, private_key.w.clone(), NonceMode::Random);
+            assert!(random_signature.verify(&message, &public_key));
+            assert_ne!(random_signature, signature);
+        }
+        #[test]
         fn test_prover_verifier(commitments in vec(any::<OwnershipEntry>(), 0..50)) {
-            let prover = build_prover(commitments.iter()).unwrap();
+            let prover = build_prover(commitments.iter());
             let mut note_history = NoteHistory::new();
             for (i, commitment) in commitments.into_iter().enumerate() {
                 let digest = note_history.digest();
@@ -334,6 +582,145 @@ mod test {
                 bv.perform_one_operation(&Operation::Insert(KeyValue { key: [(i as i64).to_be_bytes().as_slice(), commitment.reserve_id.sigma_serialize_bytes().unwrap().as_slice()].concat().into(), value: commitment.signature.serialize().into() })).unwrap();
             }
             assert_eq!(&note_history.digest().0[..], &prover.digest().unwrap()[..]);
+            let batch_history = NoteHistory::from_entries(note_history.ownership_entries().to_vec());
+            assert_eq!(batch_history.digest(), note_history.digest());
+            for (position, entry) in note_history.ownership_entries().iter().enumerate() {
+                assert_eq!(
+                    batch_history.lookup_proof(entry.reserve_id, position as i64).unwrap(),
+                    note_history.clone().lookup_proof(entry.reserve_id, position as i64).unwrap()
+                );
+            }
+        }
+    }
+
+    fn decode(hex: &str) -> Vec<u8> {
+        Base16DecodedBytes::try_from(hex.to_owned()).unwrap().0
+    }
+
+    // Known answer vectors, the second one needs two retries before z and e have their high bit unset
+    #[test]
+    fn test_sign_known_answer() {
+        let private_key = DlogProverInput::from_bytes(
+            &decode("3b6a27bcceb6a42d62a3a8d02a6f0d73653215771de243a63ac048a18b59da29")
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+        let public_key = EcPoint::sigma_parse_bytes(&decode(
+            "027255034fc045bd2f2629c515c63bcb0c721c825410ff1e7d18a959d42cbf4797",
+        ))
+        .unwrap();
+        assert_eq!(*private_key.public_image().h, public_key);
+        let note_id = TokenId::from(Digest32::from([7; 32]));
+        for (position, expected) in [
+            (4, "02bdc3fb2e640214c45c97f7f535e6b24be71b2fd06db6e1939f659657567ab3e75c85f55f159c5b37281dba19dc356f41d618ab5cdbbab6e168ed524f0cb28f1d"),
+            (0, "034cda52d7112c8d06858eb1ffd48f826d2e4f56122978c4eff6d66ff434c8786c47918109f78dffbb1196fba05fe53c47bec07da62841657ed056a5d75d6d750e"),
+        ] {
+            let message = signing_message(position, 1_000_000, note_id);
+            let signature = sign(&message, private_key.w.clone());
+            assert_eq!(signature.serialize(), decode(expected));
+            assert!(signature.verify(&message, &public_key));
         }
     }
+
+    #[test]
+    fn test_history_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<NoteHistory>();
+    }
+
+    #[test]
+    fn test_history_used_across_threads() {
+        let entries: Vec<OwnershipEntry> = (0..10).map(|_| force_any_val()).collect();
+        let entry = force_any_val::<OwnershipEntry>();
+        let mut history = NoteHistory::from_entries(entries.clone());
+        let proof = history.add_commitment(entry.clone()).unwrap();
+        let mut spent = NoteHistory::from_entries(entries.clone());
+        let spent_proof = std::thread::spawn(move || spent.add_commitment(entry).unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(spent_proof, proof);
+        let lookup_history = history.clone();
+        assert_eq!(
+            std::thread::spawn(move || lookup_history.lookup_proof(entries[3].reserve_id, 3))
+                .join()
+                .unwrap()
+                .unwrap(),
+            history.lookup_proof(entries[3].reserve_id, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_clone_add_commitment() {
+        let entries: Vec<OwnershipEntry> = (0..10).map(|_| force_any_val()).collect();
+        let history = NoteHistory::from_entries(entries.clone());
+        let entry = force_any_val::<OwnershipEntry>();
+        let mut cloned = history.clone();
+        let proof = cloned.add_commitment(entry.clone()).unwrap();
+        // Original history no longer has a cached prover and rebuilds it
+        let mut rebuilt = history.clone();
+        assert_eq!(rebuilt.add_commitment(entry).unwrap(), proof);
+        assert_eq!(rebuilt.digest(), cloned.digest());
+        assert_eq!(
+            history.lookup_proof(entries[3].reserve_id, 3).unwrap(),
+            NoteHistory::from_entries(entries.clone())
+                .lookup_proof(entries[3].reserve_id, 3)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_from_entries_with_digest() {
+        let entries: Vec<OwnershipEntry> = (0..10).map(|_| force_any_val()).collect();
+        let digest = NoteHistory::from_entries(entries.clone()).digest();
+        let history = NoteHistory::from_entries_with_digest(entries.clone(), digest);
+        assert_eq!(history.digest(), digest);
+        assert_eq!(
+            history.lookup_proof(entries[3].reserve_id, 3).unwrap(),
+            NoteHistory::from_entries(entries.clone())
+                .lookup_proof(entries[3].reserve_id, 3)
+                .unwrap()
+        );
+        // Tree built from entries that don't match digest is rejected
+        let forged_history =
+            NoteHistory::from_entries_with_digest(entries.clone(), Digest([0; 33]));
+        assert!(matches!(
+            forged_history.lookup_proof(entries[3].reserve_id, 3),
+            Err(NoteHistoryError::DigestMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_verify_history() {
+        let note_id = force_any_val::<TokenId>();
+        let owners: Vec<DlogProverInput> = (0..3).map(|_| DlogProverInput::random()).collect();
+        let mut entries: Vec<OwnershipEntry> = owners
+            .iter()
+            .enumerate()
+            .map(|(position, owner)| {
+                let amount = 100 - position as u64;
+                OwnershipEntry {
+                    reserve_id: force_any_val::<TokenId>(),
+                    amount,
+                    signature: sign(
+                        &signing_message(position as u64, amount, note_id),
+                        owner.w.clone(),
+                    ),
+                    signer: *owner.public_image().h,
+                }
+            })
+            .collect();
+        let history = NoteHistory::from_entries(entries.clone());
+        history.verify(note_id).unwrap();
+        assert!(matches!(
+            history.verify(force_any_val::<TokenId>()),
+            Err(NoteHistoryError::InvalidSignature(0))
+        ));
+        entries[1].signer = entries[2].signer.clone();
+        let forged_history = NoteHistory::from_entries(entries);
+        assert!(matches!(
+            forged_history.verify(note_id),
+            Err(NoteHistoryError::InvalidSignature(1))
+        ));
+    }
 }
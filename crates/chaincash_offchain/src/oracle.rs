@@ -1,31 +1,193 @@
-use ergo_lib::{ergo_chain_types::Digest32, ergotree_ir::chain::token::TokenId};
+use ergo_lib::ergotree_ir::{
+    chain::{
+        ergo_box::{BoxId, ErgoBox, NonMandatoryRegisterId, RegisterValueError},
+        token::TokenId,
+    },
+    mir::constant::TryExtractInto,
+    types::stype::SType,
+};
+use thiserror::Error;
 
 pub const GOLD_ORACLE_NFT: &str =
     "3c45f29a5165b030fdb5eaf5d81f8108f9d8f507b31487dd51f4ae08fe07cf4a";
 pub const BUYBACK_NFT: &str = "bf24ed4af7eb5a7839c43aa6b240697d81b196120c837e1a941832c266d3755c";
-const GOLD_ORACLE_NFT_TESTNET: &str =
+pub const GOLD_ORACLE_NFT_TESTNET: &str =
     "a7271cbaea40c8718ef568ebbda125b195207c597b7d53d14873f0b521d4f6d1";
-const BUYBACK_NFT_TESTNET: &str =
+pub const BUYBACK_NFT_TESTNET: &str =
     "8fc353ac5bf8411b757180001e15fb87840a37ebd2e133fe70dce857fc526a19";
 
-pub fn oracle_nft(is_mainnet: bool) -> TokenId {
-    if is_mainnet {
-        Digest32::try_from(GOLD_ORACLE_NFT.to_string())
-            .unwrap()
-            .into()
-    } else {
-        Digest32::try_from(GOLD_ORACLE_NFT_TESTNET.to_string())
-            .unwrap()
-            .into()
+/// Maximum number of blocks since oracle box was created before its price is considered stale
+pub const MAX_ORACLE_AGE: u32 = 720;
+
+#[derive(Error, Debug)]
+pub enum OracleError {
+    #[error("Failed to access register")]
+    BadRegister(#[from] RegisterValueError),
+
+    #[error("Oracle box does not hold oracle NFT {0:?}")]
+    InvalidNft(TokenId),
+
+    #[error("Oracle price register R4 was unexpectedly empty")]
+    MissingPrice,
+
+    #[error("Oracle box field '{field}' was set to incorrect type: {tpe}")]
+    InvalidType { field: &'static str, tpe: SType },
+
+    #[error("Oracle price must be positive, found {0}")]
+    InvalidPrice(i64),
+
+    #[error("Oracle box created at height {creation_height} is stale at height {current_height}")]
+    Stale {
+        creation_height: u32,
+        current_height: u32,
+    },
+}
+
+/// Gold oracle pool box, used as data input when redeeming notes
+#[derive(Debug, Clone)]
+pub struct OracleBoxSpec {
+    /// Price of gold in nanoERG per kg
+    pub nanoerg_per_kg: i64,
+    /// Epoch reported by oracle pool in R5, if set
+    pub epoch: Option<i32>,
+    pub creation_height: u32,
+    inner: ErgoBox,
+}
+
+impl OracleBoxSpec {
+    /// Parse oracle box, checking that it holds `oracle_nft` and a positive price
+    pub fn new(oracle_box: &ErgoBox, oracle_nft: TokenId) -> Result<Self, OracleError> {
+        if oracle_box
+            .tokens
+            .as_ref()
+            .and_then(|tokens| tokens.get(0))
+            .map(|token| token.token_id)
+            != Some(oracle_nft)
+        {
+            return Err(OracleError::InvalidNft(oracle_nft));
+        }
+        let nanoerg_per_kg = oracle_box
+            .get_register(NonMandatoryRegisterId::R4.into())?
+            .ok_or(OracleError::MissingPrice)
+            .and_then(|reg| {
+                if reg.tpe == SType::SLong {
+                    Ok(reg.v.try_extract_into::<i64>().unwrap())
+                } else {
+                    Err(OracleError::InvalidType {
+                        field: "price",
+                        tpe: reg.tpe,
+                    })
+                }
+            })?;
+        if nanoerg_per_kg <= 0 {
+            return Err(OracleError::InvalidPrice(nanoerg_per_kg));
+        }
+        let epoch = oracle_box
+            .get_register(NonMandatoryRegisterId::R5.into())?
+            .map(|reg| {
+                if reg.tpe == SType::SInt {
+                    Ok(reg.v.try_extract_into::<i32>().unwrap())
+                } else {
+                    Err(OracleError::InvalidType {
+                        field: "epoch",
+                        tpe: reg.tpe,
+                    })
+                }
+            })
+            .transpose()?;
+        Ok(Self {
+            nanoerg_per_kg,
+            epoch,
+            creation_height: oracle_box.creation_height,
+            inner: oracle_box.clone(),
+        })
+    }
+
+    /// Price of gold in nanoERG per mg
+    pub fn nanoerg_per_mg(&self) -> i64 {
+        self.nanoerg_per_kg / 1_000_000
+    }
+
+    /// Check that oracle box was created at most `max_age` blocks before `current_height`
+    pub fn check_age(&self, current_height: u32, max_age: u32) -> Result<(), OracleError> {
+        if current_height.saturating_sub(self.creation_height) > max_age {
+            return Err(OracleError::Stale {
+                creation_height: self.creation_height,
+                current_height,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn box_id(&self) -> BoxId {
+        self.inner.box_id()
+    }
+
+    pub fn ergo_box(&self) -> &ErgoBox {
+        &self.inner
     }
 }
 
-pub fn buyback_nft(is_mainnet: bool) -> TokenId {
-    if is_mainnet {
-        Digest32::try_from(BUYBACK_NFT.to_string()).unwrap().into()
-    } else {
-        Digest32::try_from(BUYBACK_NFT_TESTNET.to_string())
-            .unwrap()
-            .into()
+#[cfg(test)]
+mod test {
+    use ergo_lib::{
+        chain::ergo_box::box_builder::ErgoBoxCandidateBuilder,
+        ergotree_ir::chain::{
+            ergo_box::{box_value::BoxValue, NonMandatoryRegisterId},
+            token::TokenId,
+        },
+    };
+
+    use super::{OracleBoxSpec, OracleError, MAX_ORACLE_AGE};
+    use crate::network::Config;
+    use crate::test_util::{create_box, create_oracle_box, force_any_val};
+
+    const NANOERG_PER_KG: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_oracle_box() {
+        let oracle = OracleBoxSpec::new(
+            &create_oracle_box(NANOERG_PER_KG as i64),
+            Config::mainnet().oracle_nft,
+        )
+        .unwrap();
+        assert_eq!(oracle.nanoerg_per_kg, NANOERG_PER_KG as i64);
+        assert_eq!(oracle.nanoerg_per_mg(), NANOERG_PER_KG as i64 / 1_000_000);
+        oracle.check_age(MAX_ORACLE_AGE, MAX_ORACLE_AGE).unwrap();
+        assert!(matches!(
+            oracle.check_age(MAX_ORACLE_AGE + 1, MAX_ORACLE_AGE),
+            Err(OracleError::Stale { .. })
+        ));
+        assert!(matches!(
+            OracleBoxSpec::new(
+                &create_oracle_box(NANOERG_PER_KG as i64),
+                Config::testnet().oracle_nft
+            ),
+            Err(OracleError::InvalidNft(_))
+        ));
+        assert!(matches!(
+            OracleBoxSpec::new(&create_oracle_box(0), Config::mainnet().oracle_nft),
+            Err(OracleError::InvalidPrice(0))
+        ));
+    }
+
+    #[test]
+    fn test_oracle_box_invalid_register() {
+        let oracle_box = create_oracle_box(NANOERG_PER_KG as i64);
+        let mut candidate =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, oracle_box.ergo_tree.clone(), 0);
+        candidate.add_token(oracle_box.tokens.as_ref().unwrap().first().clone());
+        candidate.set_register_value(NonMandatoryRegisterId::R4, (NANOERG_PER_KG as i32).into());
+        assert!(matches!(
+            OracleBoxSpec::new(
+                &create_box(candidate.build().unwrap()),
+                Config::mainnet().oracle_nft
+            ),
+            Err(OracleError::InvalidType { field: "price", .. })
+        ));
+        assert!(matches!(
+            OracleBoxSpec::new(&oracle_box, force_any_val::<TokenId>()),
+            Err(OracleError::InvalidNft(_))
+        ));
     }
 }
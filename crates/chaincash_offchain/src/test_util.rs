@@ -90,6 +90,21 @@ pub fn create_wallet_box(public_key: EcPoint, amount: u64) -> ErgoBox {
 }
 
 pub fn create_reserve(public_key: EcPoint, amount: u64) -> ReserveBoxSpec {
+    create_reserve_with_identifier(
+        public_key,
+        amount,
+        serde_json::from_str(
+            "\"161A3A5250655368566D597133743677397A24432646294A404D635166546A57\"",
+        )
+        .unwrap(),
+    )
+}
+
+pub fn create_reserve_with_identifier(
+    public_key: EcPoint,
+    amount: u64,
+    identifier: TokenId,
+) -> ReserveBoxSpec {
     let mut box_candidate = ErgoBoxCandidateBuilder::new(
         BoxValue::new(amount).unwrap(),
         AddressEncoder::new(ergo_lib::ergotree_ir::chain::address::NetworkPrefix::Mainnet)
@@ -101,10 +116,7 @@ pub fn create_reserve(public_key: EcPoint, amount: u64) -> ReserveBoxSpec {
     );
     box_candidate.set_register_value(NonMandatoryRegisterId::R4, public_key.into());
     box_candidate.add_token(Token {
-        token_id: serde_json::from_str(
-            "\"161A3A5250655368566D597133743677397A24432646294A404D635166546A57\"",
-        )
-        .unwrap(),
+        token_id: identifier,
         amount: 1.try_into().unwrap(),
     });
     ReserveBoxSpec::try_from(&create_box(box_candidate.build().unwrap())).unwrap()
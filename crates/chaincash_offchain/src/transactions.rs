@@ -1,5 +1,9 @@
+pub mod box_selection;
+pub mod fee;
 pub mod notes;
+pub mod receipts;
 pub mod reserves;
+pub mod validation;
 
 use ergo_lib::{
     chain::ergo_box::box_builder::ErgoBoxCandidateBuilderError,
@@ -10,9 +14,11 @@ use ergo_lib::{
     },
     wallet::{box_selector::BoxSelectorError, tx_builder::TxBuilderError},
 };
+use std::sync::Arc;
 use thiserror::Error;
 
-use crate::{boxes, note_history::NoteHistoryError};
+use crate::{boxes, note_history::NoteHistoryError, oracle::OracleError};
+use box_selection::WalletBoxSelector;
 
 #[derive(Debug, Error)]
 pub enum TransactionError {
@@ -43,6 +49,9 @@ pub enum TransactionError {
     #[error("address error: {0}")]
     Address(#[from] AddressEncoderError),
 
+    #[error("Oracle box error: {0}")]
+    Oracle(#[from] OracleError),
+
     #[error("Note history error: {0}")]
     NoteHistoryError(#[from] NoteHistoryError),
 
@@ -55,6 +64,12 @@ pub enum TransactionError {
         output_amount: u64,
     },
 
+    #[error("No outputs specified for spending note")]
+    NoNoteOutputs,
+
+    #[error("Note output {0} has zero amount")]
+    ZeroNoteOutput(usize),
+
     #[error("Must top-up reserve with atleast 1 ERG, got {}", *.0 as f64 / 1_000_000_000.0)]
     TopUpAmountError(u64),
 
@@ -62,10 +77,30 @@ pub enum TransactionError {
         "Attempted to redeem from reserve {0:?}, note does not include ownership entry for this reserve"
     )]
     ReserveEntryNotFound(TokenId),
+
+    #[error(
+        "Attempted to redeem receipt against reserve {0:?}, note history does not include ownership entry for this reserve before position {1}"
+    )]
+    ReceiptReserveEntryNotFound(TokenId, i64),
+
+    #[error("Refund already initiated for reserve {0:?} at height {1}")]
+    RefundAlreadyInitiated(TokenId, i64),
+
+    #[error("Refund not initiated for reserve {0:?}")]
+    RefundNotInitiated(TokenId),
+
+    #[error("Refund for reserve {0:?} can not be completed before height {1}")]
+    RefundNotAvailable(TokenId, i64),
+
+    #[error("Refund amount {refund_amount} exceeds withdrawable reserve value {available}")]
+    RefundAmountError { refund_amount: u64, available: u64 },
 }
 
+#[derive(Clone)]
 pub struct TxContext {
     pub current_height: u32,
     pub change_address: String,
     pub fee: u64,
+    /// Strategy for picking wallet boxes that fund the transaction
+    pub box_selector: Arc<dyn WalletBoxSelector>,
 }
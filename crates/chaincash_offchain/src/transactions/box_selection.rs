@@ -0,0 +1,325 @@
+//! Pluggable strategies for selecting wallet boxes to fund transactions
+
+use std::{collections::HashSet, sync::Arc};
+
+use ergo_lib::{
+    ergotree_ir::chain::{
+        ergo_box::{box_value::BoxValue, ErgoBox},
+        token::{Token, TokenId},
+    },
+    wallet::box_selector::{
+        BoxSelection, BoxSelector, BoxSelectorError, ErgoBoxAssetsData, SimpleBoxSelector,
+    },
+};
+use serde::Deserialize;
+
+/// Selects wallet boxes covering a target balance and tokens. Unlike [`BoxSelector`] this is object safe so strategies can be chosen at runtime
+pub trait WalletBoxSelector: Send + Sync {
+    fn select(
+        &self,
+        wallet_boxes: Vec<ErgoBox>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, BoxSelectorError>;
+}
+
+/// Spend wallet boxes in the order they were given until target is reached
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimpleSelector;
+
+impl WalletBoxSelector for SimpleSelector {
+    fn select(
+        &self,
+        wallet_boxes: Vec<ErgoBox>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, BoxSelectorError> {
+        SimpleBoxSelector::new().select(wallet_boxes, target_balance, target_tokens)
+    }
+}
+
+/// Spend largest boxes first, minimizing number of inputs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FewestInputsSelector;
+
+impl WalletBoxSelector for FewestInputsSelector {
+    fn select(
+        &self,
+        mut wallet_boxes: Vec<ErgoBox>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, BoxSelectorError> {
+        wallet_boxes.sort_by_key(|b| std::cmp::Reverse(*b.value.as_u64()));
+        SimpleBoxSelector::new().select(wallet_boxes, target_balance, target_tokens)
+    }
+}
+
+/// Spend up to `max_dust_inputs` boxes worth less than `dust_threshold` in every transaction, merging them into change
+#[derive(Debug, Clone, Copy)]
+pub struct ConsolidateDustSelector {
+    pub dust_threshold: u64,
+    pub max_dust_inputs: usize,
+}
+
+impl WalletBoxSelector for ConsolidateDustSelector {
+    fn select(
+        &self,
+        wallet_boxes: Vec<ErgoBox>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, BoxSelectorError> {
+        let (mut dust, mut rest): (Vec<ErgoBox>, Vec<ErgoBox>) = wallet_boxes
+            .into_iter()
+            .partition(|b| *b.value.as_u64() < self.dust_threshold && b.tokens.is_none());
+        rest.extend(dust.drain(self.max_dust_inputs.min(dust.len())..));
+        let dust_value: u64 = dust.iter().map(|b| *b.value.as_u64()).sum();
+        if dust_value == 0 {
+            return SimpleBoxSelector::new().select(rest, target_balance, target_tokens);
+        }
+        let dust_value = BoxValue::new(dust_value)?;
+        // Raise target by value of dust so that every dust box is spent, then return dust value in change
+        let BoxSelection {
+            boxes,
+            mut change_boxes,
+        } = SimpleBoxSelector::new().select(
+            dust.into_iter().chain(rest),
+            target_balance.checked_add(&dust_value)?,
+            target_tokens,
+        )?;
+        if let Some(change_box) = change_boxes.first_mut() {
+            change_box.value = change_box.value.checked_add(&dust_value)?;
+        } else {
+            change_boxes.push(ErgoBoxAssetsData {
+                value: dust_value,
+                tokens: None,
+            });
+        }
+        Ok(BoxSelection {
+            boxes,
+            change_boxes,
+        })
+    }
+}
+
+/// Never spend wallet boxes holding tokens
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoTokensSelector;
+
+impl WalletBoxSelector for NoTokensSelector {
+    fn select(
+        &self,
+        wallet_boxes: Vec<ErgoBox>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, BoxSelectorError> {
+        SimpleBoxSelector::new().select(
+            wallet_boxes.into_iter().filter(|b| b.tokens.is_none()),
+            target_balance,
+            target_tokens,
+        )
+    }
+}
+
+/// Box selection strategy used by server, set in `[box_selection]` config section
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum Config {
+    #[default]
+    Simple,
+    FewestInputs,
+    ConsolidateDust {
+        dust_threshold: u64,
+        max_dust_inputs: usize,
+    },
+    NoTokens,
+}
+
+impl Config {
+    pub fn selector(&self) -> Arc<dyn WalletBoxSelector> {
+        match *self {
+            Config::Simple => Arc::new(SimpleSelector),
+            Config::FewestInputs => Arc::new(FewestInputsSelector),
+            Config::ConsolidateDust {
+                dust_threshold,
+                max_dust_inputs,
+            } => Arc::new(ConsolidateDustSelector {
+                dust_threshold,
+                max_dust_inputs,
+            }),
+            Config::NoTokens => Arc::new(NoTokensSelector),
+        }
+    }
+}
+
+/// Split wallet boxes into those free to spend and those holding any `protected` token not listed in `allowed_tokens`
+pub fn exclude_protected_boxes(
+    wallet_boxes: Vec<ErgoBox>,
+    protected: &HashSet<TokenId>,
+    allowed_tokens: &[TokenId],
+) -> (Vec<ErgoBox>, Vec<ErgoBox>) {
+    wallet_boxes.into_iter().partition(|b| {
+        !b.tokens
+            .iter()
+            .flat_map(|tokens| tokens.iter())
+            .any(|token| {
+                protected.contains(&token.token_id) && !allowed_tokens.contains(&token.token_id)
+            })
+    })
+}
+
+/// Select inputs covering `target_balance` and `target_tokens` where `mandatory` boxes (e.g. contract boxes being spent) are always spent first
+/// and only the remainder is funded by wallet boxes picked by `selector`
+pub(crate) fn select_with_mandatory(
+    selector: &dyn WalletBoxSelector,
+    mandatory: Vec<ErgoBox>,
+    wallet_boxes: Vec<ErgoBox>,
+    target_balance: BoxValue,
+    target_tokens: &[Token],
+) -> Result<BoxSelection<ErgoBox>, BoxSelectorError> {
+    let mandatory_value: u64 = mandatory.iter().map(|b| *b.value.as_u64()).sum();
+    let remaining_tokens: Vec<Token> = target_tokens
+        .iter()
+        .filter(|token| {
+            !mandatory
+                .iter()
+                .filter_map(|b| b.tokens.as_ref())
+                .flat_map(|tokens| tokens.iter())
+                .any(|t| t.token_id == token.token_id && t.amount.as_u64() >= token.amount.as_u64())
+        })
+        .cloned()
+        .collect();
+    let remaining = target_balance.as_u64().saturating_sub(mandatory_value);
+    if remaining == 0 && remaining_tokens.is_empty() {
+        if let Ok(selection) =
+            SimpleBoxSelector::new().select(mandatory.clone(), target_balance, target_tokens)
+        {
+            return Ok(selection);
+        }
+    }
+    // Fund at least a change box if mandatory boxes cover target by themselves but their surplus is too small for one
+    let wallet_target = BoxValue::new(remaining.max(*BoxValue::SAFE_USER_MIN.as_u64()))?;
+    let wallet_selection = selector.select(wallet_boxes, wallet_target, &remaining_tokens)?;
+    SimpleBoxSelector::new().select(
+        mandatory.into_iter().chain(wallet_selection.boxes.to_vec()),
+        target_balance,
+        target_tokens,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use ergo_lib::{
+        ergotree_interpreter::sigma_protocol::private_input::DlogProverInput,
+        ergotree_ir::chain::ergo_box::{box_value::BoxValue, ErgoBox},
+        wallet::box_selector::BoxSelection,
+    };
+
+    use std::collections::HashSet;
+
+    use super::{exclude_protected_boxes, select_with_mandatory, Config, WalletBoxSelector};
+    use crate::test_util::{create_reserve, create_wallet_box};
+
+    fn selected_values(selection: &BoxSelection<ErgoBox>) -> Vec<u64> {
+        selection.boxes.iter().map(|b| *b.value.as_u64()).collect()
+    }
+
+    #[test]
+    fn test_strategies() {
+        let public_key = *DlogProverInput::random().public_image().h;
+        let wallet_boxes = vec![
+            create_wallet_box(public_key.clone(), 2_000_000),
+            create_wallet_box(public_key.clone(), 3_000_000),
+            create_wallet_box(public_key.clone(), 5_000_000_000),
+            create_reserve(public_key.clone(), 10_000_000_000)
+                .ergo_box()
+                .clone(),
+        ];
+        let target = BoxValue::new(1_000_000_000).unwrap();
+
+        let simple = Config::Simple.selector();
+        let selection = simple.select(wallet_boxes.clone(), target, &[]).unwrap();
+        assert_eq!(
+            selected_values(&selection),
+            vec![2_000_000, 3_000_000, 5_000_000_000]
+        );
+
+        let fewest = Config::FewestInputs.selector();
+        let selection = fewest.select(wallet_boxes.clone(), target, &[]).unwrap();
+        assert_eq!(selected_values(&selection), vec![10_000_000_000]);
+
+        let no_tokens = Config::NoTokens.selector();
+        let mut reversed = wallet_boxes.clone();
+        reversed.reverse();
+        let selection = no_tokens.select(reversed, target, &[]).unwrap();
+        assert!(selection.boxes.iter().all(|b| b.tokens.is_none()));
+        assert_eq!(selected_values(&selection), vec![5_000_000_000]);
+
+        let consolidate = Config::ConsolidateDust {
+            dust_threshold: 10_000_000,
+            max_dust_inputs: 1,
+        }
+        .selector();
+        let mut dust_last = wallet_boxes.clone();
+        dust_last.rotate_left(2);
+        let selection = consolidate.select(dust_last, target, &[]).unwrap();
+        let selected = selected_values(&selection);
+        assert_eq!(selected.len(), 2);
+        assert!(selected.contains(&2_000_000) && selected.contains(&5_000_000_000));
+        let change: u64 = selection
+            .change_boxes
+            .iter()
+            .map(|b| *b.value.as_u64())
+            .sum();
+        assert_eq!(change, 2_000_000 + 5_000_000_000 - 1_000_000_000);
+    }
+
+    #[test]
+    fn test_exclude_protected_boxes() {
+        let public_key = *DlogProverInput::random().public_image().h;
+        let reserve = create_reserve(public_key.clone(), 1_000_000_000);
+        let wallet_boxes = vec![
+            create_wallet_box(public_key, 1_000_000_000),
+            reserve.ergo_box().clone(),
+        ];
+        let (spendable, protected) =
+            exclude_protected_boxes(wallet_boxes.clone(), &HashSet::new(), &[]);
+        assert_eq!((spendable.len(), protected.len()), (2, 0));
+        let tracked = HashSet::from([reserve.identifier]);
+        let (spendable, protected) = exclude_protected_boxes(wallet_boxes.clone(), &tracked, &[]);
+        assert_eq!(spendable, vec![wallet_boxes[0].clone()]);
+        assert_eq!(protected, vec![wallet_boxes[1].clone()]);
+        let (spendable, protected) =
+            exclude_protected_boxes(wallet_boxes.clone(), &tracked, &[reserve.identifier]);
+        assert_eq!((spendable.len(), protected.len()), (2, 0));
+    }
+
+    #[test]
+    fn test_select_with_mandatory() {
+        let public_key = *DlogProverInput::random().public_image().h;
+        let reserve = create_reserve(public_key.clone(), 1_000_000_000);
+        let nft = reserve.ergo_box().tokens.as_ref().unwrap().first().clone();
+        let wallet_boxes = vec![create_wallet_box(public_key, 2_000_000_000)];
+        let no_tokens = Config::NoTokens.selector();
+        let selection = select_with_mandatory(
+            no_tokens.as_ref(),
+            vec![reserve.ergo_box().clone()],
+            wallet_boxes.clone(),
+            BoxValue::new(2_000_000_000).unwrap(),
+            &[nft.clone()],
+        )
+        .unwrap();
+        assert_eq!(selection.boxes.first().box_id(), reserve.box_id());
+        assert_eq!(selection.boxes.len(), 2);
+
+        let selection = select_with_mandatory(
+            no_tokens.as_ref(),
+            vec![reserve.ergo_box().clone()],
+            wallet_boxes,
+            BoxValue::new(500_000_000).unwrap(),
+            &[nft],
+        )
+        .unwrap();
+        assert_eq!(selection.boxes.len(), 1);
+        assert!(selection.change_boxes.iter().all(|b| b.tokens.is_none()));
+    }
+}
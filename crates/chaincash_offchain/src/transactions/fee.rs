@@ -0,0 +1,196 @@
+//! Fee policies and fee estimation from transaction size
+
+use ergo_lib::{
+    chain::transaction::unsigned::UnsignedTransaction,
+    ergotree_ir::chain::ergo_box::box_value::BoxValue, wallet::tx_builder::SUGGESTED_TX_FEE,
+};
+use serde::Deserialize;
+
+use super::TxContext;
+
+/// Estimated size of an input's spending proof. Transactions built here are spent with a single Schnorr signature (24 byte challenge + 32 byte response) per input
+pub const PROOF_SIZE_ESTIMATE: usize = 56;
+
+// Upper bound on rebuilds, fee box and change values may change serialized size by a few bytes each time fee is updated
+const MAX_FEE_ITERATIONS: usize = 3;
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeePolicy {
+    /// Pay the same fee for every transaction
+    Fixed { fee: u64 },
+    /// Pay `nanoerg_per_byte` for each byte of the signed transaction, optionally bounded by `min_fee` and `max_fee`
+    PerByte {
+        nanoerg_per_byte: u64,
+        #[serde(default)]
+        min_fee: Option<u64>,
+        #[serde(default)]
+        max_fee: Option<u64>,
+    },
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        FeePolicy::Fixed {
+            fee: *SUGGESTED_TX_FEE().as_u64(),
+        }
+    }
+}
+
+/// Estimate size of `transaction` once signed
+pub fn estimate_size(transaction: &UnsignedTransaction) -> usize {
+    // bytes_to_sign serializes inputs with empty proofs
+    transaction
+        .bytes_to_sign()
+        .map(|bytes| bytes.len())
+        .unwrap_or_default()
+        + transaction.inputs.len() * PROOF_SIZE_ESTIMATE
+}
+
+impl FeePolicy {
+    /// Fee used for the first build of a transaction, before its size is known
+    pub fn initial_fee(&self) -> u64 {
+        match self {
+            FeePolicy::Fixed { fee } => *fee,
+            FeePolicy::PerByte { min_fee, .. } => min_fee.unwrap_or(*SUGGESTED_TX_FEE().as_u64()),
+        }
+    }
+
+    /// Fee for a transaction of `size` bytes. Never less than minimum box value since fee is paid to a box
+    pub fn fee_for_size(&self, size: usize) -> u64 {
+        let fee = match self {
+            FeePolicy::Fixed { fee } => *fee,
+            FeePolicy::PerByte {
+                nanoerg_per_byte,
+                min_fee,
+                max_fee,
+            } => {
+                let fee = nanoerg_per_byte.saturating_mul(size as u64);
+                let fee = min_fee.map_or(fee, |min_fee| fee.max(min_fee));
+                max_fee.map_or(fee, |max_fee| fee.min(max_fee))
+            }
+        };
+        fee.max(*BoxValue::SAFE_USER_MIN.as_u64())
+    }
+
+    pub fn fee_for(&self, transaction: &UnsignedTransaction) -> u64 {
+        self.fee_for_size(estimate_size(transaction))
+    }
+
+    /// Build transaction(s) with `build`, rebuilding with fee estimated from the size of the result until it is paid in full.
+    /// `transactions` returns the transactions built, each of which must pay the fee in `context`
+    pub fn build<T, E>(
+        &self,
+        context: &mut TxContext,
+        build: impl Fn(&TxContext) -> Result<T, E>,
+        transactions: impl Fn(&T) -> Vec<&UnsignedTransaction>,
+    ) -> Result<T, E> {
+        let mut result = build(context)?;
+        for iteration in 0..MAX_FEE_ITERATIONS {
+            let fee = transactions(&result)
+                .into_iter()
+                .map(|transaction| self.fee_for(transaction))
+                .max()
+                .unwrap_or(context.fee);
+            // After the first rebuild accept small over-payment instead of oscillating between sizes
+            if fee == context.fee || (iteration > 0 && fee < context.fee) {
+                break;
+            }
+            context.fee = fee;
+            result = build(context)?;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ergo_lib::{
+        ergotree_interpreter::sigma_protocol::private_input::DlogProverInput,
+        ergotree_ir::chain::{
+            address::{Address, NetworkAddress, NetworkPrefix},
+            ergo_box::box_value::BoxValue,
+        },
+    };
+
+    use super::{estimate_size, FeePolicy};
+    use crate::{
+        test_util::{create_reserve, create_wallet_box},
+        transactions::{
+            box_selection::SimpleSelector, reserves::top_up_reserve_transaction, TxContext,
+        },
+    };
+
+    #[test]
+    fn test_fee_for_size() {
+        let min_box_value = *BoxValue::SAFE_USER_MIN.as_u64();
+        assert_eq!(
+            FeePolicy::Fixed { fee: 2_000_000 }.fee_for_size(10_000),
+            2_000_000
+        );
+        let per_byte = FeePolicy::PerByte {
+            nanoerg_per_byte: 10_000,
+            min_fee: None,
+            max_fee: None,
+        };
+        assert_eq!(per_byte.fee_for_size(500), 5_000_000);
+        assert_eq!(per_byte.fee_for_size(1), min_box_value);
+        let bounded = FeePolicy::PerByte {
+            nanoerg_per_byte: 10_000,
+            min_fee: Some(2_000_000),
+            max_fee: Some(8_000_000),
+        };
+        assert_eq!(bounded.initial_fee(), 2_000_000);
+        assert_eq!(bounded.fee_for_size(100), 2_000_000);
+        assert_eq!(bounded.fee_for_size(500), 5_000_000);
+        assert_eq!(bounded.fee_for_size(10_000), 8_000_000);
+    }
+
+    #[test]
+    fn test_build_with_fee_policy() {
+        let private_key = DlogProverInput::random();
+        let public_key = *private_key.public_image().h;
+        let reserve = create_reserve(public_key.clone(), 1_000_000_000);
+        let wallet_boxes = vec![
+            create_wallet_box(public_key.clone(), 1_000_000_000),
+            create_wallet_box(public_key, 1_000_000_000),
+        ];
+        let policy = FeePolicy::PerByte {
+            nanoerg_per_byte: 10_000,
+            min_fee: None,
+            max_fee: None,
+        };
+        let mut context = TxContext {
+            current_height: 0,
+            change_address: NetworkAddress::new(
+                NetworkPrefix::Mainnet,
+                &Address::P2Pk(private_key.public_image()),
+            )
+            .to_base58(),
+            fee: policy.initial_fee(),
+            box_selector: Arc::new(SimpleSelector),
+        };
+        let response = policy
+            .build(
+                &mut context,
+                |context| {
+                    top_up_reserve_transaction(
+                        &reserve,
+                        wallet_boxes.clone(),
+                        1_500_000_000,
+                        context,
+                    )
+                },
+                |response| vec![&response.transaction],
+            )
+            .unwrap();
+        let transaction = &response.transaction;
+        let fee_output = transaction.output_candidates.last();
+        assert_eq!(*fee_output.value.as_u64(), context.fee);
+        assert!(context.fee >= policy.fee_for(transaction));
+        assert!(context.fee - policy.fee_for(transaction) <= 10 * 10_000);
+        assert!(estimate_size(transaction) > 0);
+    }
+}
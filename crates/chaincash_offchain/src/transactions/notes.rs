@@ -1,5 +1,6 @@
 use crate::boxes::{Note, ReserveBoxSpec};
 use crate::note_history::NoteHistory;
+use crate::oracle::OracleBoxSpec;
 
 use super::{TransactionError, TxContext};
 use ergo_avltree_rust::authenticated_tree_ops::AuthenticatedTreeOps;
@@ -15,13 +16,13 @@ use ergo_lib::ergotree_interpreter::sigma_protocol::wscalar::Wscalar;
 use ergo_lib::ergotree_ir::chain::address::NetworkAddress;
 use ergo_lib::ergotree_ir::chain::ergo_box::box_value::{BoxValue, BoxValueError};
 use ergo_lib::ergotree_ir::chain::ergo_box::{ErgoBoxCandidate, NonMandatoryRegisterId};
-use ergo_lib::ergotree_ir::chain::{ergo_box::ErgoBox, token::Token};
+use ergo_lib::ergotree_ir::chain::{
+    ergo_box::ErgoBox,
+    token::{Token, TokenAmount, TokenId},
+};
 use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
 use ergo_lib::ergotree_ir::mir::avl_tree_data::{AvlTreeData, AvlTreeFlags};
-use ergo_lib::ergotree_ir::mir::constant::TryExtractInto;
-use ergo_lib::wallet::box_selector::{
-    BoxSelection, BoxSelector, ErgoBoxAssetsData, SimpleBoxSelector,
-};
+use ergo_lib::wallet::box_selector::{BoxSelection, ErgoBoxAssetsData};
 use ergo_lib::wallet::tx_builder::TxBuilder;
 use serde::{Deserialize, Serialize};
 
@@ -132,6 +133,13 @@ fn create_note_candidate(
     Ok(box_candidate.build()?)
 }
 
+/// Recipient and amount of a note created by spending a note
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NoteOutput {
+    pub recipient_pubkey: EcPoint,
+    pub amount: u64,
+}
+
 pub struct SpendNoteResponse<T: ErgoTransaction> {
     pub transaction: T,
     pub recipient_note: Note,
@@ -140,15 +148,20 @@ pub struct SpendNoteResponse<T: ErgoTransaction> {
 
 pub type SignedSpendNoteResponse = SpendNoteResponse<Transaction>;
 
+/// Spend note to a single recipient. Change note (if any) is owned by `change_owner`, or the note's current owner if not set
 pub fn spend_note_transaction(
     note: &Note,
     reserve: &ReserveBoxSpec,
     private_key: Wscalar,
-    recipient: EcPoint,
-    amount: u64,
+    output: &NoteOutput,
+    change_owner: Option<EcPoint>,
     wallet_boxes: Vec<ErgoBox>,
     context: &TxContext,
 ) -> Result<SpendNoteResponse<UnsignedTransaction>, TransactionError> {
+    let NoteOutput {
+        recipient_pubkey: recipient,
+        amount,
+    } = output.clone();
     let change_amount =
         note.amount
             .as_u64()
@@ -161,11 +174,12 @@ pub fn spend_note_transaction(
 
     // If there is a change note box must have atleast as many erg as the original note
     let erg_needed = note.ergo_box().value.as_u64() * has_change as u64 + context.fee;
-    let box_selector = SimpleBoxSelector::new();
     let BoxSelection {
         boxes,
         change_boxes,
-    } = box_selector.select(wallet_boxes, BoxValue::new(erg_needed)?, &[])?;
+    } = context
+        .box_selector
+        .select(wallet_boxes, BoxValue::new(erg_needed)?, &[])?;
     let mut boxes = boxes.to_vec();
     boxes.push(note.ergo_box().clone());
 
@@ -189,7 +203,7 @@ pub fn spend_note_transaction(
         output_candidates.push(create_note_candidate(
             note,
             new_digest,
-            note.owner.clone(), // TODO: allow setting change address in request
+            change_owner.unwrap_or_else(|| note.owner.clone()),
             change_amount,
             context.current_height,
         )?)
@@ -233,73 +247,265 @@ pub fn spend_note_transaction(
     })
 }
 
-fn create_receipt_candidate(
+/// Spend note to several recipients. Note contract only allows a recipient and a change output per spent note, so paying N recipients
+/// out of one note can't be done in a single transaction. Instead a chain of transactions is built, each paying one recipient and passing
+/// change on to the next. Every transaction adds an ownership entry signed against `reserve` to the note history, so N recipients grow
+/// the history of the last recipient's note by N entries.
+/// Intermediate change notes stay with the note's owner so they can be signed, the final change note is owned by `change_owner`,
+/// or the note's current owner if not set
+pub fn spend_note_split_transactions(
     note: &Note,
     reserve: &ReserveBoxSpec,
-    position: i64,
+    private_key: Wscalar,
+    outputs: &[NoteOutput],
+    change_owner: Option<EcPoint>,
+    mut wallet_boxes: Vec<ErgoBox>,
+    context: &TxContext,
+) -> Result<Vec<SpendNoteResponse<UnsignedTransaction>>, TransactionError> {
+    if outputs.is_empty() {
+        return Err(TransactionError::NoNoteOutputs);
+    }
+    if let Some(i) = outputs.iter().position(|output| output.amount == 0) {
+        return Err(TransactionError::ZeroNoteOutput(i));
+    }
+    let output_amount = outputs.iter().map(|output| output.amount).sum();
+    if output_amount > *note.amount.as_u64() {
+        return Err(TransactionError::NoteAmountError {
+            input_amount: *note.amount.as_u64(),
+            output_amount,
+        });
+    }
+    let mut responses: Vec<SpendNoteResponse<UnsignedTransaction>> = vec![];
+    for (i, output) in outputs.iter().enumerate() {
+        // Outputs are non-zero and sum to at most the note amount, so every hop but the last leaves change
+        let input_note = match responses.last() {
+            Some(response) => {
+                response
+                    .change_note
+                    .as_ref()
+                    .ok_or(TransactionError::NoteAmountError {
+                        input_amount: *note.amount.as_u64(),
+                        output_amount,
+                    })?
+            }
+            None => note,
+        };
+        let hop_change_owner = if i == outputs.len() - 1 {
+            change_owner.clone()
+        } else {
+            None
+        };
+        let response = spend_note_transaction(
+            input_note,
+            reserve,
+            private_key.clone(),
+            output,
+            hop_change_owner,
+            wallet_boxes.clone(),
+            context,
+        )?;
+        // Next transaction spends the ERG change of this one, layout is [recipient note, change note?, change..., fee]
+        let tx_outputs = response.transaction.outputs();
+        let note_outputs = 1 + response.change_note.is_some() as usize;
+        wallet_boxes.retain(|wallet_box| {
+            !response
+                .transaction
+                .inputs
+                .iter()
+                .any(|input| input.box_id == wallet_box.box_id())
+        });
+        wallet_boxes.extend(
+            tx_outputs
+                .iter()
+                .take(tx_outputs.len() - 1)
+                .skip(note_outputs)
+                .cloned(),
+        );
+        responses.push(response);
+    }
+    Ok(responses)
+}
+
+/// Box being redeemed at INPUTS(0) of a redemption transaction, either a note or a receipt of an earlier redemption
+pub(super) struct Redemption<'a> {
+    pub input: &'a ErgoBox,
+    pub note_id: TokenId,
+    pub amount: TokenAmount,
+    pub history: &'a NoteHistory,
+    /// Position of the ownership entry being redeemed
+    pub position: i64,
+    /// Amount signed at `position`
+    pub max_amount: u64,
+    /// Set when redeeming a receipt against an earlier reserve in the note's history
+    pub receipt_mode: bool,
+}
+
+fn create_receipt_candidate(
+    redemption: &Redemption,
+    reserve: &ReserveBoxSpec,
     receipt_contract: &ErgoTree,
     height: u32,
 ) -> Result<ErgoBoxCandidate, TransactionError> {
     // Note value must be >= old note's value
     let mut box_candidate =
-        ErgoBoxCandidateBuilder::new(note.ergo_box().value, receipt_contract.clone(), height);
+        ErgoBoxCandidateBuilder::new(redemption.input.value, receipt_contract.clone(), height);
     box_candidate.add_token(Token {
-        token_id: note.note_id,
-        amount: note.amount,
+        token_id: redemption.note_id,
+        amount: redemption.amount,
     });
-    box_candidate.set_register_value(NonMandatoryRegisterId::R4, note.history.to_avltree().into());
-    box_candidate.set_register_value(NonMandatoryRegisterId::R5, position.into());
+    box_candidate.set_register_value(
+        NonMandatoryRegisterId::R4,
+        redemption.history.to_avltree().into(),
+    );
+    box_candidate.set_register_value(NonMandatoryRegisterId::R5, redemption.position.into());
     box_candidate.set_register_value(NonMandatoryRegisterId::R6, (height as i32).into());
     box_candidate.set_register_value(NonMandatoryRegisterId::R7, reserve.owner.clone().into());
     Ok(box_candidate.build()?)
 }
 
+/// Breakdown of ERG paid out when redeeming a note against a reserve
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RedemptionQuote {
+    pub reserve_id: TokenId,
+    /// Position of the reserve's last ownership entry in note history
+    pub position: i64,
+    /// Value of note in nanoERG at oracle price
+    pub note_value: u64,
+    /// Note value after 2% haircut
+    pub after_haircut: u64,
+    /// Maximum amount of nanoERG the reserve can pay out while keeping minimum box value
+    pub reserve_cap: u64,
+    /// nanoERG taken from reserve, the lesser of `after_haircut` and `reserve_cap`
+    pub redeemable: u64,
+    /// Share of redeemable amount sent to buyback box (0.2%)
+    pub to_buyback: u64,
+    /// nanoERG received by redeemer
+    pub payout: u64,
+}
+
+fn quote_redemption(
+    amount: TokenAmount,
+    position: i64,
+    reserve_box: &ReserveBoxSpec,
+    oracle_box: &OracleBoxSpec,
+) -> RedemptionQuote {
+    // nano erg per mg of gold
+    let price = oracle_box.nanoerg_per_mg() as u64;
+    let note_value = amount.as_u64() * price;
+    let after_haircut = (note_value * 98) / 100;
+    let reserve_cap = reserve_box
+        .ergo_box()
+        .value
+        .as_u64()
+        .saturating_sub(*BoxValue::SAFE_USER_MIN.as_u64());
+    let redeemable = std::cmp::min(reserve_cap, after_haircut);
+    let to_buyback = (redeemable * 2) / 1000;
+    RedemptionQuote {
+        reserve_id: reserve_box.identifier,
+        position,
+        note_value,
+        after_haircut,
+        reserve_cap,
+        redeemable,
+        to_buyback,
+        payout: redeemable - to_buyback,
+    }
+}
+
+// Last ownership entry in note history signed against `reserve_id`, returning its position and amount
+fn last_reserve_entry(history: &NoteHistory, reserve_id: TokenId) -> Option<(i64, u64)> {
+    history
+        .ownership_entries()
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, entry)| entry.reserve_id == reserve_id)
+        .map(|(position, entry)| (position as i64, entry.amount))
+}
+
+/// Compute how much redeeming `note_box` against `reserve_box` would pay out, without building a transaction
+pub fn redemption_quote(
+    note_box: &Note,
+    reserve_box: &ReserveBoxSpec,
+    oracle_box: &OracleBoxSpec,
+) -> Result<RedemptionQuote, TransactionError> {
+    let (position, _) = last_reserve_entry(&note_box.history, reserve_box.identifier).ok_or(
+        TransactionError::ReserveEntryNotFound(reserve_box.identifier),
+    )?;
+    Ok(quote_redemption(
+        note_box.amount,
+        position,
+        reserve_box,
+        oracle_box,
+    ))
+}
+
 pub fn redeem_note(
     note_box: &Note,
     reserve_box: &ReserveBoxSpec,
-    oracle_box: &ErgoBox,
+    oracle_box: &OracleBoxSpec,
+    buyback_box: &ErgoBox,
+    receipt_contract: &ErgoTree,
+    wallet_boxes: Vec<ErgoBox>,
+    context: &TxContext,
+) -> Result<UnsignedTransaction, TransactionError> {
+    let (position, max_amount) = last_reserve_entry(&note_box.history, reserve_box.identifier)
+        .ok_or(TransactionError::ReserveEntryNotFound(
+            reserve_box.identifier,
+        ))?;
+    redemption_transaction(
+        Redemption {
+            input: note_box.ergo_box(),
+            note_id: note_box.note_id,
+            amount: note_box.amount,
+            history: &note_box.history,
+            position,
+            max_amount,
+            receipt_mode: false,
+        },
+        reserve_box,
+        oracle_box,
+        buyback_box,
+        receipt_contract,
+        wallet_boxes,
+        context,
+    )
+}
+
+pub(super) fn redemption_transaction(
+    redemption: Redemption,
+    reserve_box: &ReserveBoxSpec,
+    oracle_box: &OracleBoxSpec,
     buyback_box: &ErgoBox,
     receipt_contract: &ErgoTree,
     wallet_boxes: Vec<ErgoBox>,
     context: &TxContext,
 ) -> Result<UnsignedTransaction, TransactionError> {
     // TX structure:
-    // INPUTS: [note, reserve, buyback, wallet boxes...]
+    // INPUTS: [note or receipt, reserve, buyback, wallet boxes...]
     // OUTPUTS: [reserve, receipt, buyback, change]
     // DATAINPUTS: [oracle]
-    // nano erg per mg of gold
-    let price: i64 = oracle_box
-        .get_register(NonMandatoryRegisterId::R4.into())
-        .unwrap()
-        .unwrap()
-        .try_extract_into::<i64>()
-        .unwrap()
-        / 1_000_000;
-    let redeemable = std::cmp::min(
-        *reserve_box.ergo_box().value.as_u64() - BoxValue::SAFE_USER_MIN.as_u64(),
-        (note_box.amount.as_u64() * price as u64 * 98) / 100,
+    let RedemptionQuote {
+        redeemable,
+        to_buyback: to_oracle,
+        payout: to_change,
+        ..
+    } = quote_redemption(
+        redemption.amount,
+        redemption.position,
+        reserve_box,
+        oracle_box,
     );
-    let to_oracle = (redeemable * 2) / 1000;
-    let to_change = redeemable - to_oracle;
 
-    let (position, max_amount) = note_box
-        .history
-        .ownership_entries()
-        .iter()
-        .enumerate()
-        .rev()
-        .find(|(_, entry)| entry.reserve_id == reserve_box.identifier)
-        .map(|(position, entry)| (position as i64, entry.amount))
-        .ok_or(TransactionError::ReserveEntryNotFound(
-            reserve_box.identifier,
-        ))?;
     let BoxSelection {
         boxes,
         mut change_boxes,
-    } = SimpleBoxSelector::new().select(wallet_boxes, context.fee.try_into().unwrap(), &[])?;
+    } = context
+        .box_selector
+        .select(wallet_boxes, context.fee.try_into()?, &[])?;
     let boxes = [
         &[
-            note_box.ergo_box().clone(),
+            redemption.input.clone(),
             reserve_box.ergo_box().clone(),
             buyback_box.clone(),
         ][..],
@@ -324,9 +530,8 @@ pub fn redeem_note(
             .ok_or(BoxValueError::Overflow)?,
     )?;
     let receipt_output = create_receipt_candidate(
-        note_box,
+        &redemption,
         reserve_box,
-        position,
         receipt_contract,
         context.current_height,
     )?;
@@ -349,24 +554,30 @@ pub fn redeem_note(
         context_extension.values.insert(0, 0i8.into());
         context_extension.values.insert(
             1,
-            note_box
+            redemption
                 .history
-                .lookup_proof(reserve_box.identifier, position)?
+                .lookup_proof(reserve_box.identifier, redemption.position)?
                 .to_vec()
                 .into(),
         );
         context_extension
             .values
-            .insert(2, max_amount.to_be_bytes().to_vec().into());
-        context_extension.values.insert(3, position.into());
-        context_extension.values.insert(4, false.into());
+            .insert(2, redemption.max_amount.to_be_bytes().to_vec().into());
         context_extension
-    });
-    tx_builder.set_context_extension(note_box.ergo_box().box_id(), {
-        let mut context_extension = ContextExtension::empty();
-        context_extension.values.insert(0, (-1i8).into());
+            .values
+            .insert(3, redemption.position.into());
+        context_extension
+            .values
+            .insert(4, redemption.receipt_mode.into());
         context_extension
     });
+    if !redemption.receipt_mode {
+        tx_builder.set_context_extension(redemption.input.box_id(), {
+            let mut context_extension = ContextExtension::empty();
+            context_extension.values.insert(0, (-1i8).into());
+            context_extension
+        });
+    }
     tx_builder.set_context_extension(buyback_box.box_id(), {
         let mut context_extension = ContextExtension::empty();
         context_extension.values.insert(0, (1i32).into());
@@ -377,8 +588,12 @@ pub fn redeem_note(
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use ergo_lib::{
-        chain::ergo_state_context::ErgoStateContext,
+        chain::{
+            ergo_state_context::ErgoStateContext, transaction::ergo_transaction::ErgoTransaction,
+        },
         ergotree_interpreter::sigma_protocol::private_input::DlogProverInput,
         ergotree_ir::chain::{
             address::{Address, AddressEncoder, NetworkAddress, NetworkPrefix},
@@ -388,14 +603,20 @@ mod test {
     };
 
     use crate::{
+        boxes::ReceiptBoxSpec,
+        network::Config,
+        oracle::OracleBoxSpec,
         test_util::{
             create_buyback_box, create_note, create_oracle_box, create_reserve, create_wallet_box,
             force_any_val, RECEIPT_ADDRESS,
         },
-        transactions::TxContext,
+        transactions::{box_selection::SimpleSelector, TransactionError, TxContext},
     };
 
-    use super::{redeem_note, spend_note_transaction, SpendNoteResponse};
+    use super::{
+        redeem_note, redemption_quote, spend_note_split_transactions, spend_note_transaction,
+        NoteOutput, SpendNoteResponse,
+    };
     // Test spending a note with change output
     #[test]
     fn test_spend_note() {
@@ -410,8 +631,11 @@ mod test {
             &note,
             &reserve,
             private_key.w.clone(),
-            *recipient,
-            8,
+            &NoteOutput {
+                recipient_pubkey: *recipient,
+                amount: 8,
+            },
+            None,
             wallet_boxes.clone(),
             &TxContext {
                 current_height: 0,
@@ -421,6 +645,7 @@ mod test {
                 )
                 .to_base58(),
                 fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+                box_selector: Arc::new(SimpleSelector),
             },
         )
         .unwrap();
@@ -440,10 +665,129 @@ mod test {
         assert_eq!(*(note_output_tokens.get(0).unwrap().amount.as_u64()), 8);
         assert_eq!(*(change_output_tokens.get(0).unwrap().amount.as_u64()), 2);
     }
+    // Test splitting a note between several recipients with change sent to a different key
+    #[test]
+    fn test_spend_note_split() {
+        let private_key = DlogProverInput::random();
+        let public_key = private_key.public_image().h;
+        let note = create_note(&public_key, 10);
+        let reserve = create_reserve(*public_key.clone(), 1_000_000_000);
+        let wallet_boxes = vec![create_wallet_box(*public_key.clone(), 1_000_000_000)];
+        let outputs = vec![
+            NoteOutput {
+                recipient_pubkey: *DlogProverInput::random().public_image().h,
+                amount: 3,
+            },
+            NoteOutput {
+                recipient_pubkey: *DlogProverInput::random().public_image().h,
+                amount: 5,
+            },
+        ];
+        let change_owner = *DlogProverInput::random().public_image().h;
+        let responses = spend_note_split_transactions(
+            &note,
+            &reserve,
+            private_key.w.clone(),
+            &outputs,
+            Some(change_owner.clone()),
+            wallet_boxes.clone(),
+            &TxContext {
+                current_height: 0,
+                change_address: NetworkAddress::new(
+                    NetworkPrefix::Mainnet,
+                    &Address::P2Pk(private_key.public_image()),
+                )
+                .to_base58(),
+                fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+                box_selector: Arc::new(SimpleSelector),
+            },
+        )
+        .unwrap();
+        assert_eq!(responses.len(), 2);
+
+        let wallet = Wallet::from_secrets(vec![private_key.into()]);
+        let mut boxes_to_spend = wallet_boxes;
+        boxes_to_spend.push(note.ergo_box().clone());
+        for (response, output) in responses.iter().zip(&outputs) {
+            assert_eq!(response.recipient_note.owner, output.recipient_pubkey);
+            assert_eq!(*response.recipient_note.amount.as_u64(), output.amount);
+            let inputs = response
+                .transaction
+                .inputs
+                .iter()
+                .map(|input| {
+                    boxes_to_spend
+                        .iter()
+                        .find(|b| b.box_id() == input.box_id)
+                        .unwrap()
+                        .clone()
+                })
+                .collect();
+            let tx_context = TransactionContext::new(
+                response.transaction.clone(),
+                inputs,
+                vec![reserve.ergo_box().clone()],
+            )
+            .unwrap();
+            wallet
+                .sign_transaction(tx_context, &force_any_val(), None)
+                .unwrap();
+            boxes_to_spend.extend(response.transaction.outputs().iter().cloned());
+        }
+        // Intermediate change stays with the spender, final change goes to change owner
+        assert_eq!(
+            responses[0].change_note.as_ref().unwrap().owner,
+            *public_key
+        );
+        let change_note = responses[1].change_note.as_ref().unwrap();
+        assert_eq!(change_note.owner, change_owner);
+        assert_eq!(*change_note.amount.as_u64(), 2);
+        assert_eq!(change_note.length, 2);
+    }
+    #[test]
+    fn test_spend_note_split_zero_amount() {
+        let private_key = DlogProverInput::random();
+        let public_key = private_key.public_image().h;
+        let note = create_note(&public_key, 10);
+        let reserve = create_reserve(*public_key.clone(), 1_000_000_000);
+        let outputs = vec![
+            NoteOutput {
+                recipient_pubkey: *DlogProverInput::random().public_image().h,
+                amount: 10,
+            },
+            NoteOutput {
+                recipient_pubkey: *DlogProverInput::random().public_image().h,
+                amount: 0,
+            },
+        ];
+        let result = spend_note_split_transactions(
+            &note,
+            &reserve,
+            private_key.w.clone(),
+            &outputs,
+            None,
+            vec![create_wallet_box(*public_key.clone(), 1_000_000_000)],
+            &TxContext {
+                current_height: 0,
+                change_address: NetworkAddress::new(
+                    NetworkPrefix::Mainnet,
+                    &Address::P2Pk(private_key.public_image()),
+                )
+                .to_base58(),
+                fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+                box_selector: Arc::new(SimpleSelector),
+            },
+        );
+        assert!(matches!(result, Err(TransactionError::ZeroNoteOutput(1))));
+    }
     #[test]
     fn test_redeem_note() {
         const NANOERG_PER_KG: u64 = 1_000_000_000;
-        let oracle_box = create_oracle_box(NANOERG_PER_KG as i64);
+        let oracle_box = OracleBoxSpec::new(
+            &create_oracle_box(NANOERG_PER_KG as i64),
+            Config::mainnet().oracle_nft,
+        )
+        .unwrap();
         let buyback_box = create_buyback_box();
         let reserve_owner_sk = DlogProverInput::random();
         let reserve_owner_pk = *reserve_owner_sk.public_image().h.clone();
@@ -460,6 +804,7 @@ mod test {
             )
             .to_base58(),
             fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+            box_selector: Arc::new(SimpleSelector),
         };
         let recipient_sk = DlogProverInput::random();
         let recipient_pk = *recipient_sk.public_image().h.clone();
@@ -471,8 +816,11 @@ mod test {
             &note,
             &reserve,
             reserve_owner_sk.w.clone(),
-            recipient_pk.clone(),
-            1000,
+            &NoteOutput {
+                recipient_pubkey: recipient_pk.clone(),
+                amount: 1000,
+            },
+            None,
             wallet_boxes,
             &context,
         )
@@ -494,6 +842,19 @@ mod test {
             &context,
         )
         .unwrap();
+        let quote = redemption_quote(&recipient_note, &reserve, &oracle_box).unwrap();
+        assert_eq!(quote.position, 0);
+        assert_eq!(
+            quote.note_value,
+            (NANOERG_PER_KG / 1_000_000) * note.amount.as_u64()
+        );
+        assert_eq!(quote.redeemable, quote.after_haircut);
+        assert_eq!(quote.payout + quote.to_buyback, quote.redeemable);
+        let receipt = ReceiptBoxSpec::try_from(tx.outputs().get(1).unwrap()).unwrap();
+        assert_eq!(receipt.note_id, recipient_note.note_id);
+        assert_eq!(receipt.position, 0);
+        assert_eq!(receipt.height, context.current_height as i32);
+        assert_eq!(receipt.reserve_owner, reserve_owner_pk);
         assert_eq!(
             *tx.output_candidates
                 .get(tx.output_candidates.len() - 2)
@@ -503,6 +864,14 @@ mod test {
             wallet_boxes[0].value.as_u64() - context.fee
                 + (((NANOERG_PER_KG / 1_000_000) * note.amount.as_u64() * 98) / 100) * 998 / 1000,
         );
+        assert_eq!(
+            wallet_boxes[0].value.as_u64() - context.fee + quote.payout,
+            *tx.output_candidates
+                .get(tx.output_candidates.len() - 2)
+                .unwrap()
+                .value
+                .as_u64()
+        );
         let mut input_boxes = wallet_boxes.clone();
         input_boxes.extend_from_slice(&[
             recipient_note.ergo_box().clone(),
@@ -512,7 +881,12 @@ mod test {
         let wallet = Wallet::from_secrets(vec![SecretKey::DlogSecretKey(recipient_sk)]);
         wallet
             .sign_transaction(
-                TransactionContext::new(tx, input_boxes.clone(), vec![oracle_box.clone()]).unwrap(),
+                TransactionContext::new(
+                    tx,
+                    input_boxes.clone(),
+                    vec![oracle_box.ergo_box().clone()],
+                )
+                .unwrap(),
                 &state_context,
                 None,
             )
@@ -544,9 +918,26 @@ mod test {
                 .as_u64(),
             wallet_boxes[0].value.as_u64() - context.fee + 998
         );
+        let quote = redemption_quote(&recipient_note, &reserve, &oracle_box).unwrap();
+        assert_eq!(quote.reserve_cap, 1000);
+        assert_eq!(quote.redeemable, 1000);
+        assert_eq!(quote.payout, 998);
+        assert!(matches!(
+            redemption_quote(
+                &recipient_note,
+                &create_reserve(recipient_note.owner.clone(), 1_000_000_000),
+                &oracle_box
+            ),
+            Err(TransactionError::ReserveEntryNotFound(_))
+        ));
         wallet
             .sign_transaction(
-                TransactionContext::new(tx, input_boxes.clone(), vec![oracle_box.clone()]).unwrap(),
+                TransactionContext::new(
+                    tx,
+                    input_boxes.clone(),
+                    vec![oracle_box.ergo_box().clone()],
+                )
+                .unwrap(),
                 &state_context,
                 None,
             )
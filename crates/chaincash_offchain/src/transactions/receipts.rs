@@ -0,0 +1,232 @@
+use crate::boxes::{Receipt, ReserveBoxSpec};
+use crate::oracle::OracleBoxSpec;
+
+use super::notes::{redemption_transaction, Redemption};
+use super::{TransactionError, TxContext};
+use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+
+/// Redeem a receipt against the reserve of an earlier signer in the note's history.
+/// The reserve must have signed the note at a position lower than the receipt's position
+pub fn redeem_receipt(
+    receipt: &Receipt,
+    reserve_box: &ReserveBoxSpec,
+    oracle_box: &OracleBoxSpec,
+    buyback_box: &ErgoBox,
+    receipt_contract: &ErgoTree,
+    wallet_boxes: Vec<ErgoBox>,
+    context: &TxContext,
+) -> Result<UnsignedTransaction, TransactionError> {
+    let receipt_position = receipt.receipt_box.position;
+    let (position, max_amount) = receipt
+        .history
+        .ownership_entries()
+        .iter()
+        .enumerate()
+        .take(receipt_position.max(0) as usize)
+        .rev()
+        .find(|(_, entry)| entry.reserve_id == reserve_box.identifier)
+        .map(|(position, entry)| (position as i64, entry.amount))
+        .ok_or(TransactionError::ReceiptReserveEntryNotFound(
+            reserve_box.identifier,
+            receipt_position,
+        ))?;
+    redemption_transaction(
+        Redemption {
+            input: receipt.ergo_box(),
+            note_id: receipt.receipt_box.note_id,
+            amount: receipt.receipt_box.amount,
+            history: &receipt.history,
+            position,
+            max_amount,
+            receipt_mode: true,
+        },
+        reserve_box,
+        oracle_box,
+        buyback_box,
+        receipt_contract,
+        wallet_boxes,
+        context,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ergo_lib::{
+        chain::{
+            ergo_state_context::ErgoStateContext, transaction::ergo_transaction::ErgoTransaction,
+        },
+        ergotree_interpreter::sigma_protocol::private_input::DlogProverInput,
+        ergotree_ir::chain::{
+            address::{Address, AddressEncoder, NetworkAddress, NetworkPrefix},
+            ergo_box::box_value::BoxValue,
+        },
+        wallet::{secret_key::SecretKey, signing::TransactionContext, Wallet},
+    };
+
+    use crate::{
+        boxes::{Receipt, ReceiptBoxSpec},
+        network::Config,
+        oracle::OracleBoxSpec,
+        test_util::{
+            create_buyback_box, create_note, create_oracle_box, create_reserve,
+            create_reserve_with_identifier, create_wallet_box, force_any_val, RECEIPT_ADDRESS,
+        },
+        transactions::{
+            box_selection::SimpleSelector,
+            notes::{redeem_note, spend_note_transaction, NoteOutput, SpendNoteResponse},
+            TransactionError, TxContext,
+        },
+    };
+
+    use super::redeem_receipt;
+
+    #[test]
+    fn test_redeem_receipt() {
+        const NANOERG_PER_KG: u64 = 1_000_000_000;
+        let oracle_box = OracleBoxSpec::new(
+            &create_oracle_box(NANOERG_PER_KG as i64),
+            Config::mainnet().oracle_nft,
+        )
+        .unwrap();
+        let buyback_box = create_buyback_box();
+        let issuer_sk = DlogProverInput::random();
+        let issuer_pk = *issuer_sk.public_image().h.clone();
+        let signer_sk = DlogProverInput::random();
+        let signer_pk = *signer_sk.public_image().h.clone();
+        let holder_sk = DlogProverInput::random();
+        let holder_pk = *holder_sk.public_image().h.clone();
+        let issuer_reserve = create_reserve(issuer_pk.clone(), 1_000_000_000);
+        // Second signer's reserve is undercollateralized
+        let signer_reserve = create_reserve_with_identifier(
+            signer_pk.clone(),
+            BoxValue::SAFE_USER_MIN.as_u64() + 1000,
+            force_any_val(),
+        );
+        let state_context = force_any_val::<ErgoStateContext>();
+        let context = TxContext {
+            current_height: state_context.pre_header.height,
+            change_address: NetworkAddress::new(
+                NetworkPrefix::Mainnet,
+                &Address::P2Pk(issuer_sk.public_image()),
+            )
+            .to_base58(),
+            fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+            box_selector: Arc::new(SimpleSelector),
+        };
+        // issuer -> signer -> holder
+        let note = create_note(&issuer_pk, 1000);
+        let SpendNoteResponse { recipient_note, .. } = spend_note_transaction(
+            &note,
+            &issuer_reserve,
+            issuer_sk.w.clone(),
+            &NoteOutput {
+                recipient_pubkey: signer_pk.clone(),
+                amount: 1000,
+            },
+            None,
+            vec![create_wallet_box(issuer_pk.clone(), 1_000_000_000)],
+            &context,
+        )
+        .unwrap();
+        let SpendNoteResponse { recipient_note, .. } = spend_note_transaction(
+            &recipient_note,
+            &signer_reserve,
+            signer_sk.w.clone(),
+            &NoteOutput {
+                recipient_pubkey: holder_pk.clone(),
+                amount: 1000,
+            },
+            None,
+            vec![create_wallet_box(signer_pk.clone(), 1_000_000_000)],
+            &context,
+        )
+        .unwrap();
+
+        let wallet_boxes = vec![create_wallet_box(holder_pk, 1_000_000_000)];
+        let receipt_tree = AddressEncoder::new(NetworkPrefix::Mainnet)
+            .parse_address_from_str(RECEIPT_ADDRESS)
+            .unwrap()
+            .script()
+            .unwrap();
+        let tx = redeem_note(
+            &recipient_note,
+            &signer_reserve,
+            &oracle_box,
+            &buyback_box,
+            &receipt_tree,
+            wallet_boxes.clone(),
+            &context,
+        )
+        .unwrap();
+        let receipt_box = ReceiptBoxSpec::try_from(tx.outputs().get(1).unwrap()).unwrap();
+        assert_eq!(receipt_box.position, 1);
+        let receipt = Receipt::new(receipt_box, recipient_note.history.clone()).unwrap();
+
+        // Receipt can only be redeemed against reserves that signed before it
+        assert!(matches!(
+            redeem_receipt(
+                &receipt,
+                &signer_reserve,
+                &oracle_box,
+                &buyback_box,
+                &receipt_tree,
+                wallet_boxes.clone(),
+                &context,
+            ),
+            Err(TransactionError::ReceiptReserveEntryNotFound(_, 1))
+        ));
+
+        let tx = redeem_receipt(
+            &receipt,
+            &issuer_reserve,
+            &oracle_box,
+            &buyback_box,
+            &receipt_tree,
+            wallet_boxes.clone(),
+            &context,
+        )
+        .unwrap();
+        assert_eq!(tx.inputs.first().box_id, receipt.ergo_box().box_id());
+        assert_eq!(
+            tx.inputs.get(1).unwrap().extension.values.get(&4),
+            Some(&true.into())
+        );
+        let new_receipt = ReceiptBoxSpec::try_from(tx.outputs().get(1).unwrap()).unwrap();
+        assert_eq!(new_receipt.position, 0);
+        assert_eq!(new_receipt.reserve_owner, issuer_pk);
+        assert_eq!(new_receipt.history.digest, recipient_note.history.digest());
+        assert_eq!(
+            *tx.output_candidates
+                .get(tx.output_candidates.len() - 2)
+                .unwrap()
+                .value
+                .as_u64(),
+            wallet_boxes[0].value.as_u64() - context.fee
+                + (((NANOERG_PER_KG / 1_000_000) * note.amount.as_u64() * 98) / 100) * 998 / 1000,
+        );
+
+        // Receipt is spent by the owner of the reserve it was redeemed against, wallet boxes by the holder
+        let mut input_boxes = wallet_boxes.clone();
+        input_boxes.extend_from_slice(&[
+            receipt.ergo_box().clone(),
+            buyback_box.clone(),
+            issuer_reserve.ergo_box().clone(),
+        ]);
+        let wallet = Wallet::from_secrets(vec![
+            SecretKey::DlogSecretKey(holder_sk),
+            SecretKey::DlogSecretKey(signer_sk),
+        ]);
+        wallet
+            .sign_transaction(
+                TransactionContext::new(tx, input_boxes, vec![oracle_box.ergo_box().clone()])
+                    .unwrap(),
+                &state_context,
+                None,
+            )
+            .unwrap();
+    }
+}
@@ -1,5 +1,6 @@
 use crate::boxes::ReserveBoxSpec;
 
+use super::box_selection::select_with_mandatory;
 use super::{TransactionError, TxContext};
 use ergo_lib::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
 use ergo_lib::chain::transaction::ergo_transaction::ErgoTransaction;
@@ -8,14 +9,17 @@ use ergo_lib::chain::transaction::Transaction;
 use ergo_lib::ergo_chain_types::EcPoint;
 use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ContextExtension;
 use ergo_lib::ergotree_ir::chain::address::NetworkAddress;
+use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
 use ergo_lib::ergotree_ir::chain::ergo_box::{ErgoBox, ErgoBoxCandidate};
 use ergo_lib::ergotree_ir::chain::token::TokenAmount;
 use ergo_lib::ergotree_ir::chain::{ergo_box::NonMandatoryRegisterId, token::Token};
 use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
-use ergo_lib::wallet::box_selector::{BoxSelector, SimpleBoxSelector};
 use ergo_lib::wallet::{box_selector::BoxSelection, tx_builder::TxBuilder};
 use serde::{Deserialize, Serialize};
 
+/// Number of blocks that must pass after a refund is initiated before it can be completed. Enforced by the reserve contract
+pub const REFUND_DELAY: i64 = 14400;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct MintReserveRequest {
     pub public_key_hex: String,
@@ -83,16 +87,16 @@ pub fn mint_reserve_transaction(
 
 pub fn top_up_reserve_transaction(
     reserve: &ReserveBoxSpec,
-    mut wallet_boxes: Vec<ErgoBox>,
+    wallet_boxes: Vec<ErgoBox>,
     top_up_amount: u64,
     context: &TxContext,
 ) -> Result<ReserveResponse<UnsignedTransaction>, TransactionError> {
     if top_up_amount < 1_000_000_000 {
         return Err(TransactionError::TopUpAmountError(top_up_amount));
     }
-    wallet_boxes.push(reserve.ergo_box().clone());
-    let box_selector = SimpleBoxSelector::new();
-    let box_selection = box_selector.select(
+    let box_selection = select_with_mandatory(
+        context.box_selector.as_ref(),
+        vec![reserve.ergo_box().clone()],
         wallet_boxes,
         (reserve.ergo_box().value.as_u64() + top_up_amount + context.fee).try_into()?,
         &[Token {
@@ -125,9 +129,148 @@ pub fn top_up_reserve_transaction(
     })
 }
 
+/// Build a copy of the reserve box with new value and refund height (R5)
+fn reserve_candidate(
+    reserve: &ReserveBoxSpec,
+    value: BoxValue,
+    refund_height: Option<i64>,
+    current_height: u32,
+) -> Result<ErgoBoxCandidate, TransactionError> {
+    let mut reserve_box_builder =
+        ErgoBoxCandidateBuilder::new(value, reserve.ergo_box().ergo_tree.clone(), current_height);
+    if let Some(tokens) = &reserve.ergo_box().tokens {
+        for token in tokens.iter() {
+            reserve_box_builder.add_token(token.clone());
+        }
+    }
+    reserve_box_builder
+        .set_register_value(NonMandatoryRegisterId::R4, reserve.owner.clone().into());
+    if let Some(refund_height) = refund_height {
+        reserve_box_builder.set_register_value(NonMandatoryRegisterId::R5, refund_height.into());
+    }
+    Ok(reserve_box_builder.build()?)
+}
+
+/// Spend reserve box with given action, recreating it at output #0. Fee (and missing value) is covered by wallet boxes
+fn reserve_action_transaction(
+    reserve: &ReserveBoxSpec,
+    reserve_box_candidate: ErgoBoxCandidate,
+    wallet_boxes: Vec<ErgoBox>,
+    action: i8,
+    context: &TxContext,
+) -> Result<ReserveResponse<UnsignedTransaction>, TransactionError> {
+    let box_selection = select_with_mandatory(
+        context.box_selector.as_ref(),
+        vec![reserve.ergo_box().clone()],
+        wallet_boxes,
+        reserve_box_candidate
+            .value
+            .checked_add(&context.fee.try_into()?)?,
+        &[Token {
+            token_id: reserve.identifier,
+            amount: TokenAmount::try_from(1).unwrap(),
+        }],
+    )?;
+    let mut tx_builder = TxBuilder::new(
+        box_selection,
+        vec![reserve_box_candidate],
+        context.current_height,
+        context.fee.try_into()?,
+        NetworkAddress::try_from(context.change_address.clone())?.address(),
+    );
+    let mut context_extension = ContextExtension::empty();
+    context_extension.values.insert(0u8, action.into());
+    tx_builder.set_context_extension(reserve.box_id(), context_extension);
+    let transaction = tx_builder.build()?;
+    let reserve_box = ReserveBoxSpec::try_from(transaction.outputs().first().unwrap()).unwrap();
+    Ok(ReserveResponse {
+        reserve_box,
+        transaction,
+    })
+}
+
+/// Start refunding reserve. Once [`REFUND_DELAY`] blocks have passed ERG can be withdrawn from reserve
+pub fn initiate_refund_transaction(
+    reserve: &ReserveBoxSpec,
+    wallet_boxes: Vec<ErgoBox>,
+    context: &TxContext,
+) -> Result<ReserveResponse<UnsignedTransaction>, TransactionError> {
+    if let Some(refund_height) = reserve.refund_height {
+        return Err(TransactionError::RefundAlreadyInitiated(
+            reserve.identifier,
+            refund_height,
+        ));
+    }
+    let reserve_box_candidate = reserve_candidate(
+        reserve,
+        reserve.ergo_box().value,
+        Some(context.current_height as i64),
+        context.current_height,
+    )?;
+    // 20 for refund initiation. 2 = initiate refund, 0 = output index of new reserve box
+    reserve_action_transaction(reserve, reserve_box_candidate, wallet_boxes, 20, context)
+}
+
+/// Cancel refund of reserve, clearing refund height
+pub fn cancel_refund_transaction(
+    reserve: &ReserveBoxSpec,
+    wallet_boxes: Vec<ErgoBox>,
+    context: &TxContext,
+) -> Result<ReserveResponse<UnsignedTransaction>, TransactionError> {
+    if reserve.refund_height.is_none() {
+        return Err(TransactionError::RefundNotInitiated(reserve.identifier));
+    }
+    let reserve_box_candidate = reserve_candidate(
+        reserve,
+        reserve.ergo_box().value,
+        None,
+        context.current_height,
+    )?;
+    // 30 for refund cancellation. 3 = cancel refund, 0 = output index of new reserve box
+    reserve_action_transaction(reserve, reserve_box_candidate, wallet_boxes, 30, context)
+}
+
+/// Complete refund of reserve, withdrawing `refund_amount` nanoERG to change address. Refund height is cleared so another refund can be initiated later
+pub fn complete_refund_transaction(
+    reserve: &ReserveBoxSpec,
+    wallet_boxes: Vec<ErgoBox>,
+    refund_amount: u64,
+    context: &TxContext,
+) -> Result<ReserveResponse<UnsignedTransaction>, TransactionError> {
+    let refund_height = reserve
+        .refund_height
+        .ok_or(TransactionError::RefundNotInitiated(reserve.identifier))?;
+    let available_height = refund_height + REFUND_DELAY;
+    if (context.current_height as i64) < available_height {
+        return Err(TransactionError::RefundNotAvailable(
+            reserve.identifier,
+            available_height,
+        ));
+    }
+    let reserve_value = *reserve.ergo_box().value.as_u64();
+    let available = reserve_value.saturating_sub(*BoxValue::SAFE_USER_MIN.as_u64());
+    if refund_amount > available {
+        return Err(TransactionError::RefundAmountError {
+            refund_amount,
+            available,
+        });
+    }
+    let reserve_box_candidate = reserve_candidate(
+        reserve,
+        (reserve_value - refund_amount).try_into()?,
+        None,
+        context.current_height,
+    )?;
+    // 40 for refund completion. 4 = complete refund, 0 = output index of new reserve box
+    reserve_action_transaction(reserve, reserve_box_candidate, wallet_boxes, 40, context)
+}
+
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use ergo_lib::{
+        chain::ergo_state_context::ErgoStateContext,
         ergotree_interpreter::sigma_protocol::private_input::DlogProverInput,
         ergotree_ir::chain::{
             address::{Address, NetworkAddress, NetworkPrefix},
@@ -137,11 +280,15 @@ mod test {
     };
 
     use crate::{
+        boxes::ReserveBoxSpec,
         test_util::{create_reserve, create_wallet_box, force_any_val},
-        transactions::TxContext,
+        transactions::{box_selection::SimpleSelector, TransactionError, TxContext},
     };
 
-    use super::top_up_reserve_transaction;
+    use super::{
+        cancel_refund_transaction, complete_refund_transaction, initiate_refund_transaction,
+        top_up_reserve_transaction, REFUND_DELAY,
+    };
 
     #[test]
     fn test_topup() {
@@ -157,6 +304,7 @@ mod test {
             )
             .to_base58(),
             fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+            box_selector: Arc::new(SimpleSelector),
         };
         let mut wallet_boxes = vec![create_wallet_box(
             *public_key.clone(),
@@ -178,4 +326,90 @@ mod test {
             reserve.ergo_box().value.as_u64() + top_up_amount
         );
     }
+
+    #[test]
+    fn test_refund() {
+        let private_key = DlogProverInput::random();
+        let public_key = private_key.public_image().h;
+        let reserve = create_reserve(*public_key.clone(), 10_000_000_000);
+        let mut state_context = force_any_val::<ErgoStateContext>();
+        state_context.pre_header.height = 1_000;
+        let mut context = TxContext {
+            current_height: state_context.pre_header.height,
+            change_address: NetworkAddress::new(
+                NetworkPrefix::Mainnet,
+                &Address::P2Pk(private_key.public_image()),
+            )
+            .to_base58(),
+            fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+            box_selector: Arc::new(SimpleSelector),
+        };
+        let wallet_box = create_wallet_box(*public_key.clone(), context.fee);
+        let wallet = Wallet::from_secrets(vec![private_key.into()]);
+
+        let refund_amount = 5_000_000_000;
+        let reserve_response =
+            initiate_refund_transaction(&reserve, vec![wallet_box.clone()], &context).unwrap();
+        assert_eq!(reserve_response.reserve_box.refund_height, Some(1_000));
+        let tx_context = TransactionContext::new(
+            reserve_response.transaction,
+            vec![reserve.ergo_box().clone(), wallet_box.clone()],
+            vec![],
+        )
+        .unwrap();
+        let transaction = wallet
+            .sign_transaction(tx_context, &state_context, None)
+            .unwrap();
+        let refunding_reserve = ReserveBoxSpec::try_from(transaction.outputs.first()).unwrap();
+        assert!(matches!(
+            initiate_refund_transaction(&refunding_reserve, vec![wallet_box.clone()], &context),
+            Err(TransactionError::RefundAlreadyInitiated(_, 1_000))
+        ));
+
+        let reserve_response =
+            cancel_refund_transaction(&refunding_reserve, vec![wallet_box.clone()], &context)
+                .unwrap();
+        assert_eq!(reserve_response.reserve_box.refund_height, None);
+        let tx_context = TransactionContext::new(
+            reserve_response.transaction,
+            vec![refunding_reserve.ergo_box().clone(), wallet_box.clone()],
+            vec![],
+        )
+        .unwrap();
+        wallet
+            .sign_transaction(tx_context, &state_context, None)
+            .unwrap();
+
+        assert!(matches!(
+            complete_refund_transaction(&refunding_reserve, vec![], refund_amount, &context),
+            Err(TransactionError::RefundNotAvailable(_, height)) if height == 1_000 + REFUND_DELAY
+        ));
+        state_context.pre_header.height += REFUND_DELAY as u32;
+        context.current_height = state_context.pre_header.height;
+        assert!(matches!(
+            complete_refund_transaction(&refunding_reserve, vec![], 10_000_000_000, &context),
+            Err(TransactionError::RefundAmountError { .. })
+        ));
+        let reserve_response =
+            complete_refund_transaction(&refunding_reserve, vec![], refund_amount, &context)
+                .unwrap();
+        assert_eq!(reserve_response.reserve_box.refund_height, None);
+        let tx_context = TransactionContext::new(
+            reserve_response.transaction,
+            vec![refunding_reserve.ergo_box().clone()],
+            vec![],
+        )
+        .unwrap();
+        let transaction = wallet
+            .sign_transaction(tx_context, &state_context, None)
+            .unwrap();
+        assert_eq!(
+            *transaction.outputs.first().value.as_u64(),
+            10_000_000_000 - refund_amount
+        );
+        assert_eq!(
+            *transaction.outputs.get(1).unwrap().value.as_u64(),
+            refund_amount - context.fee
+        );
+    }
 }
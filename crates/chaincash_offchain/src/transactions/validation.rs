@@ -0,0 +1,242 @@
+//! Local evaluation of input scripts, so transactions that contracts would reject are caught before submission
+use ergo_lib::{
+    chain::{ergo_state_context::ErgoStateContext, transaction::unsigned::UnsignedTransaction},
+    ergotree_interpreter::eval::reduce_to_crypto,
+    ergotree_ir::{
+        chain::ergo_box::{BoxId, ErgoBox},
+        ergo_tree::ErgoTree,
+        sigma_protocol::sigma_boolean::SigmaBoolean,
+    },
+    wallet::{signing::make_context, tx_context::TransactionContext},
+};
+use thiserror::Error;
+
+use crate::contracts::ContractTrees;
+
+#[derive(Debug, Error)]
+pub enum ValidationError {
+    #[error("Box {0} spent or read by transaction was not provided")]
+    MissingBox(BoxId),
+
+    #[error("Failed to create context for transaction: {0}")]
+    Context(String),
+
+    #[error("Input {index} (box {box_id}) guarded by {contract} contract evaluated to false")]
+    ScriptFalse {
+        index: usize,
+        box_id: BoxId,
+        contract: &'static str,
+    },
+
+    #[error(
+        "Input {index} (box {box_id}) guarded by {contract} contract failed to evaluate: {reason}"
+    )]
+    Evaluation {
+        index: usize,
+        box_id: BoxId,
+        contract: &'static str,
+        reason: String,
+    },
+}
+
+fn contract_name(tree: &ErgoTree, contracts: &ContractTrees) -> &'static str {
+    if *tree == contracts.reserve {
+        "reserve"
+    } else if *tree == contracts.receipt {
+        "receipt"
+    } else if *tree == contracts.note {
+        "note"
+    } else {
+        "non-chaincash"
+    }
+}
+
+/// Reduce script of every input of `transaction`. Inputs guarded by public keys reduce to a proposition that is only checked
+/// once signed, any script reducing to false or failing to evaluate is reported with its input and contract.
+/// `boxes` must contain every box spent or used as a data input by transaction
+pub fn validate_inputs(
+    transaction: &UnsignedTransaction,
+    boxes: &[ErgoBox],
+    state_context: &ErgoStateContext,
+    contracts: &ContractTrees,
+) -> Result<(), ValidationError> {
+    let find_box = |box_id: BoxId| {
+        boxes
+            .iter()
+            .find(|b| b.box_id() == box_id)
+            .cloned()
+            .ok_or(ValidationError::MissingBox(box_id))
+    };
+    let input_boxes = transaction
+        .inputs
+        .iter()
+        .map(|input| find_box(input.box_id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let data_boxes = transaction
+        .data_inputs
+        .iter()
+        .flat_map(|data_inputs| data_inputs.iter())
+        .map(|data_input| find_box(data_input.box_id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let tx_context = TransactionContext::new(transaction.clone(), input_boxes.clone(), data_boxes)
+        .map_err(|e| ValidationError::Context(e.to_string()))?;
+
+    for (index, input_box) in input_boxes.iter().enumerate() {
+        let contract = contract_name(&input_box.ergo_tree, contracts);
+        let context = make_context(state_context, &tx_context, index)
+            .map_err(|e| ValidationError::Context(e.to_string()))?;
+        let reduced = reduce_to_crypto(&input_box.ergo_tree, &context).map_err(|e| {
+            ValidationError::Evaluation {
+                index,
+                box_id: input_box.box_id(),
+                contract,
+                reason: e.to_string(),
+            }
+        })?;
+        if reduced.sigma_prop == SigmaBoolean::TrivialProp(false) {
+            return Err(ValidationError::ScriptFalse {
+                index,
+                box_id: input_box.box_id(),
+                contract,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ergo_lib::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use ergo_lib::chain::ergo_state_context::ErgoStateContext;
+    use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+    use ergo_lib::ergoscript_compiler::compiler::compile;
+    use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergo_lib::ergotree_ir::chain::address::{Address, NetworkAddress, NetworkPrefix};
+    use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+    use ergo_lib::wallet::box_selector::{BoxSelector, SimpleBoxSelector};
+    use ergo_lib::wallet::tx_builder::TxBuilder;
+
+    use super::{validate_inputs, ValidationError};
+    use crate::contracts::ContractTrees;
+    use crate::network;
+    use crate::test_util::{create_box, create_reserve, create_wallet_box, force_any_val};
+    use crate::transactions::box_selection::SimpleSelector;
+    use crate::transactions::reserves::top_up_reserve_transaction;
+    use crate::transactions::TxContext;
+
+    fn spend(script: &str) -> (UnsignedTransaction, Vec<ErgoBox>) {
+        let public_key = *DlogProverInput::random().public_image().h;
+        let script_box = create_box(
+            ErgoBoxCandidateBuilder::new(
+                BoxValue::new(1_000_000_000).unwrap(),
+                compile(script, Default::default()).unwrap(),
+                0,
+            )
+            .build()
+            .unwrap(),
+        );
+        let wallet_box = create_wallet_box(public_key.clone(), 1_000_000_000);
+        let boxes = vec![script_box, wallet_box];
+        let selection = SimpleBoxSelector::new()
+            .select(
+                boxes.clone(),
+                BoxValue::new(1_900_000_000 + BoxValue::SAFE_USER_MIN.as_u64()).unwrap(),
+                &[],
+            )
+            .unwrap();
+        let output = ErgoBoxCandidateBuilder::new(
+            BoxValue::new(1_900_000_000).unwrap(),
+            Address::P2Pk(public_key.clone().into()).script().unwrap(),
+            0,
+        )
+        .build()
+        .unwrap();
+        let transaction = TxBuilder::new(
+            selection,
+            vec![output],
+            0,
+            BoxValue::SAFE_USER_MIN,
+            Address::P2Pk(public_key.into()),
+        )
+        .build()
+        .unwrap();
+        (transaction, boxes)
+    }
+
+    #[test]
+    fn test_validate_inputs() {
+        let contracts = ContractTrees::new(&network::Config::mainnet()).unwrap();
+        let state_context = force_any_val::<ErgoStateContext>();
+
+        let (transaction, boxes) = spend("sigmaProp(true)");
+        validate_inputs(&transaction, &boxes, &state_context, &contracts).unwrap();
+
+        let (transaction, boxes) = spend("sigmaProp(false)");
+        assert!(matches!(
+            validate_inputs(&transaction, &boxes, &state_context, &contracts),
+            Err(ValidationError::ScriptFalse { index: 0, box_id, contract: "non-chaincash" })
+                if box_id == boxes[0].box_id()
+        ));
+
+        let (transaction, boxes) = spend("sigmaProp(getVar[Int](1).get > 0)");
+        assert!(matches!(
+            validate_inputs(&transaction, &boxes, &state_context, &contracts),
+            Err(ValidationError::Evaluation { index: 0, .. })
+        ));
+
+        assert!(matches!(
+            validate_inputs(&transaction, &boxes[1..], &state_context, &contracts),
+            Err(ValidationError::MissingBox(box_id)) if box_id == boxes[0].box_id()
+        ));
+    }
+
+    #[test]
+    fn test_validate_reserve_top_up() {
+        let contracts = ContractTrees::new(&network::Config::mainnet()).unwrap();
+        let state_context = force_any_val::<ErgoStateContext>();
+        let public_key = *DlogProverInput::random().public_image().h;
+        let reserve = create_reserve(public_key.clone(), 1_000_000_000);
+        let wallet_box = create_wallet_box(public_key.clone(), 10_000_000_000);
+        let context = TxContext {
+            current_height: 1000,
+            change_address: NetworkAddress::new(
+                NetworkPrefix::Mainnet,
+                &Address::P2Pk(public_key.into()),
+            )
+            .to_base58(),
+            fee: *BoxValue::SAFE_USER_MIN.as_u64(),
+            box_selector: Arc::new(SimpleSelector),
+        };
+        let boxes = vec![reserve.ergo_box().clone(), wallet_box.clone()];
+        let transaction =
+            top_up_reserve_transaction(&reserve, vec![wallet_box], 1_000_000_000, &context)
+                .unwrap()
+                .transaction;
+        validate_inputs(&transaction, &boxes, &state_context, &contracts).unwrap();
+
+        // Reserve output that isn't topped up is rejected by reserve contract
+        let mut outputs = transaction.output_candidates.to_vec();
+        outputs[0].value = reserve.ergo_box().value;
+        let transaction = UnsignedTransaction::new(
+            transaction.inputs.clone(),
+            transaction.data_inputs.clone(),
+            outputs.try_into().unwrap(),
+        )
+        .unwrap();
+        assert!(matches!(
+            validate_inputs(&transaction, &boxes, &state_context, &contracts),
+            Err(ValidationError::ScriptFalse {
+                index: 0,
+                contract: "reserve",
+                ..
+            }) | Err(ValidationError::Evaluation {
+                index: 0,
+                contract: "reserve",
+                ..
+            })
+        ));
+    }
+}
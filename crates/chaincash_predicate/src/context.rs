@@ -14,6 +14,8 @@ pub struct NoteContext {
     pub issuer: PubKeyHex,
     /// Agents that have signed and traded the note
     pub signers: Vec<PubKeyHex>,
+    /// NFT IDs of reserves backing each ownership entry of the note, hex encoded, in the same order as `signers`
+    pub reserves: Vec<String>,
 }
 
 /// Implementors provide a way to access extra context when processing a note
@@ -1,5 +1,6 @@
 pub mod context;
 pub mod predicates;
+pub mod trace;
 
 use std::path::PathBuf;
 
@@ -15,8 +16,62 @@ pub enum Error {
     },
 }
 
+/// How results of configured predicates are combined to decide if a note is accepted
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Combination {
+    /// Note is accepted if any predicate accepts it
+    #[default]
+    Any,
+    /// Note is accepted if every predicate accepts it
+    All,
+}
+
+impl Combination {
+    /// Combine predicate results, returning whether note is accepted and the index of the predicate that decided it:
+    /// the first accepting predicate for [`Combination::Any`] or the first rejecting predicate for [`Combination::All`].
+    /// With no predicates `Any` rejects and `All` accepts
+    pub fn decide(&self, results: impl IntoIterator<Item = bool>) -> (bool, Option<usize>) {
+        let mut results = results.into_iter();
+        match self {
+            Combination::Any => {
+                let decided = results.position(|accepted| accepted);
+                (decided.is_some(), decided)
+            }
+            Combination::All => {
+                let decided = results.position(|accepted| !accepted);
+                (decided.is_none(), decided)
+            }
+        }
+    }
+}
+
 #[derive(serde::Deserialize, Debug)]
 pub struct Config {
     /// Path to enabled predicate configuration files
     pub predicates: Vec<PathBuf>,
+    /// How results of predicates are combined
+    #[serde(default)]
+    pub combine: Combination,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combination() {
+        assert_eq!(
+            Combination::Any.decide([false, true, true]),
+            (true, Some(1))
+        );
+        assert_eq!(Combination::Any.decide([false, false]), (false, None));
+        assert_eq!(Combination::Any.decide([]), (false, None));
+        assert_eq!(
+            Combination::All.decide([true, false, false]),
+            (false, Some(1))
+        );
+        assert_eq!(Combination::All.decide([true, true]), (true, None));
+        assert_eq!(Combination::All.decide([]), (true, None));
+    }
 }
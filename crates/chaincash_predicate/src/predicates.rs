@@ -1,24 +1,43 @@
 use crate::context::{ContextProvider, PredicateContext};
+use crate::trace::Trace;
 use crate::Error;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+pub mod and;
+pub mod at_least;
 pub mod blacklist;
 pub mod collateral;
+pub mod distinct_reserves;
+pub mod history_length;
+pub mod not;
 pub mod or;
+pub mod value;
 pub mod whitelist;
 
 pub trait Accept {
     fn accept<P: ContextProvider>(&self, context: &PredicateContext<P>) -> bool;
 }
 
+pub trait Explain {
+    /// Evaluate predicate like [`Accept::accept`], returning a [`Trace`] of how it was decided.
+    /// Unlike `accept` evaluation doesn't stop early, so every condition is part of the trace
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace;
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Predicate {
     Or(or::Or),
+    And(and::And),
+    Not(not::Not),
+    AtLeast(at_least::AtLeast),
     Whitelist(whitelist::Whitelist),
     Blacklist(blacklist::Blacklist),
     Collateral(collateral::Collateral),
+    Value(value::Value),
+    HistoryLength(history_length::HistoryLength),
+    DistinctReserves(distinct_reserves::DistinctReserves),
 }
 
 impl Predicate {
@@ -32,13 +51,41 @@ impl Predicate {
     }
 }
 
+/// Whether `value` is within inclusive bounds, a missing bound is not checked
+fn within<T: PartialOrd>(value: T, min: Option<T>, max: Option<T>) -> bool {
+    !matches!(min, Some(min) if value < min) && !matches!(max, Some(max) if value > max)
+}
+
 impl Accept for Predicate {
     fn accept<P: ContextProvider>(&self, context: &PredicateContext<P>) -> bool {
         match self {
             Predicate::Or(p) => p.accept(context),
+            Predicate::And(p) => p.accept(context),
+            Predicate::Not(p) => p.accept(context),
+            Predicate::AtLeast(p) => p.accept(context),
             Predicate::Whitelist(p) => p.accept(context),
             Predicate::Blacklist(p) => p.accept(context),
             Predicate::Collateral(p) => p.accept(context),
+            Predicate::Value(p) => p.accept(context),
+            Predicate::HistoryLength(p) => p.accept(context),
+            Predicate::DistinctReserves(p) => p.accept(context),
+        }
+    }
+}
+
+impl Explain for Predicate {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        match self {
+            Predicate::Or(p) => p.explain(context),
+            Predicate::And(p) => p.explain(context),
+            Predicate::Not(p) => p.explain(context),
+            Predicate::AtLeast(p) => p.explain(context),
+            Predicate::Whitelist(p) => p.explain(context),
+            Predicate::Blacklist(p) => p.explain(context),
+            Predicate::Collateral(p) => p.explain(context),
+            Predicate::Value(p) => p.explain(context),
+            Predicate::HistoryLength(p) => p.explain(context),
+            Predicate::DistinctReserves(p) => p.explain(context),
         }
     }
 }
@@ -58,4 +105,36 @@ mod tests {
             "#;
         assert!(toml::from_str::<Predicate>(s).is_ok())
     }
+
+    #[test]
+    fn test_nested_combinators_deser() {
+        let s = r#"
+            type = "and"
+            conditions = [
+                {type = "not", condition = {type = "blacklist", kind = "historical", agents = ["PK1"]}},
+                {type = "at_least", k = 2, conditions = [
+                    {type = "whitelist", kind = "owner", agents = ["PK1", "PK2"]},
+                    {type = "whitelist", kind = "issuer", agents = ["PK3"]},
+                    {type = "or", conditions = [{type = "collateral", percent = 110}]}
+                ]}
+            ]
+            "#;
+        let predicate = toml::from_str::<Predicate>(s).unwrap();
+        assert!(matches!(predicate, Predicate::And(_)));
+    }
+
+    #[test]
+    fn test_limits_deser() {
+        let s = r#"
+            type = "and"
+            conditions = [
+                {type = "value", max = 1000000000},
+                {type = "value", min = 1000, max = 2000},
+                {type = "history_length", max = 10},
+                {type = "distinct_reserves", min = 2}
+            ]
+            "#;
+        let predicate = toml::from_str::<Predicate>(s).unwrap();
+        assert!(matches!(predicate, Predicate::And(_)));
+    }
 }
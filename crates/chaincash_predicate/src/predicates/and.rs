@@ -0,0 +1,75 @@
+use crate::context::{ContextProvider, PredicateContext};
+use crate::predicates::{Accept, Explain, Predicate};
+use crate::trace::{Trace, TraceDetail};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct And {
+    conditions: Vec<Predicate>,
+}
+
+impl Accept for And {
+    fn accept<P: ContextProvider>(&self, context: &PredicateContext<P>) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.accept(context))
+    }
+}
+
+impl Explain for And {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        let conditions: Vec<Trace> = self
+            .conditions
+            .iter()
+            .map(|condition| condition.explain(context))
+            .collect();
+        Trace {
+            accepted: conditions.iter().all(|trace| trace.accepted),
+            detail: TraceDetail::And { conditions },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{test_util::TestContextProvider, NoteContext};
+    use crate::predicates::whitelist::{Whitelist, WhitelistKind};
+
+    fn whitelist(agent: &str, kind: WhitelistKind) -> Predicate {
+        Predicate::Whitelist(Whitelist {
+            agents: vec![agent.to_owned()],
+            kind,
+        })
+    }
+
+    #[test]
+    fn test_returns_true_only_if_all_conditions_return_true() {
+        let note = NoteContext {
+            nanoerg: 1000,
+            issuer: "issuer1".to_owned(),
+            owner: "owner1".to_owned(),
+            signers: vec!["issuer1".to_owned()],
+            reserves: vec![],
+        };
+        let provider = TestContextProvider { agents: vec![] };
+        let context = PredicateContext { note, provider };
+        let p = And {
+            conditions: vec![
+                whitelist("owner1", WhitelistKind::Owner),
+                whitelist("issuer1", WhitelistKind::Issuer),
+            ],
+        };
+        assert!(p.accept(&context));
+        assert!(p.explain(&context).accepted);
+
+        let p = And {
+            conditions: vec![
+                whitelist("owner1", WhitelistKind::Owner),
+                whitelist("issuer2", WhitelistKind::Issuer),
+            ],
+        };
+        assert!(!p.accept(&context));
+        assert!(!p.explain(&context).accepted);
+    }
+}
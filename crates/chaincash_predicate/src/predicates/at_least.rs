@@ -0,0 +1,143 @@
+use crate::context::{ContextProvider, PredicateContext};
+use crate::predicates::{Accept, Explain, Predicate};
+use crate::trace::{Trace, TraceDetail};
+use serde::{Deserialize, Serialize};
+
+/// Accepts if at least `k` of the conditions accept
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(try_from = "AtLeastConfig")]
+pub struct AtLeast {
+    k: usize,
+    conditions: Vec<Predicate>,
+}
+
+/// [`AtLeast`] as configured, before checking that `k` can be satisfied
+#[derive(Deserialize)]
+struct AtLeastConfig {
+    k: usize,
+    conditions: Vec<Predicate>,
+}
+
+impl TryFrom<AtLeastConfig> for AtLeast {
+    type Error = String;
+
+    fn try_from(config: AtLeastConfig) -> Result<Self, Self::Error> {
+        if config.k > config.conditions.len() {
+            return Err(format!(
+                "at_least k = {} exceeds number of conditions {}",
+                config.k,
+                config.conditions.len()
+            ));
+        }
+        Ok(Self {
+            k: config.k,
+            conditions: config.conditions,
+        })
+    }
+}
+
+impl Accept for AtLeast {
+    fn accept<P: ContextProvider>(&self, context: &PredicateContext<P>) -> bool {
+        let mut accepted = 0;
+        for condition in &self.conditions {
+            if accepted >= self.k {
+                break;
+            }
+            if condition.accept(context) {
+                accepted += 1;
+            }
+        }
+
+        accepted >= self.k
+    }
+}
+
+impl Explain for AtLeast {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        let conditions: Vec<Trace> = self
+            .conditions
+            .iter()
+            .map(|condition| condition.explain(context))
+            .collect();
+        let accepted_count = conditions.iter().filter(|trace| trace.accepted).count();
+        Trace {
+            accepted: accepted_count >= self.k,
+            detail: TraceDetail::AtLeast {
+                k: self.k,
+                accepted_count,
+                conditions,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{test_util::TestContextProvider, NoteContext};
+    use crate::predicates::whitelist::{Whitelist, WhitelistKind};
+
+    fn whitelist(agent: &str, kind: WhitelistKind) -> Predicate {
+        Predicate::Whitelist(Whitelist {
+            agents: vec![agent.to_owned()],
+            kind,
+        })
+    }
+
+    #[test]
+    fn test_returns_true_if_at_least_k_conditions_return_true() {
+        let note = NoteContext {
+            nanoerg: 1000,
+            issuer: "issuer1".to_owned(),
+            owner: "owner1".to_owned(),
+            signers: vec!["issuer1".to_owned()],
+            reserves: vec![],
+        };
+        let provider = TestContextProvider { agents: vec![] };
+        let context = PredicateContext { note, provider };
+        let conditions = vec![
+            whitelist("owner1", WhitelistKind::Owner),
+            whitelist("issuer2", WhitelistKind::Issuer),
+            whitelist("issuer1", WhitelistKind::Historical),
+        ];
+
+        for (k, expected) in [(0, true), (1, true), (2, true), (3, false)] {
+            let p = AtLeast {
+                k,
+                conditions: conditions.clone(),
+            };
+            assert_eq!(p.accept(&context), expected, "k = {k}");
+            let trace = p.explain(&context);
+            assert_eq!(trace.accepted, expected, "k = {k}");
+            assert!(matches!(
+                trace.detail,
+                TraceDetail::AtLeast {
+                    accepted_count: 2,
+                    ..
+                }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rejects_k_above_condition_count() {
+        let config = |k: usize| {
+            format!(
+                r#"
+                type = "at_least"
+                k = {k}
+                conditions = [
+                    {{type = "whitelist", kind = "owner", agents = ["owner1"]}},
+                    {{type = "whitelist", kind = "issuer", agents = ["issuer1"]}},
+                ]
+                "#
+            )
+        };
+        assert!(toml::from_str::<Predicate>(&config(2)).is_ok());
+        let err = toml::from_str::<Predicate>(&config(3)).unwrap_err();
+        assert!(
+            err.to_string().contains("exceeds number of conditions 2"),
+            "{err}"
+        );
+    }
+}
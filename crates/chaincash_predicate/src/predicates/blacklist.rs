@@ -1,5 +1,6 @@
 use crate::context::{ContextProvider, PredicateContext};
-use crate::predicates::Accept;
+use crate::predicates::{Accept, Explain};
+use crate::trace::{Trace, TraceDetail};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -30,6 +31,35 @@ impl Accept for Blacklist {
     }
 }
 
+impl Blacklist {
+    /// Agents of the note matching `agents`, depending on kind
+    fn matched<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Vec<String> {
+        let candidates = match self.kind {
+            BlacklistKind::Issuer => std::slice::from_ref(&context.note.issuer),
+            BlacklistKind::Owner => std::slice::from_ref(&context.note.owner),
+            BlacklistKind::Historical => &context.note.signers[..],
+        };
+        candidates
+            .iter()
+            .filter(|agent| self.agents.contains(agent))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Explain for Blacklist {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        let matched = self.matched(context);
+        Trace {
+            accepted: matched.is_empty(),
+            detail: TraceDetail::Blacklist {
+                kind: self.kind.clone(),
+                matched,
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,6 +73,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -62,6 +93,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -80,6 +112,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -99,6 +132,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -121,6 +155,7 @@ mod tests {
                 "signer1".to_owned(),
                 "next_owner".to_owned(),
             ],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -140,6 +175,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone(), "another1".to_owned()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -1,5 +1,6 @@
-use crate::context::{ContextProvider, PredicateContext};
-use crate::predicates::Accept;
+use crate::context::{ContextProvider, NanoErg, PredicateContext};
+use crate::predicates::{Accept, Explain};
+use crate::trace::{AgentCollateral, Trace, TraceDetail};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -14,39 +15,111 @@ impl Default for CollateralAlgorithm {
     }
 }
 
+/// Reserves as percent of notes value. Infinite if agent has reserves but no notes, NaN if it has neither
+fn collateral_percent(reserves: NanoErg, notes: NanoErg) -> f64 {
+    (reserves as f64 / notes as f64) * 100.0
+}
+
+fn agent_collateral(
+    agent: &str,
+    reserves_nanoerg: NanoErg,
+    notes_nanoerg: NanoErg,
+    required_percent: u16,
+) -> AgentCollateral {
+    let percent = collateral_percent(reserves_nanoerg, notes_nanoerg);
+    AgentCollateral {
+        agent: agent.to_owned(),
+        reserves_nanoerg,
+        notes_nanoerg,
+        percent: percent.is_finite().then_some(percent),
+        sufficient: percent >= required_percent as f64,
+    }
+}
+
 impl CollateralAlgorithm {
-    fn initial<P: ContextProvider>(&self, percent: u16, context: &PredicateContext<P>) -> bool {
+    // Issuer's reserves against all notes it issued
+    fn issuer_collateral<P: ContextProvider>(
+        percent: u16,
+        context: &PredicateContext<P>,
+    ) -> AgentCollateral {
         let issuer_note_tally: u64 = context
             .provider
             .agent_issued_notes(&context.note.issuer)
             .iter()
             .map(|n| n.nanoerg)
             .sum();
-        let issuer_reserves = context
-            .provider
-            .agent_reserves_nanoerg(&context.note.issuer);
-        let issuer_collateral = (issuer_reserves as f64 / issuer_note_tally as f64) * 100.0;
-
-        if issuer_collateral >= percent as f64 {
-            return true;
-        }
+        agent_collateral(
+            &context.note.issuer,
+            context
+                .provider
+                .agent_reserves_nanoerg(&context.note.issuer),
+            issuer_note_tally,
+            percent,
+        )
+    }
 
-        for signer in context.note.signers.iter().skip(1) {
-            let signer_notes = context.provider.agent_issued_notes(signer);
-            let highest_value_note = signer_notes.iter().max_by_key(|n| n.nanoerg);
+    // Signer's reserves against its highest value issued note, `None` if signer issued no notes
+    fn signer_collateral<P: ContextProvider>(
+        signer: &str,
+        percent: u16,
+        context: &PredicateContext<P>,
+    ) -> Option<AgentCollateral> {
+        let highest_value = context
+            .provider
+            .agent_issued_notes(signer)
+            .iter()
+            .map(|n| n.nanoerg)
+            .max()?;
+        Some(agent_collateral(
+            signer,
+            context.provider.agent_reserves_nanoerg(signer),
+            highest_value,
+            percent,
+        ))
+    }
 
-            if let Some(signer_note) = highest_value_note {
-                let signer_reserves = context.provider.agent_reserves_nanoerg(signer);
-                let signer_collateral =
-                    (signer_reserves as f64 / signer_note.nanoerg as f64) * 100.0;
+    fn initial<P: ContextProvider>(&self, percent: u16, context: &PredicateContext<P>) -> bool {
+        Self::issuer_collateral(percent, context).sufficient
+            || context.note.signers.iter().skip(1).any(|signer| {
+                Self::signer_collateral(signer, percent, context)
+                    .is_some_and(|collateral| collateral.sufficient)
+            })
+    }
 
-                if signer_collateral >= percent as f64 {
-                    return true;
-                }
-            }
+    // Collateral of the issuer and every signer, instead of stopping at the first sufficient one like `initial`
+    fn explain_initial<P: ContextProvider>(
+        &self,
+        percent: u16,
+        context: &PredicateContext<P>,
+    ) -> Trace {
+        let issuer = Self::issuer_collateral(percent, context);
+        let signers: Vec<AgentCollateral> = context
+            .note
+            .signers
+            .iter()
+            .skip(1)
+            .map(|signer| {
+                // Signers without issued notes are skipped by the algorithm
+                Self::signer_collateral(signer, percent, context).unwrap_or_else(|| {
+                    AgentCollateral {
+                        agent: signer.clone(),
+                        reserves_nanoerg: context.provider.agent_reserves_nanoerg(signer),
+                        notes_nanoerg: 0,
+                        percent: None,
+                        sufficient: false,
+                    }
+                })
+            })
+            .collect();
+        Trace {
+            accepted: issuer.sufficient || signers.iter().any(|signer| signer.sufficient),
+            detail: TraceDetail::Collateral {
+                algorithm: self.clone(),
+                required_percent: percent,
+                issuer,
+                signers,
+            },
         }
-
-        false
     }
 
     pub fn eval<P: ContextProvider>(&self, percent: u16, context: &PredicateContext<P>) -> bool {
@@ -54,6 +127,16 @@ impl CollateralAlgorithm {
             CollateralAlgorithm::Initial => self.initial(percent, context),
         }
     }
+
+    pub fn explain<P: ContextProvider>(
+        &self,
+        percent: u16,
+        context: &PredicateContext<P>,
+    ) -> Trace {
+        match self {
+            CollateralAlgorithm::Initial => self.explain_initial(percent, context),
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -69,6 +152,12 @@ impl Accept for Collateral {
     }
 }
 
+impl Explain for Collateral {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        self.algorithm.explain(self.percent, context)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,6 +172,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         // issuer of note of interest
         // has 90% reserves of note of interest
@@ -119,6 +209,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner5".to_owned(),
             signers: vec![issuer_pk.clone(), signer_pk.clone()],
+            reserves: vec![],
         };
         // issuer, only has 10% reserves for note of interest
         // which is not enough
@@ -132,6 +223,7 @@ mod tests {
             issuer: signer_pk.clone(),
             owner: "owner5".to_owned(),
             signers: vec![signer_pk.clone()],
+            reserves: vec![],
         };
         // has 1000 reserves, has one note worth 1000
         // thus has 100% collateral
@@ -168,6 +260,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner5".to_owned(),
             signers: vec![issuer_pk.clone(), signer_pk.clone()],
+            reserves: vec![],
         };
         // issuer only has 10% collateral
         let issuer = TestAgent {
@@ -180,6 +273,7 @@ mod tests {
             issuer: signer_pk.clone(),
             owner: "owner5".to_owned(),
             signers: vec![signer_pk.clone()],
+            reserves: vec![],
         };
         // this signer note is 100% collaterized, which meets the `percent` requirement
         // but it is not the highest valued note issued by signer so it is not considered.
@@ -188,6 +282,7 @@ mod tests {
             issuer: signer_pk.clone(),
             owner: "owner5".to_owned(),
             signers: vec![signer_pk.clone()],
+            reserves: vec![],
         };
         // signer has 800 reserves but their max value note is 1000 value
         // 80% collateral - we require 100%
@@ -210,4 +305,67 @@ mod tests {
         // not acceptable, issuer and signer dont have 100% collateral
         assert!(!p.accept(&context))
     }
+
+    #[test]
+    fn test_explain_initial_records_issuer_and_signers() {
+        let issuer_pk = "issuer1".to_owned();
+        let signer_pk = "signer2".to_owned();
+        let note_of_interest = NoteContext {
+            nanoerg: 1000,
+            issuer: issuer_pk.clone(),
+            owner: "owner5".to_owned(),
+            signers: vec![issuer_pk.clone(), signer_pk.clone(), "signer3".to_owned()],
+            reserves: vec![],
+        };
+        let issuer = TestAgent {
+            pk: issuer_pk.clone(),
+            issued_notes: vec![note_of_interest.clone()],
+            reserves: 100,
+        };
+        let signer = TestAgent {
+            pk: signer_pk.clone(),
+            issued_notes: vec![NoteContext {
+                nanoerg: 1000,
+                issuer: signer_pk.clone(),
+                owner: "owner5".to_owned(),
+                signers: vec![signer_pk.clone()],
+                reserves: vec![],
+            }],
+            reserves: 1000,
+        };
+        let context = PredicateContext {
+            note: note_of_interest,
+            provider: TestContextProvider {
+                agents: vec![issuer, signer],
+            },
+        };
+        let p = Collateral {
+            percent: 100,
+            algorithm: CollateralAlgorithm::Initial,
+        };
+        let trace = p.explain(&context);
+        assert_eq!(trace.accepted, p.accept(&context));
+        assert!(trace.accepted);
+        let TraceDetail::Collateral {
+            required_percent,
+            issuer,
+            signers,
+            ..
+        } = trace.detail
+        else {
+            panic!("expected collateral trace");
+        };
+        assert_eq!(required_percent, 100);
+        assert_eq!(issuer.agent, issuer_pk);
+        assert_eq!(issuer.percent, Some(10.0));
+        assert!(!issuer.sufficient);
+        assert_eq!(signers.len(), 2);
+        assert_eq!(signers[0].agent, signer_pk);
+        assert_eq!(signers[0].percent, Some(100.0));
+        assert!(signers[0].sufficient);
+        // signer3 has issued no notes
+        assert_eq!(signers[1].notes_nanoerg, 0);
+        assert_eq!(signers[1].percent, None);
+        assert!(!signers[1].sufficient);
+    }
 }
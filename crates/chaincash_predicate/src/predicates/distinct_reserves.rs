@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+
+use crate::context::{ContextProvider, PredicateContext};
+use crate::predicates::{within, Accept, Explain};
+use crate::trace::{Trace, TraceDetail};
+use serde::{Deserialize, Serialize};
+
+/// Accepts notes backed by a number of distinct reserves within inclusive bounds. An agent may back several ownership
+/// entries with the same reserve, which is counted once
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DistinctReserves {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) min: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max: Option<usize>,
+}
+
+fn distinct_reserves<P: ContextProvider>(context: &PredicateContext<P>) -> usize {
+    context.note.reserves.iter().collect::<HashSet<_>>().len()
+}
+
+impl Accept for DistinctReserves {
+    fn accept<P: ContextProvider>(&self, context: &PredicateContext<P>) -> bool {
+        within(distinct_reserves(context), self.min, self.max)
+    }
+}
+
+impl Explain for DistinctReserves {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        let reserves = distinct_reserves(context);
+        Trace {
+            accepted: within(reserves, self.min, self.max),
+            detail: TraceDetail::DistinctReserves {
+                reserves,
+                min: self.min,
+                max: self.max,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{test_util::TestContextProvider, NoteContext};
+
+    #[test]
+    fn test_counts_reserves_once() {
+        let note = NoteContext {
+            nanoerg: 1000,
+            issuer: "issuer1".to_owned(),
+            owner: "owner1".to_owned(),
+            signers: vec![
+                "issuer1".to_owned(),
+                "signer1".to_owned(),
+                "issuer1".to_owned(),
+            ],
+            reserves: vec![
+                "reserve1".to_owned(),
+                "reserve2".to_owned(),
+                "reserve1".to_owned(),
+            ],
+        };
+        let provider = TestContextProvider { agents: vec![] };
+        let context = PredicateContext { note, provider };
+        let p = DistinctReserves {
+            min: None,
+            max: Some(2),
+        };
+        assert!(p.accept(&context));
+        let p = DistinctReserves {
+            min: Some(3),
+            max: None,
+        };
+        assert!(!p.accept(&context));
+
+        let trace = p.explain(&context);
+        assert!(!trace.accepted);
+        assert!(matches!(
+            trace.detail,
+            TraceDetail::DistinctReserves { reserves: 2, .. }
+        ));
+    }
+}
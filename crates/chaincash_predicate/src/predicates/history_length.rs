@@ -0,0 +1,81 @@
+use crate::context::{ContextProvider, PredicateContext};
+use crate::predicates::{within, Accept, Explain};
+use crate::trace::{Trace, TraceDetail};
+use serde::{Deserialize, Serialize};
+
+/// Accepts notes whose number of ownership entries is within inclusive bounds. Note that was never spent by its issuer
+/// has no ownership entries
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct HistoryLength {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) min: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max: Option<usize>,
+}
+
+impl Accept for HistoryLength {
+    fn accept<P: ContextProvider>(&self, context: &PredicateContext<P>) -> bool {
+        within(context.note.signers.len(), self.min, self.max)
+    }
+}
+
+impl Explain for HistoryLength {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        Trace {
+            accepted: self.accept(context),
+            detail: TraceDetail::HistoryLength {
+                length: context.note.signers.len(),
+                min: self.min,
+                max: self.max,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{test_util::TestContextProvider, NoteContext};
+
+    #[test]
+    fn test_history_length() {
+        let note = NoteContext {
+            nanoerg: 1000,
+            issuer: "issuer1".to_owned(),
+            owner: "owner1".to_owned(),
+            signers: vec![
+                "issuer1".to_owned(),
+                "signer1".to_owned(),
+                "signer2".to_owned(),
+            ],
+            reserves: vec![
+                "reserve1".to_owned(),
+                "reserve2".to_owned(),
+                "reserve1".to_owned(),
+            ],
+        };
+        let provider = TestContextProvider { agents: vec![] };
+        let context = PredicateContext { note, provider };
+        let p = HistoryLength {
+            min: None,
+            max: Some(3),
+        };
+        assert!(p.accept(&context));
+        let p = HistoryLength {
+            min: None,
+            max: Some(2),
+        };
+        assert!(!p.accept(&context));
+        let p = HistoryLength {
+            min: Some(4),
+            max: None,
+        };
+        assert!(!p.accept(&context));
+
+        let trace = p.explain(&context);
+        assert!(matches!(
+            trace.detail,
+            TraceDetail::HistoryLength { length: 3, .. }
+        ));
+    }
+}
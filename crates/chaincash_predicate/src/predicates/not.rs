@@ -0,0 +1,61 @@
+use crate::context::{ContextProvider, PredicateContext};
+use crate::predicates::{Accept, Explain, Predicate};
+use crate::trace::{Trace, TraceDetail};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Not {
+    condition: Box<Predicate>,
+}
+
+impl Accept for Not {
+    fn accept<P: ContextProvider>(&self, context: &PredicateContext<P>) -> bool {
+        !self.condition.accept(context)
+    }
+}
+
+impl Explain for Not {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        let condition = self.condition.explain(context);
+        Trace {
+            accepted: !condition.accepted,
+            detail: TraceDetail::Not {
+                condition: Box::new(condition),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{test_util::TestContextProvider, NoteContext};
+    use crate::predicates::whitelist::{Whitelist, WhitelistKind};
+
+    #[test]
+    fn test_negates_condition() {
+        let note = NoteContext {
+            nanoerg: 1000,
+            issuer: "issuer1".to_owned(),
+            owner: "owner1".to_owned(),
+            signers: vec!["issuer1".to_owned()],
+            reserves: vec![],
+        };
+        let provider = TestContextProvider { agents: vec![] };
+        let context = PredicateContext { note, provider };
+        let p = Not {
+            condition: Box::new(Predicate::Whitelist(Whitelist {
+                agents: vec!["owner1".to_owned()],
+                kind: WhitelistKind::Owner,
+            })),
+        };
+        assert!(!p.accept(&context));
+
+        let trace = p.explain(&context);
+        assert!(!trace.accepted);
+        assert!(matches!(
+            trace.detail,
+            TraceDetail::Not { condition } if condition.accepted
+        ));
+    }
+}
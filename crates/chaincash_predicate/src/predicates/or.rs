@@ -1,5 +1,6 @@
 use crate::context::{ContextProvider, PredicateContext};
-use crate::predicates::{Accept, Predicate};
+use crate::predicates::{Accept, Explain, Predicate};
+use crate::trace::{Trace, TraceDetail};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -19,6 +20,20 @@ impl Accept for Or {
     }
 }
 
+impl Explain for Or {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        let conditions: Vec<Trace> = self
+            .conditions
+            .iter()
+            .map(|condition| condition.explain(context))
+            .collect();
+        Trace {
+            accepted: conditions.iter().any(|trace| trace.accepted),
+            detail: TraceDetail::Or { conditions },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -33,6 +48,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -62,6 +78,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -82,4 +99,42 @@ mod tests {
 
         assert!(!p.accept(&context))
     }
+
+    #[test]
+    fn test_explain_includes_every_condition() {
+        let note = NoteContext {
+            nanoerg: 1000,
+            issuer: "issuer1".to_owned(),
+            owner: "owner1".to_owned(),
+            signers: vec!["issuer1".to_owned()],
+            reserves: vec![],
+        };
+        let provider = TestContextProvider { agents: vec![] };
+        let context = PredicateContext { note, provider };
+        let p = Or {
+            conditions: vec![
+                Predicate::Whitelist(Whitelist {
+                    agents: vec!["owner1".to_string()],
+                    kind: WhitelistKind::Owner,
+                }),
+                Predicate::Whitelist(Whitelist {
+                    agents: vec!["PK0".to_string()],
+                    kind: WhitelistKind::Issuer,
+                }),
+            ],
+        };
+
+        let trace = p.explain(&context);
+        assert!(trace.accepted);
+        let TraceDetail::Or { conditions } = trace.detail else {
+            panic!("expected or trace");
+        };
+        assert_eq!(conditions.len(), 2);
+        assert!(conditions[0].accepted);
+        assert!(matches!(
+            &conditions[0].detail,
+            TraceDetail::Whitelist { matched, .. } if matched == &["owner1".to_owned()]
+        ));
+        assert!(!conditions[1].accepted);
+    }
 }
@@ -0,0 +1,83 @@
+use crate::context::{ContextProvider, NanoErg, PredicateContext};
+use crate::predicates::{within, Accept, Explain};
+use crate::trace::{Trace, TraceDetail};
+use serde::{Deserialize, Serialize};
+
+/// Accepts notes whose nanoERG value is within inclusive bounds
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Value {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) min: Option<NanoErg>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max: Option<NanoErg>,
+}
+
+impl Accept for Value {
+    fn accept<P: ContextProvider>(&self, context: &PredicateContext<P>) -> bool {
+        within(context.note.nanoerg, self.min, self.max)
+    }
+}
+
+impl Explain for Value {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        Trace {
+            accepted: self.accept(context),
+            detail: TraceDetail::Value {
+                nanoerg: context.note.nanoerg,
+                min: self.min,
+                max: self.max,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{test_util::TestContextProvider, NoteContext};
+
+    fn context(nanoerg: NanoErg) -> PredicateContext<TestContextProvider> {
+        let note = NoteContext {
+            nanoerg,
+            issuer: "issuer1".to_owned(),
+            owner: "owner1".to_owned(),
+            signers: vec![],
+            reserves: vec![],
+        };
+        let provider = TestContextProvider { agents: vec![] };
+        PredicateContext { note, provider }
+    }
+
+    #[test]
+    fn test_bounds_inclusive() {
+        let p = Value {
+            min: Some(1000),
+            max: Some(2000),
+        };
+        assert!(!p.accept(&context(999)));
+        assert!(p.accept(&context(1000)));
+        assert!(p.accept(&context(2000)));
+        assert!(!p.accept(&context(2001)));
+    }
+
+    #[test]
+    fn test_missing_bound_unchecked() {
+        let p = Value {
+            min: None,
+            max: Some(2000),
+        };
+        assert!(p.accept(&context(0)));
+        assert!(!p.accept(&context(2001)));
+
+        let trace = p.explain(&context(2001));
+        assert!(!trace.accepted);
+        assert!(matches!(
+            trace.detail,
+            TraceDetail::Value {
+                nanoerg: 2001,
+                min: None,
+                max: Some(2000)
+            }
+        ));
+    }
+}
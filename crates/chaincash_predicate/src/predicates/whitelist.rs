@@ -1,5 +1,6 @@
 use crate::context::{ContextProvider, PredicateContext};
-use crate::predicates::Accept;
+use crate::predicates::{Accept, Explain};
+use crate::trace::{Trace, TraceDetail};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -28,6 +29,35 @@ impl Accept for Whitelist {
     }
 }
 
+impl Whitelist {
+    /// Agents of the note matching `agents`, depending on kind
+    fn matched<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Vec<String> {
+        let candidates = match self.kind {
+            WhitelistKind::Issuer => std::slice::from_ref(&context.note.issuer),
+            WhitelistKind::Owner => std::slice::from_ref(&context.note.owner),
+            WhitelistKind::Historical => &context.note.signers[..],
+        };
+        candidates
+            .iter()
+            .filter(|agent| self.agents.contains(agent))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Explain for Whitelist {
+    fn explain<P: ContextProvider>(&self, context: &PredicateContext<P>) -> Trace {
+        let matched = self.matched(context);
+        Trace {
+            accepted: !matched.is_empty(),
+            detail: TraceDetail::Whitelist {
+                kind: self.kind.clone(),
+                matched,
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -41,6 +71,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -60,6 +91,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -78,6 +110,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -97,6 +130,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -119,6 +153,7 @@ mod tests {
                 "signer1".to_owned(),
                 "next_owner".to_owned(),
             ],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -138,6 +173,7 @@ mod tests {
             issuer: issuer_pk.clone(),
             owner: "owner1".to_owned(),
             signers: vec![issuer_pk.clone(), "another1".to_owned()],
+            reserves: vec![],
         };
         let provider = TestContextProvider { agents: vec![] };
         let context = PredicateContext { note, provider };
@@ -0,0 +1,308 @@
+//! Explainable predicate evaluation. A [`Trace`] mirrors the structure of the evaluated predicate and records why each
+//! part of it accepted or rejected the note.
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::context::{NanoErg, PubKeyHex};
+use crate::predicates::blacklist::BlacklistKind;
+use crate::predicates::collateral::CollateralAlgorithm;
+use crate::predicates::whitelist::WhitelistKind;
+
+/// Result of evaluating a predicate with the details that decided it
+#[derive(Serialize, Debug, Clone)]
+pub struct Trace {
+    pub accepted: bool,
+    #[serde(flatten)]
+    pub detail: TraceDetail,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceDetail {
+    Or {
+        /// Traces of every condition, in configured order
+        conditions: Vec<Trace>,
+    },
+    And {
+        /// Traces of every condition, in configured order
+        conditions: Vec<Trace>,
+    },
+    Not {
+        condition: Box<Trace>,
+    },
+    AtLeast {
+        k: usize,
+        /// Number of conditions that accepted the note
+        accepted_count: usize,
+        conditions: Vec<Trace>,
+    },
+    Whitelist {
+        kind: WhitelistKind,
+        /// Agents of the note that are whitelisted
+        matched: Vec<PubKeyHex>,
+    },
+    Blacklist {
+        kind: BlacklistKind,
+        /// Agents of the note that are blacklisted
+        matched: Vec<PubKeyHex>,
+    },
+    Collateral {
+        algorithm: CollateralAlgorithm,
+        /// Collateral percent required by predicate
+        required_percent: u16,
+        issuer: AgentCollateral,
+        /// Signers after the issuer, in order of the note history
+        signers: Vec<AgentCollateral>,
+    },
+    Value {
+        /// Value of the note
+        nanoerg: NanoErg,
+        min: Option<NanoErg>,
+        max: Option<NanoErg>,
+    },
+    HistoryLength {
+        /// Number of ownership entries of the note
+        length: usize,
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    DistinctReserves {
+        /// Number of distinct reserves backing the note
+        reserves: usize,
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+}
+
+/// Collateralization of an agent as computed by [`CollateralAlgorithm`]
+#[derive(Serialize, Debug, Clone)]
+pub struct AgentCollateral {
+    pub agent: PubKeyHex,
+    pub reserves_nanoerg: NanoErg,
+    /// Value of notes the reserves are compared to: every note issued by the issuer, or the highest value note issued by
+    /// another signer
+    pub notes_nanoerg: NanoErg,
+    /// Reserves as percent of notes value, not set if agent has no issued notes
+    pub percent: Option<f64>,
+    /// Whether agent alone satisfies the required collateral
+    pub sufficient: bool,
+}
+
+impl Trace {
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        let result = if self.accepted {
+            "accepted"
+        } else {
+            "rejected"
+        };
+        match &self.detail {
+            TraceDetail::Or { conditions } => {
+                writeln!(f, "{indent}{result}: or")?;
+                for condition in conditions {
+                    condition.fmt_indented(f, depth + 1)?;
+                }
+                Ok(())
+            }
+            TraceDetail::And { conditions } => {
+                writeln!(f, "{indent}{result}: and")?;
+                for condition in conditions {
+                    condition.fmt_indented(f, depth + 1)?;
+                }
+                Ok(())
+            }
+            TraceDetail::Not { condition } => {
+                writeln!(f, "{indent}{result}: not")?;
+                condition.fmt_indented(f, depth + 1)
+            }
+            TraceDetail::AtLeast {
+                k,
+                accepted_count,
+                conditions,
+            } => {
+                writeln!(
+                    f,
+                    "{indent}{result}: at least {k} of {}, {accepted_count} accepted",
+                    conditions.len()
+                )?;
+                for condition in conditions {
+                    condition.fmt_indented(f, depth + 1)?;
+                }
+                Ok(())
+            }
+            TraceDetail::Whitelist { kind, matched } => writeln!(
+                f,
+                "{indent}{result}: whitelist ({kind:?}), matched [{}]",
+                matched.join(", ")
+            ),
+            TraceDetail::Blacklist { kind, matched } => writeln!(
+                f,
+                "{indent}{result}: blacklist ({kind:?}), matched [{}]",
+                matched.join(", ")
+            ),
+            TraceDetail::Collateral {
+                algorithm,
+                required_percent,
+                issuer,
+                signers,
+            } => {
+                writeln!(
+                    f,
+                    "{indent}{result}: collateral ({algorithm:?}) >= {required_percent}%"
+                )?;
+                issuer.fmt_indented(f, depth + 1, "issuer")?;
+                for signer in signers {
+                    signer.fmt_indented(f, depth + 1, "signer")?;
+                }
+                Ok(())
+            }
+            TraceDetail::Value { nanoerg, min, max } => writeln!(
+                f,
+                "{indent}{result}: value {nanoerg} nanoERG, bounds {}",
+                fmt_bounds(min, max)
+            ),
+            TraceDetail::HistoryLength { length, min, max } => writeln!(
+                f,
+                "{indent}{result}: history length {length}, bounds {}",
+                fmt_bounds(min, max)
+            ),
+            TraceDetail::DistinctReserves { reserves, min, max } => writeln!(
+                f,
+                "{indent}{result}: distinct reserves {reserves}, bounds {}",
+                fmt_bounds(min, max)
+            ),
+        }
+    }
+}
+
+/// Format inclusive bounds as `[min, max]`, a missing bound is shown as `_`
+fn fmt_bounds<T: fmt::Display>(min: &Option<T>, max: &Option<T>) -> String {
+    let bound = |bound: &Option<T>| {
+        bound
+            .as_ref()
+            .map(|bound| bound.to_string())
+            .unwrap_or_else(|| "_".to_owned())
+    };
+    format!("[{}, {}]", bound(min), bound(max))
+}
+
+impl AgentCollateral {
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize, role: &str) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        let percent = self
+            .percent
+            .map(|percent| format!("{percent:.2}%"))
+            .unwrap_or_else(|| "no notes".to_owned());
+        writeln!(
+            f,
+            "{indent}{role} {}: {percent} (reserves {} nanoERG, notes {} nanoERG){}",
+            self.agent,
+            self.reserves_nanoerg,
+            self.notes_nanoerg,
+            if self.sufficient { ", sufficient" } else { "" }
+        )
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collateral_trace() -> Trace {
+        Trace {
+            accepted: false,
+            detail: TraceDetail::Or {
+                conditions: vec![
+                    Trace {
+                        accepted: false,
+                        detail: TraceDetail::Blacklist {
+                            kind: BlacklistKind::Issuer,
+                            matched: vec!["issuer1".to_owned()],
+                        },
+                    },
+                    Trace {
+                        accepted: false,
+                        detail: TraceDetail::Collateral {
+                            algorithm: CollateralAlgorithm::Initial,
+                            required_percent: 100,
+                            issuer: AgentCollateral {
+                                agent: "issuer1".to_owned(),
+                                reserves_nanoerg: 500,
+                                notes_nanoerg: 1000,
+                                percent: Some(50.0),
+                                sufficient: false,
+                            },
+                            signers: vec![],
+                        },
+                    },
+                ],
+            },
+        }
+    }
+
+    #[test]
+    fn test_trace_json() {
+        let json = serde_json::to_value(collateral_trace()).unwrap();
+        assert_eq!(json["type"], "or");
+        assert_eq!(json["accepted"], false);
+        assert_eq!(json["conditions"][0]["type"], "blacklist");
+        assert_eq!(json["conditions"][0]["kind"], "issuer");
+        assert_eq!(json["conditions"][1]["type"], "collateral");
+        assert_eq!(json["conditions"][1]["issuer"]["percent"], 50.0);
+    }
+
+    #[test]
+    fn test_at_least_trace_json() {
+        let TraceDetail::Or { conditions } = collateral_trace().detail else {
+            unreachable!()
+        };
+        let trace = Trace {
+            accepted: false,
+            detail: TraceDetail::AtLeast {
+                k: 2,
+                accepted_count: 0,
+                conditions,
+            },
+        };
+        let json = serde_json::to_value(trace).unwrap();
+        assert_eq!(json["type"], "at_least");
+        assert_eq!(json["accepted"], false);
+        assert_eq!(json["k"], 2);
+        assert_eq!(json["accepted_count"], 0);
+        assert_eq!(json["conditions"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_trace_display() {
+        assert_eq!(
+            collateral_trace().to_string(),
+            "rejected: or\n  \
+             rejected: blacklist (Issuer), matched [issuer1]\n  \
+             rejected: collateral (Initial) >= 100%\n    \
+             issuer issuer1: 50.00% (reserves 500 nanoERG, notes 1000 nanoERG)\n"
+        );
+    }
+
+    #[test]
+    fn test_limit_trace_display() {
+        let trace = Trace {
+            accepted: false,
+            detail: TraceDetail::Value {
+                nanoerg: 2001,
+                min: None,
+                max: Some(2000),
+            },
+        };
+        assert_eq!(
+            trace.to_string(),
+            "rejected: value 2001 nanoERG, bounds [_, 2000]\n"
+        );
+    }
+}
@@ -3,10 +3,17 @@ use std::sync::Arc;
 use axum::{
     extract::State,
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
-use chaincash_services::ServerState;
+use chaincash_offchain::bundle::NoteBundle;
+use chaincash_services::{
+    acceptance::{evaluate_note, NoteSource},
+    bundle::BundleServiceError,
+    ServerState,
+};
+use ergo_lib::ergotree_ir::chain::token::TokenId;
+use serde::Deserialize;
 
 use crate::api::ApiError;
 
@@ -14,6 +21,31 @@ async fn get_acceptance(State(state): State<Arc<ServerState>>) -> Result<Respons
     Ok(Json(&state.predicates).into_response())
 }
 
+/// Note to evaluate, e.g. `{"note_id": 1}`, `{"token_id": "..."}` or `{"bundle": {...}}` with a JSON note bundle
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EvaluateRequest {
+    NoteId(i32),
+    TokenId(TokenId),
+    Bundle(serde_json::Value),
+}
+
+async fn evaluate(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<EvaluateRequest>,
+) -> Result<Response, ApiError> {
+    let source = match body {
+        EvaluateRequest::NoteId(note_id) => NoteSource::NoteId(note_id),
+        EvaluateRequest::TokenId(token_id) => NoteSource::TokenId(token_id),
+        EvaluateRequest::Bundle(bundle) => {
+            NoteSource::Bundle(NoteBundle::from_json(bundle).map_err(BundleServiceError::from)?)
+        }
+    };
+    Ok(Json(evaluate_note(&state, source).await?).into_response())
+}
+
 pub fn router() -> Router<Arc<ServerState>> {
-    Router::new().route("/", get(get_acceptance))
+    Router::new()
+        .route("/", get(get_acceptance))
+        .route("/evaluate", post(evaluate))
 }
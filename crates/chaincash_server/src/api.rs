@@ -3,6 +3,9 @@ use std::sync::Arc;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::{Json, Router};
+use chaincash_services::acceptance::AcceptanceError;
+use chaincash_services::bundle::BundleServiceError;
+use chaincash_services::transaction::TransactionServiceError;
 use chaincash_services::ServerState;
 use serde_json::json;
 use thiserror::Error;
@@ -29,13 +32,45 @@ pub enum ApiError {
     TransactionService(#[from] chaincash_services::transaction::TransactionServiceError),
     #[error("Store error: {0}")]
     StoreError(#[from] chaincash_store::Error),
+    #[error("Note bundle error: {0}")]
+    Bundle(#[from] chaincash_services::bundle::BundleServiceError),
+    #[error("Note acceptance error: {0}")]
+    Acceptance(#[from] chaincash_services::acceptance::AcceptanceError),
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status_code, msg) = match self {
-            ApiError::TransactionService(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            ApiError::TransactionService(e) => {
+                let status_code = match e {
+                    TransactionServiceError::OwnerKeyNotInWallet(_) => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status_code, e.to_string())
+            }
             ApiError::StoreError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            ApiError::Bundle(e) => {
+                let status_code = match e {
+                    BundleServiceError::AlreadyImported(_) => StatusCode::CONFLICT,
+                    BundleServiceError::Store(_) | BundleServiceError::Chain(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                    _ => StatusCode::BAD_REQUEST,
+                };
+                (status_code, e.to_string())
+            }
+            ApiError::Acceptance(e) => {
+                let status_code = match e {
+                    AcceptanceError::Bundle(
+                        BundleServiceError::Store(_) | BundleServiceError::Chain(_),
+                    ) => StatusCode::INTERNAL_SERVER_ERROR,
+                    AcceptanceError::Bundle(_)
+                    | AcceptanceError::UnknownNote(_)
+                    | AcceptanceError::UnknownNoteId(_) => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status_code, e.to_string())
+            }
         };
         let body = Json(json!({
             "error": {
@@ -51,7 +86,9 @@ pub fn router() -> Router<Arc<ServerState>> {
     let router_v1 = Router::new()
         .nest("/reserves", crate::reserves::router())
         .nest("/notes", crate::notes::router())
-        .nest("/acceptance", crate::acceptance::router());
+        .nest("/receipts", crate::receipts::router())
+        .nest("/acceptance", crate::acceptance::router())
+        .nest("/transactions", crate::transactions::router());
 
     Router::new().nest("/v1", router_v1)
 }
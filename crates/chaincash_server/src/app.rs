@@ -40,34 +40,117 @@ mod tests {
         body::Body,
         http::{Request, StatusCode},
     };
+    use chaincash_mock_node::MockNode;
+    use chaincash_services::parameters::ParametersClient;
     use chaincash_store::ChainCashStore;
+    use ergo_lib::ergotree_ir::chain::{address::NetworkPrefix, token::TokenId};
+    use serde_json::Value;
     use tower::ServiceExt;
 
     use super::*;
 
-    pub fn test_server() -> Arc<ServerState> {
-        // node shouldn't be actually used in unit tests
-        let node = ergo_client::node::NodeClient::from_url_str(
-            "http://127.0.0.1:9052",
-            "hello".to_string(),
-            std::time::Duration::from_secs(5),
-        )
-        .unwrap();
-
-        Arc::new(ServerState::new(
-            node,
-            ChainCashStore::open_in_memory().unwrap(),
-            vec![],
-        ))
+    // Server backed by a mock node, which is stopped once dropped
+    pub async fn test_server() -> (MockNode, Arc<ServerState>) {
+        let node = MockNode::start(NetworkPrefix::Mainnet).await.unwrap();
+        let state = Arc::new(
+            ServerState::new(
+                node.client(),
+                ParametersClient::new(&node.url()).unwrap(),
+                ChainCashStore::open_in_memory().unwrap(),
+                vec![],
+                chaincash_predicate::Combination::default(),
+                chaincash_offchain::network::Config::default(),
+                chaincash_offchain::transactions::fee::FeePolicy::default(),
+                chaincash_offchain::transactions::box_selection::Config::default(),
+            )
+            .unwrap(),
+        );
+        (node, state)
     }
+
     #[tokio::test]
     async fn test_healthcheck() {
+        let (_node, state) = test_server().await;
         let response = Server::router()
-            .with_state(test_server())
+            .with_state(state)
             .oneshot(Request::get("/healthcheck").body(Body::default()).unwrap())
             .await
             .unwrap();
 
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn test_evaluate_unknown_note() {
+        let request = Request::post("/api/v1/acceptance/evaluate")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"token_id": "0000000000000000000000000000000000000000000000000000000000000000"}"#,
+            ))
+            .unwrap();
+        let (_node, state) = test_server().await;
+        let response = Server::router()
+            .with_state(state)
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_unknown_note_id() {
+        let request = Request::post("/api/v1/acceptance/evaluate")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"note_id": 1}"#))
+            .unwrap();
+        let (_node, state) = test_server().await;
+        let response = Server::router()
+            .with_state(state)
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_mint_reserve() {
+        let (node, state) = test_server().await;
+        let owner = {
+            let mut chain = node.chain();
+            chain.add_wallet_box(100_000_000_000, vec![]);
+            chain.wallet_public_key()
+        };
+        let request = Request::post("/api/v1/reserves/mint")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "public_key_hex": String::from(owner),
+                    "amount": 10_000_000_000u64,
+                })
+                .to_string(),
+            ))
+            .unwrap();
+        let response = Server::router()
+            .with_state(state.clone())
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        let reserve_id: TokenId = serde_json::from_value(body["reserveNftId"].clone()).unwrap();
+        let reserve = state
+            .store
+            .reserves()
+            .get_reserve_by_identifier(&reserve_id)
+            .unwrap();
+        assert!(node.chain().box_by_id(&reserve.box_id()).is_ok());
+    }
 }
@@ -25,7 +25,9 @@ pub(crate) mod api;
 pub mod app;
 pub mod error;
 pub(crate) mod notes;
+pub(crate) mod receipts;
 pub(crate) mod reserves;
+pub(crate) mod transactions;
 
 pub use app::Server;
 pub use error::Error;
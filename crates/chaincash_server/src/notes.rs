@@ -1,13 +1,19 @@
 use std::sync::Arc;
 
+use axum::body::Bytes;
 use axum::extract::{Path, State};
+use axum::http::header;
 use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
+use chaincash_offchain::bundle::NoteBundle;
 use chaincash_offchain::transactions::notes::{
     MintNoteRequest, SignedMintNoteResponse, SignedSpendNoteResponse,
 };
-use chaincash_services::transaction::{RedeemNoteRequest, SpendNoteRequest};
+use chaincash_services::bundle::{export_note, import_note, BundleServiceError};
+use chaincash_services::transaction::{
+    RedeemNoteRequest, RedemptionQuoteRequest, SpendNoteRequest,
+};
 use chaincash_services::ServerState;
 use ergo_lib::ergo_chain_types::EcPoint;
 use serde_json::json;
@@ -26,21 +32,39 @@ async fn mint_note(
     Ok(response.into_response())
 }
 
+async fn mint_note_unsigned(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<MintNoteRequest>,
+) -> Result<Response, ApiError> {
+    let transaction = state.tx_service().mint_note_unsigned(body).await?;
+    Ok(Json(json!({ "transaction": transaction })).into_response())
+}
+
 async fn spend_note(
     State(state): State<Arc<ServerState>>,
     Json(body): Json<SpendNoteRequest>,
 ) -> Result<Response, ApiError> {
-    let SignedSpendNoteResponse {
-        transaction,
-        recipient_note: _,
-        change_note: _,
-    } = state.tx_service().spend_note(body).await?;
+    let responses = state.tx_service().spend_note(body).await?;
+    let tx_ids = responses
+        .iter()
+        .map(|SignedSpendNoteResponse { transaction, .. }| transaction.id().to_string())
+        .collect::<Vec<_>>();
     let response = Json(json!({
-        "txId": transaction.id().to_string(),
+        // Last transaction, kept for clients of the single recipient API
+        "txId": tx_ids.last(),
+        "txIds": tx_ids,
     }));
     Ok(response.into_response())
 }
 
+async fn spend_note_unsigned(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<SpendNoteRequest>,
+) -> Result<Response, ApiError> {
+    let transactions = state.tx_service().spend_note_unsigned(body).await?;
+    Ok(Json(json!({ "transactions": transactions })).into_response())
+}
+
 async fn redeem_note(
     State(state): State<Arc<ServerState>>,
     Json(body): Json<RedeemNoteRequest>,
@@ -52,6 +76,22 @@ async fn redeem_note(
     Ok(response.into_response())
 }
 
+async fn redeem_note_unsigned(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<RedeemNoteRequest>,
+) -> Result<Response, ApiError> {
+    let transaction = state.tx_service().redeem_note_unsigned(body).await?;
+    Ok(Json(json!({ "transaction": transaction })).into_response())
+}
+
+async fn redeem_quote(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<RedemptionQuoteRequest>,
+) -> Result<Response, ApiError> {
+    let quotes = state.tx_service().redemption_quotes(body).await?;
+    Ok(Json(json!({ "quotes": quotes })).into_response())
+}
+
 async fn list_wallet_notes(State(state): State<Arc<ServerState>>) -> Result<Response, ApiError> {
     let pubkeys = state.wallet_pubkeys().await?;
     let notes = state.store.notes().notes_by_pubkeys(&pubkeys)?;
@@ -66,11 +106,56 @@ async fn by_pubkey(
     Ok(Json(state.store.notes().notes_by_pubkeys(&[pubkey])?).into_response())
 }
 
+async fn export_note_json(
+    State(state): State<Arc<ServerState>>,
+    Path(note_id): Path<i32>,
+) -> Result<Response, ApiError> {
+    Ok(Json(export_note(&state, note_id)?.to_json()).into_response())
+}
+
+async fn export_note_binary(
+    State(state): State<Arc<ServerState>>,
+    Path(note_id): Path<i32>,
+) -> Result<Response, ApiError> {
+    let bundle = export_note(&state, note_id)?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/octet-stream")],
+        bundle.to_bytes(),
+    )
+        .into_response())
+}
+
+async fn import_note_json(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Response, ApiError> {
+    let bundle = NoteBundle::from_json(body).map_err(BundleServiceError::from)?;
+    let note = import_note(&state, bundle).await?;
+    Ok(Json(json!({ "id": note.id })).into_response())
+}
+
+async fn import_note_binary(
+    State(state): State<Arc<ServerState>>,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    let bundle = NoteBundle::from_bytes(&body).map_err(BundleServiceError::from)?;
+    let note = import_note(&state, bundle).await?;
+    Ok(Json(json!({ "id": note.id })).into_response())
+}
+
 pub fn router() -> Router<Arc<ServerState>> {
     Router::new()
         .route("/wallet", get(list_wallet_notes))
         .route("/byPubkey/:pubkey", get(by_pubkey))
         .route("/spend", post(spend_note))
+        .route("/spend/unsigned", post(spend_note_unsigned))
         .route("/redeem", post(redeem_note))
+        .route("/redeem/unsigned", post(redeem_note_unsigned))
+        .route("/redeem/quote", post(redeem_quote))
         .route("/mint", post(mint_note))
+        .route("/mint/unsigned", post(mint_note_unsigned))
+        .route("/:id/export", get(export_note_json))
+        .route("/:id/export/binary", get(export_note_binary))
+        .route("/import", post(import_note_json))
+        .route("/import/binary", post(import_note_binary))
 }
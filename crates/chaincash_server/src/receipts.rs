@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chaincash_services::transaction::RedeemReceiptRequest;
+use chaincash_services::ServerState;
+use serde_json::json;
+
+use crate::api::ApiError;
+
+async fn list_wallet_receipts(State(state): State<Arc<ServerState>>) -> Result<Response, ApiError> {
+    let pubkeys = state.wallet_pubkeys().await?;
+    Ok(Json(
+        state
+            .store
+            .receipts()
+            .receipts_by_reserve_owners(&pubkeys)?,
+    )
+    .into_response())
+}
+
+async fn list_receipts(State(state): State<Arc<ServerState>>) -> Result<Response, ApiError> {
+    Ok(Json(state.store.receipts().receipts()?).into_response())
+}
+
+async fn redeem_receipt(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<RedeemReceiptRequest>,
+) -> Result<Response, ApiError> {
+    let transaction = state.tx_service().redeem_receipt(body).await?;
+    let response = Json(json!({
+        "txId": transaction.id().to_string(),
+    }));
+    Ok(response.into_response())
+}
+
+pub fn router() -> Router<Arc<ServerState>> {
+    Router::new()
+        .route("/", get(list_receipts))
+        .route("/wallet", get(list_wallet_receipts))
+        .route("/redeem", post(redeem_receipt))
+}
@@ -5,7 +5,9 @@ use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use chaincash_offchain::transactions::reserves::{MintReserveRequest, SignedReserveResponse};
-use chaincash_services::transaction::TopUpReserveRequest;
+use chaincash_services::transaction::{
+    CompleteRefundRequest, ReserveRefundRequest, TopUpReserveRequest,
+};
 use chaincash_services::ServerState;
 use serde_json::json;
 
@@ -27,6 +29,14 @@ async fn mint_reserve(
     Ok(response.into_response())
 }
 
+async fn mint_reserve_unsigned(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<MintReserveRequest>,
+) -> Result<Response, ApiError> {
+    let transaction = state.tx_service().mint_reserve_unsigned(body).await?;
+    Ok(Json(json!({ "transaction": transaction })).into_response())
+}
+
 async fn top_up_reserve(
     State(state): State<Arc<ServerState>>,
     Json(body): Json<TopUpReserveRequest>,
@@ -41,6 +51,57 @@ async fn top_up_reserve(
     Ok(response.into_response())
 }
 
+async fn top_up_reserve_unsigned(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<TopUpReserveRequest>,
+) -> Result<Response, ApiError> {
+    let transaction = state.tx_service().top_up_reserve_unsigned(body).await?;
+    Ok(Json(json!({ "transaction": transaction })).into_response())
+}
+
+async fn initiate_refund(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<ReserveRefundRequest>,
+) -> Result<Response, ApiError> {
+    let SignedReserveResponse {
+        reserve_box,
+        transaction,
+    } = state.tx_service().initiate_refund(body).await?;
+    let response = Json(json!({
+        "txId": transaction.id(),
+        "refundHeight": reserve_box.refund_height,
+    }));
+    Ok(response.into_response())
+}
+
+async fn cancel_refund(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<ReserveRefundRequest>,
+) -> Result<Response, ApiError> {
+    let SignedReserveResponse {
+        reserve_box: _,
+        transaction,
+    } = state.tx_service().cancel_refund(body).await?;
+    let response = Json(json!({
+        "txId": transaction.id(),
+    }));
+    Ok(response.into_response())
+}
+
+async fn complete_refund(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<CompleteRefundRequest>,
+) -> Result<Response, ApiError> {
+    let SignedReserveResponse {
+        reserve_box: _,
+        transaction,
+    } = state.tx_service().complete_refund(body).await?;
+    let response = Json(json!({
+        "txId": transaction.id(),
+    }));
+    Ok(response.into_response())
+}
+
 async fn list_wallet_reserves(State(state): State<Arc<ServerState>>) -> Result<Response, ApiError> {
     Ok(Json(
         state
@@ -54,6 +115,11 @@ async fn list_wallet_reserves(State(state): State<Arc<ServerState>>) -> Result<R
 pub fn router() -> Router<Arc<ServerState>> {
     Router::new()
         .route("/mint", post(mint_reserve))
+        .route("/mint/unsigned", post(mint_reserve_unsigned))
         .route("/topup", post(top_up_reserve))
+        .route("/topup/unsigned", post(top_up_reserve_unsigned))
+        .route("/refund/initiate", post(initiate_refund))
+        .route("/refund/cancel", post(cancel_refund))
+        .route("/refund/complete", post(complete_refund))
         .route("/wallet", get(list_wallet_reserves))
 }
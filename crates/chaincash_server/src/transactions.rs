@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use chaincash_services::ServerState;
+use ergo_lib::chain::transaction::Transaction;
+use serde_json::json;
+
+use crate::api::ApiError;
+
+async fn submit_transaction(
+    State(state): State<Arc<ServerState>>,
+    Json(body): Json<Transaction>,
+) -> Result<Response, ApiError> {
+    let tx_id = state.tx_service().submit(body).await?;
+    let response = Json(json!({
+        "txId": tx_id.to_string(),
+    }));
+    Ok(response.into_response())
+}
+
+pub fn router() -> Router<Arc<ServerState>> {
+    Router::new().route("/submit", post(submit_transaction))
+}
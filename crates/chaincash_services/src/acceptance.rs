@@ -0,0 +1,99 @@
+//! Evaluation of configured acceptance predicates against a note
+
+use chaincash_offchain::{boxes::Note, bundle::NoteBundle};
+use chaincash_predicate::{
+    context::PredicateContext,
+    predicates::{Explain, Predicate},
+    trace::Trace,
+};
+use ergo_lib::ergotree_ir::chain::token::TokenId;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    bundle::{verify_bundle, BundleServiceError},
+    transaction::TransactionServiceError,
+    ServerState,
+};
+
+#[derive(Debug, Error)]
+pub enum AcceptanceError {
+    #[error("Store error: {0}")]
+    Store(#[from] chaincash_store::Error),
+
+    #[error("Note bundle error: {0}")]
+    Bundle(#[from] BundleServiceError),
+
+    #[error("Failed to fetch oracle price: {0}")]
+    Oracle(#[from] TransactionServiceError),
+
+    #[error("Note {0:?} is not tracked")]
+    UnknownNote(TokenId),
+
+    #[error("Note with id {0} is not tracked")]
+    UnknownNoteId(i32),
+}
+
+/// Note to evaluate
+pub enum NoteSource {
+    /// Note tracked in store, by primary key
+    NoteId(i32),
+    /// Note tracked in store, by note token id
+    TokenId(TokenId),
+    /// Note from a bundle offered as payment, it is verified but not added to store
+    Bundle(NoteBundle),
+}
+
+#[derive(Serialize, Debug)]
+pub struct AcceptanceDecision {
+    pub accepted: bool,
+    /// Index of the configured predicate that decided the result, see [`chaincash_predicate::Combination::decide`]
+    pub predicate_index: Option<usize>,
+    pub predicate: Option<Predicate>,
+    /// Evaluation trace of every configured predicate, in configured order
+    pub traces: Vec<Trace>,
+}
+
+async fn resolve_note(state: &ServerState, source: NoteSource) -> Result<Note, AcceptanceError> {
+    match source {
+        NoteSource::NoteId(note_id) => match state.store.notes().get_note_box(note_id) {
+            Err(e) if e.is_not_found() => Err(AcceptanceError::UnknownNoteId(note_id)),
+            note => Ok(note?),
+        },
+        NoteSource::TokenId(token_id) => state
+            .store
+            .notes()
+            .get_by_identifier(&token_id)?
+            .map(|(_, note)| note)
+            .ok_or(AcceptanceError::UnknownNote(token_id)),
+        NoteSource::Bundle(bundle) => Ok(verify_bundle(state, bundle).await?.0),
+    }
+}
+
+/// Run configured predicates against note and combine their results as configured. Every predicate is evaluated so the
+/// decision can be explained
+pub async fn evaluate_note(
+    state: &ServerState,
+    source: NoteSource,
+) -> Result<AcceptanceDecision, AcceptanceError> {
+    let note = resolve_note(state, source).await?;
+    let provider = state.context_provider().await?;
+    let context = PredicateContext {
+        note: provider.note_box_context(&note),
+        provider,
+    };
+    let traces: Vec<Trace> = state
+        .predicates
+        .iter()
+        .map(|predicate| predicate.explain(&context))
+        .collect();
+    let (accepted, predicate_index) = state
+        .combine
+        .decide(traces.iter().map(|trace| trace.accepted));
+    Ok(AcceptanceDecision {
+        accepted,
+        predicate_index,
+        predicate: predicate_index.map(|i| state.predicates[i].clone()),
+        traces,
+    })
+}
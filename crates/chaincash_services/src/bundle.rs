@@ -0,0 +1,140 @@
+//! Export and import of portable note bundles
+
+use chaincash_offchain::{
+    boxes::{self, ReserveBoxSpec},
+    bundle::{BundleError, NoteBundle},
+};
+use chaincash_store::notes::Note;
+use ergo_lib::ergotree_ir::chain::{ergo_box::BoxId, token::TokenId};
+use thiserror::Error;
+
+use crate::{
+    transaction::{TransactionService, TransactionServiceError},
+    ServerState,
+};
+
+#[derive(Debug, Error)]
+pub enum BundleServiceError {
+    #[error("Invalid note bundle: {0}")]
+    Bundle(#[from] BundleError),
+
+    #[error("Store error: {0}")]
+    Store(#[from] chaincash_store::Error),
+
+    #[error("Note box in bundle is not protected by note contract")]
+    InvalidNoteContract,
+
+    #[error("Box for reserve {0:?} in bundle is not protected by reserve contract")]
+    InvalidReserveContract(TokenId),
+
+    #[error("Failed to look up bundle boxes on chain: {0}")]
+    Chain(#[from] TransactionServiceError),
+
+    #[error("Note box {0} in bundle is not unspent on chain")]
+    NoteNotUnspent(BoxId),
+
+    #[error("Reserve {0:?} in bundle does not match reserve on chain")]
+    ReserveMismatch(TokenId),
+
+    #[error("Note box {0} is already tracked")]
+    AlreadyImported(BoxId),
+}
+
+/// Export note with its history and all reserves referenced by it
+pub fn export_note(state: &ServerState, note_id: i32) -> Result<NoteBundle, BundleServiceError> {
+    let note = state.store.notes().get_note_box(note_id)?;
+    let mut reserve_ids: Vec<TokenId> = vec![];
+    for entry in note.history.ownership_entries() {
+        if !reserve_ids.contains(&entry.reserve_id) {
+            reserve_ids.push(entry.reserve_id);
+        }
+    }
+    let reserves = reserve_ids
+        .iter()
+        .map(|reserve_id| state.store.reserves().get_reserve_by_identifier(reserve_id))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(NoteBundle::new(&note, &reserves))
+}
+
+/// Verify note bundle, that its note and reserve boxes are protected by ChainCash contracts and that they match the chain.
+/// Note box must be unspent. Reserve boxes may be outdated, so the current box holding each reserve NFT is fetched, checked to
+/// have the same owner and returned instead of the bundle's copy
+pub async fn verify_bundle(
+    state: &ServerState,
+    bundle: NoteBundle,
+) -> Result<(boxes::Note, Vec<ReserveBoxSpec>), BundleServiceError> {
+    let (note, reserves) = bundle.into_note()?;
+    if note.ergo_box().ergo_tree != *state.compiler.note_contract() {
+        return Err(BundleServiceError::InvalidNoteContract);
+    }
+    let tx_service = state.tx_service();
+    let note_box_id = note.ergo_box().box_id();
+    // Splitting a note leaves several unspent boxes holding its token
+    if !tx_service
+        .unspent_boxes_by_token_id(note.note_id)
+        .await?
+        .iter()
+        .any(|unspent| unspent == note.ergo_box())
+    {
+        return Err(BundleServiceError::NoteNotUnspent(note_box_id));
+    }
+    let mut chain_reserves = Vec::with_capacity(reserves.len());
+    for reserve in &reserves {
+        chain_reserves.push(chain_reserve(state, &tx_service, reserve).await?);
+    }
+    Ok((note, chain_reserves))
+}
+
+// Fetch current box of `reserve` from chain, checking that it is the same reserve
+async fn chain_reserve(
+    state: &ServerState,
+    tx_service: &TransactionService<'_>,
+    reserve: &ReserveBoxSpec,
+) -> Result<ReserveBoxSpec, BundleServiceError> {
+    let reserve_contract = state.compiler.reserve_contract();
+    if reserve.ergo_box().ergo_tree != *reserve_contract {
+        return Err(BundleServiceError::InvalidReserveContract(
+            reserve.identifier,
+        ));
+    }
+    let chain_box = match tx_service.unspent_box_by_token_id(reserve.identifier).await {
+        Ok(chain_box) => chain_box,
+        Err(TransactionServiceError::MissingBox(_)) => {
+            return Err(BundleServiceError::ReserveMismatch(reserve.identifier))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if chain_box.ergo_tree != *reserve_contract {
+        return Err(BundleServiceError::ReserveMismatch(reserve.identifier));
+    }
+    let chain_reserve = ReserveBoxSpec::try_from(&chain_box)
+        .map_err(|_| BundleServiceError::ReserveMismatch(reserve.identifier))?;
+    if chain_reserve.identifier != reserve.identifier || chain_reserve.owner != reserve.owner {
+        return Err(BundleServiceError::ReserveMismatch(reserve.identifier));
+    }
+    Ok(chain_reserve)
+}
+
+/// Verify note bundle and add the note to store. Reserves are added only if they aren't tracked yet, reserves already tracked
+/// are kept as they are
+pub async fn import_note(
+    state: &ServerState,
+    bundle: NoteBundle,
+) -> Result<Note, BundleServiceError> {
+    let (note, reserves) = verify_bundle(state, bundle).await?;
+    let note_box_id = note.ergo_box().box_id();
+    if state.store.notes().get_by_box_id(&note_box_id)?.is_some() {
+        return Err(BundleServiceError::AlreadyImported(note_box_id));
+    }
+    for reserve in &reserves {
+        if state
+            .store
+            .reserves()
+            .get_reserve_by_identifier(&reserve.identifier)
+            .is_err()
+        {
+            state.store.reserves().add_or_update(reserve)?;
+        }
+    }
+    Ok(state.store.notes().add_note(&note)?)
+}
@@ -0,0 +1,101 @@
+//! Predicate context backed by notes and reserves tracked in [`ChainCashStore`]
+use chaincash_offchain::boxes::Note;
+use chaincash_predicate::context::{ContextProvider, NanoErg, NoteContext, PubKeyHex};
+use chaincash_store::{notes::NoteWithHistory, ChainCashStore};
+use ergo_lib::ergo_chain_types::EcPoint;
+use tracing::warn;
+
+/// [`ContextProvider`] answering predicate queries from the store. Note values are in mg of gold and are converted to
+/// nanoERG with the oracle price the provider was created with.
+/// Store errors can't be returned through [`ContextProvider`], so they are logged and the agent is treated as having no notes or reserves
+pub struct StoreContextProvider<'a> {
+    store: &'a ChainCashStore,
+    nanoerg_per_mg: u64,
+}
+
+impl<'a> StoreContextProvider<'a> {
+    pub fn new(store: &'a ChainCashStore, nanoerg_per_mg: u64) -> Self {
+        Self {
+            store,
+            nanoerg_per_mg,
+        }
+    }
+
+    /// Build context of `note` for predicate evaluation
+    pub fn note_box_context(&self, note: &Note) -> NoteContext {
+        let signers: Vec<PubKeyHex> = note
+            .history
+            .ownership_entries()
+            .iter()
+            .map(|entry| entry.signer.to_string())
+            .collect();
+        NoteContext {
+            nanoerg: (*note.amount.as_u64()).saturating_mul(self.nanoerg_per_mg),
+            owner: note.owner.to_string(),
+            // Issuer signs first ownership entry when spending note, or still owns it if note was never spent
+            issuer: signers
+                .first()
+                .cloned()
+                .unwrap_or_else(|| note.owner.to_string()),
+            signers,
+            reserves: note
+                .history
+                .ownership_entries()
+                .iter()
+                .map(|entry| String::from(entry.reserve_id))
+                .collect(),
+        }
+    }
+
+    /// Build context of note tracked in store for predicate evaluation
+    pub fn note_context(&self, note: &NoteWithHistory) -> NoteContext {
+        NoteContext {
+            nanoerg: (note.note.value as u64).saturating_mul(self.nanoerg_per_mg),
+            owner: note.note.owner.clone(),
+            issuer: note.issuer().to_owned(),
+            signers: note.signers(),
+            reserves: note.reserves(),
+        }
+    }
+}
+
+fn parse_agent(agent: &str) -> Option<EcPoint> {
+    EcPoint::try_from(agent.to_owned())
+        .map_err(|e| warn!("Invalid agent public key {agent}: {e}"))
+        .ok()
+}
+
+impl ContextProvider for StoreContextProvider<'_> {
+    fn agent_issued_notes(&self, agent: &str) -> Vec<NoteContext> {
+        let Some(agent) = parse_agent(agent) else {
+            return vec![];
+        };
+        match self.store.notes().notes_by_issuer(&agent) {
+            Ok(notes) => notes.iter().map(|note| self.note_context(note)).collect(),
+            Err(e) => {
+                warn!("Failed to load notes issued by {agent:?}: {e}");
+                vec![]
+            }
+        }
+    }
+
+    fn agent_reserves_nanoerg(&self, agent: &str) -> NanoErg {
+        let Some(agent) = parse_agent(agent) else {
+            return 0;
+        };
+        match self
+            .store
+            .reserves()
+            .reserve_boxes_by_pubkeys(&[agent.clone()])
+        {
+            Ok(reserves) => reserves
+                .iter()
+                .map(|reserve| reserve.ergo_box().value.as_u64())
+                .sum(),
+            Err(e) => {
+                warn!("Failed to load reserves of {agent:?}: {e}");
+                0
+            }
+        }
+    }
+}
@@ -1,30 +1,72 @@
-use chaincash_predicate::predicates::Predicate;
+use std::sync::Arc;
+
+use chaincash_offchain::{
+    contracts::ContractError,
+    network,
+    transactions::{
+        box_selection::{self, WalletBoxSelector},
+        fee::FeePolicy,
+    },
+};
+use chaincash_predicate::{predicates::Predicate, Combination};
 use chaincash_store::ChainCashStore;
 use compiler::Compiler;
+use context::StoreContextProvider;
 use ergo_client::node::NodeClient;
 use ergo_lib::{ergo_chain_types::EcPoint, ergotree_ir::chain::address::Address};
-use transaction::{TransactionService, TransactionServiceError};
+use parameters::ParametersClient;
+use transaction::{PendingTransactions, TransactionService, TransactionServiceError};
 
+pub mod acceptance;
+pub mod bundle;
 pub mod compiler;
+pub mod context;
+pub mod parameters;
 pub mod scanner;
 pub mod transaction;
 
+#[cfg(test)]
+pub(crate) mod test_util;
+
 #[derive(Clone)]
 pub struct ServerState {
     pub store: ChainCashStore,
     pub node: NodeClient,
+    /// Fetches blockchain parameters from node, which `node` doesn't support
+    parameters: ParametersClient,
     compiler: Compiler,
     pub predicates: Vec<Predicate>,
+    /// How results of `predicates` are combined to accept a note
+    pub combine: Combination,
+    pub network: network::Config,
+    pub fee_policy: FeePolicy,
+    pub box_selector: Arc<dyn WalletBoxSelector>,
+    pending: Arc<PendingTransactions>,
 }
 
 impl ServerState {
-    pub fn new(node: NodeClient, store: ChainCashStore, predicates: Vec<Predicate>) -> Self {
-        ServerState {
-            compiler: Compiler::new(node.clone()),
+    pub fn new(
+        node: NodeClient,
+        parameters: ParametersClient,
+        store: ChainCashStore,
+        predicates: Vec<Predicate>,
+        combine: Combination,
+        network: network::Config,
+        fee_policy: FeePolicy,
+        box_selection: box_selection::Config,
+    ) -> Result<Self, ContractError> {
+        Ok(ServerState {
+            compiler: Compiler::new(&network)?,
             node,
+            parameters,
             store,
             predicates,
-        }
+            combine,
+            network,
+            fee_policy,
+            box_selector: box_selection.selector(),
+            pending: Arc::default(),
+        })
     }
 
     pub async fn wallet_pubkeys(&self) -> Result<Vec<EcPoint>, TransactionServiceError> {
@@ -42,7 +84,24 @@ impl ServerState {
             .collect())
     }
 
+    /// Context provider for evaluating acceptance predicates against the store, pricing notes with the current oracle price
+    pub async fn context_provider(
+        &self,
+    ) -> Result<StoreContextProvider<'_>, TransactionServiceError> {
+        let nanoerg_per_mg = self.tx_service().nanoerg_per_mg().await?;
+        Ok(StoreContextProvider::new(&self.store, nanoerg_per_mg))
+    }
+
     pub fn tx_service(&self) -> TransactionService {
-        TransactionService::new(&self.node, &self.store, &self.compiler)
+        TransactionService::new(
+            &self.node,
+            &self.parameters,
+            &self.store,
+            &self.compiler,
+            &self.network,
+            &self.fee_policy,
+            &self.box_selector,
+            &self.pending,
+        )
     }
 }
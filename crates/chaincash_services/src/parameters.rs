@@ -0,0 +1,94 @@
+//! Blockchain parameters currently in effect (costs, limits and block version voted by miners). They are part of node's
+//! `/info` response, which [`NodeClient`](ergo_client::node::NodeClient) doesn't expose
+use chaincash_offchain::node::NODE_TIMEOUT;
+use ergo_lib::chain::parameters::Parameters;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ParametersError {
+    #[error("Invalid node URL '{0}'")]
+    InvalidUrl(String),
+
+    #[error("Failed to fetch blockchain parameters from node: {0}")]
+    Request(#[from] reqwest::Error),
+}
+
+#[derive(Deserialize)]
+struct NodeInfo {
+    parameters: NodeParameters,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeParameters {
+    block_version: i32,
+    storage_fee_factor: i32,
+    min_value_per_byte: i32,
+    max_block_size: i32,
+    max_block_cost: i32,
+    token_access_cost: i32,
+    input_cost: i32,
+    data_input_cost: i32,
+    output_cost: i32,
+}
+
+/// Client for node's `/info` endpoint, timing out like the node client created by
+/// [`node_from_config`](chaincash_offchain::node::node_from_config)
+#[derive(Clone, Debug)]
+pub struct ParametersClient {
+    client: Client,
+    info_url: Url,
+}
+
+impl ParametersClient {
+    pub fn new(node_url: &str) -> Result<Self, ParametersError> {
+        let info_url = Url::parse(&format!("{}/info", node_url.trim_end_matches('/')))
+            .map_err(|_| ParametersError::InvalidUrl(node_url.to_owned()))?;
+        let client = Client::builder().timeout(NODE_TIMEOUT).build()?;
+        Ok(Self { client, info_url })
+    }
+
+    /// Fetch parameters currently in effect from node
+    pub async fn get(&self) -> Result<Parameters, ParametersError> {
+        let NodeInfo { parameters } = self
+            .client
+            .get(self.info_url.clone())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Parameters::new(
+            parameters.block_version,
+            parameters.storage_fee_factor,
+            parameters.min_value_per_byte,
+            parameters.max_block_size,
+            parameters.max_block_cost,
+            parameters.token_access_cost,
+            parameters.input_cost,
+            parameters.data_input_cost,
+            parameters.output_cost,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chaincash_mock_node::MockNode;
+    use ergo_lib::{chain::parameters::Parameters, ergotree_ir::chain::address::NetworkPrefix};
+
+    use super::{ParametersClient, ParametersError};
+
+    #[tokio::test]
+    async fn test_get_parameters() {
+        let node = MockNode::start(NetworkPrefix::Mainnet).await.unwrap();
+        let client = ParametersClient::new(&node.url()).unwrap();
+        assert_eq!(client.get().await.unwrap(), Parameters::default());
+        assert!(matches!(
+            ParametersClient::new("not a url"),
+            Err(ParametersError::InvalidUrl(_))
+        ));
+    }
+}
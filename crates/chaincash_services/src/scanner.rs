@@ -1,8 +1,8 @@
 use std::{borrow::Cow, sync::Arc, time::Duration};
 
 use chaincash_offchain::{
-    boxes::{Note, ReserveBoxSpec},
-    note_history::{NoteHistory, NoteHistoryError, OwnershipEntry},
+    boxes::{Note, Receipt, ReceiptBoxSpec, ReserveBoxSpec},
+    note_history::{NoteHistory, OwnershipEntry},
 };
 use chaincash_store::scans::ScanType;
 use ergo_client::node::{
@@ -11,12 +11,14 @@ use ergo_client::node::{
 };
 use ergo_lib::{
     chain::transaction::{ergo_transaction::ErgoTransaction, Transaction, TxId},
+    ergo_chain_types::EcPoint,
     ergotree_ir::{
         chain::{
-            ergo_box::{ErgoBox, RegisterId},
+            ergo_box::{ErgoBox, NonMandatoryRegisterId, RegisterId},
             token::TokenId,
         },
         ergo_tree::ErgoTree,
+        mir::constant::TryExtractInto,
         serialization::SigmaSerializable,
     },
 };
@@ -47,14 +49,14 @@ struct ContractScan<'a> {
 }
 
 impl<'a> ContractScan<'a> {
-    async fn new(state: &ServerState, scan_type: ScanType) -> Result<Self, ScannerError> {
+    fn new(state: &ServerState, scan_type: ScanType) -> Self {
         let contract = match scan_type {
-            ScanType::Reserves => state.compiler.reserve_contract().await?,
-            ScanType::Notes => state.compiler.note_contract().await?,
-            ScanType::Receipts => state.compiler.receipt_contract().await?,
+            ScanType::Reserves => state.compiler.reserve_contract(),
+            ScanType::Notes => state.compiler.note_contract(),
+            ScanType::Receipts => state.compiler.receipt_contract(),
         };
         let scan = Self::contract_scan(format!("Chaincash {} scan", scan_type.to_str()), contract);
-        Ok(Self { scan_type, scan })
+        Self { scan_type, scan }
     }
 
     fn contract_scan(
@@ -115,7 +117,7 @@ async fn load_scan<'a>(
     scan_type: ScanType,
     node_scans: &[RegisteredScan<'a>],
 ) -> Result<(bool, Vec<i32>), ScannerError> {
-    let contract_scan = ContractScan::new(state, scan_type).await?;
+    let contract_scan = ContractScan::new(state, scan_type);
     let scans = state.store.scans().scans_by_type(scan_type)?;
     let registered: Vec<_> = node_scans
         .iter()
@@ -194,15 +196,82 @@ async fn reserve_scanner(state: Arc<ServerState>, scan_ids: Vec<i32>) -> Result<
     }
 }
 
-async fn note_backward_scan(state: &ServerState, note_box: ErgoBox) -> Result<Note, ScannerError> {
+// Receipts only commit to the note history digest. Follow the transactions that created the receipt back to the redeemed note to recover the history
+async fn receipt_backward_scan(
+    state: &ServerState,
+    receipt_box: &ErgoBox,
+) -> Result<Receipt, ScannerError> {
+    let indexer = &state.node.endpoints().blockchain()?;
+    let receipt_box = ReceiptBoxSpec::try_from(receipt_box)?;
+    let mut cur_box = Cow::Borrowed(receipt_box.ergo_box());
+    let history = loop {
+        if let Some((_, receipt)) = state.store.receipts().get_by_box_id(&cur_box.box_id())? {
+            break receipt.history;
+        }
+        let tx = get_transaction(&state.node, &cur_box.transaction_id)
+            .await?
+            .ok_or_else(|| ScannerError::InvalidTransaction(cur_box.transaction_id.clone()))?;
+        // Redeemed note or receipt is always at INPUTS(0)
+        let input_box = indexer
+            .get_box_by_id(&tx.inputs.first().box_id)
+            .await?
+            .ergo_box;
+        if input_box.ergo_tree == *state.compiler.note_contract() {
+            // Only the history is needed, the redeemed note is removed from store by the note scanner once it is spent
+            break note_backward_scan(state, input_box).await?.0.history;
+        } else if input_box.ergo_tree == *state.compiler.receipt_contract() {
+            cur_box = Cow::Owned(input_box);
+        } else {
+            return Err(ScannerError::InvalidTransaction(tx.id()));
+        }
+    };
+    Ok(Receipt::new(receipt_box, history)?)
+}
+
+async fn receipt_scanner(state: Arc<ServerState>, scan_ids: Vec<i32>) -> Result<(), ScannerError> {
+    loop {
+        let scan_boxes = get_all_scan_boxes(&scan_ids, &state).await?;
+        for scan_box in &scan_boxes {
+            let box_id = scan_box.ergo_box.box_id();
+            if state.store.ergo_boxes().get_by_id(box_id)?.is_some() {
+                continue;
+            }
+            match receipt_backward_scan(&state, &scan_box.ergo_box).await {
+                Ok(receipt) => {
+                    state.store.receipts().add(&receipt)?;
+                    info!(
+                        "Added receipt box id {}, note identifier: {:?}",
+                        box_id, receipt.receipt_box.note_id
+                    );
+                }
+                Err(e) => warn!("Failed to import box {} from scan, err: {e}", box_id),
+            }
+        }
+        state
+            .store
+            .receipts()
+            .delete_not_in(scan_boxes.iter().map(|b| b.ergo_box.box_id()))?
+            .into_iter()
+            .for_each(|deleted| info!("Deleting box id: {deleted}"));
+        wait_scan_block(&state).await?;
+    }
+}
+
+// Recover history of `note_box` by following its transactions back until a note tracked in store or the mint transaction.
+// Returns the note and the ID of the tracked note it was spent from, if any. Store is not modified
+async fn note_backward_scan(
+    state: &ServerState,
+    note_box: ErgoBox,
+) -> Result<(Note, Option<i32>), ScannerError> {
     let indexer = &state.node.endpoints().blockchain()?;
     let note_token_id = note_box.tokens.as_ref().unwrap().first().token_id;
     let mut history = Vec::new();
+    let mut spent_note_id = None;
     let mut cur_box = Cow::Borrowed(&note_box);
     'outer: loop {
         if let Some((id, old_note)) = state.store.notes().get_by_box_id(&cur_box.box_id())? {
             history.extend(old_note.history.ownership_entries().iter().rev().cloned());
-            state.store.notes().delete_note(id)?;
+            spent_note_id = Some(id);
             break;
         }
         let tx = get_transaction(&state.node, &cur_box.transaction_id)
@@ -226,7 +295,7 @@ async fn note_backward_scan(state: &ServerState, note_box: ErgoBox) -> Result<No
         let reserve_box = ReserveBoxSpec::try_from(
             &indexer.get_box_by_id(&reserve_input.box_id).await?.ergo_box,
         )?;
-        if reserve_box.ergo_box().ergo_tree != *state.compiler.reserve_contract().await? {
+        if reserve_box.ergo_box().ergo_tree != *state.compiler.reserve_contract() {
             return Err(ScannerError::InvalidReserveBox(note_token_id, tx.id()));
         }
         for input in tx.inputs.iter() {
@@ -238,11 +307,20 @@ async fn note_backward_scan(state: &ServerState, note_box: ErgoBox) -> Result<No
                 .map(|tokens| tokens.first())
             {
                 if token.token_id == note_token_id
-                    && input_box.ergo_box.ergo_tree == *state.compiler.note_contract().await?
+                    && input_box.ergo_box.ergo_tree == *state.compiler.note_contract()
                 {
+                    // Owner of spent note box (R5) is the signer of the new ownership entry
+                    let signer = input_box
+                        .ergo_box
+                        .get_register(NonMandatoryRegisterId::R5.into())
+                        .ok()
+                        .flatten()
+                        .and_then(|reg| reg.v.try_extract_into::<EcPoint>().ok())
+                        .ok_or_else(|| ScannerError::InvalidTransaction(tx.id()))?;
                     let ownership_entry = OwnershipEntry::from_context_extension(
                         *token.amount.as_u64(),
                         reserve_box.identifier,
+                        signer,
                         &input.spending_proof.extension,
                     )?;
                     history.push(ownership_entry);
@@ -253,20 +331,9 @@ async fn note_backward_scan(state: &ServerState, note_box: ErgoBox) -> Result<No
         }
         return Err(ScannerError::InvalidTransaction(tx.id()));
     }
-    let note_history = history.into_iter().rev().try_fold(
-        NoteHistory::new(),
-        |mut history, entry| -> Result<NoteHistory, NoteHistoryError> {
-            history.add_commitment(entry)?;
-            Ok(history)
-        },
-    )?;
-    let note = Note::new(note_box, note_history)?;
-    info!(
-        "Added note box id {}, identifier: {:?}",
-        note.ergo_box().box_id(),
-        note.note_id
-    );
-    Ok(note)
+    let note_history = NoteHistory::from_entries(history.into_iter().rev());
+    note_history.verify(note_token_id)?;
+    Ok((Note::new(note_box, note_history)?, spent_note_id))
 }
 
 async fn note_scanner(state: Arc<ServerState>, scan_ids: Vec<i32>) -> Result<(), ScannerError> {
@@ -285,8 +352,17 @@ async fn note_scanner(state: Arc<ServerState>, scan_ids: Vec<i32>) -> Result<(),
                 continue;
             }
             match note_backward_scan(&state, scan_box.ergo_box.clone()).await {
-                Ok(note) => {
+                Ok((note, spent_note_id)) => {
+                    // Tracked note was spent into this one, replace it
+                    if let Some(spent_note_id) = spent_note_id {
+                        state.store.notes().delete_note(spent_note_id).unwrap();
+                    }
                     state.store.notes().add_note(&note).unwrap();
+                    info!(
+                        "Added note box id {}, identifier: {:?}",
+                        note.ergo_box().box_id(),
+                        note.note_id
+                    );
                 }
                 Err(e) => warn!(
                     "Filtered invalid note box id {} from scan, error {e:?}",
@@ -314,11 +390,18 @@ pub async fn start_scanner(state: Arc<ServerState>) -> Result<(), ScannerError>
     let (mut needs_rescan, reserve_scans) = load_scan(&state, ScanType::Reserves, &scans).await?;
     let (rescan, note_scans) = load_scan(&state, ScanType::Notes, &scans).await?;
     needs_rescan |= rescan;
+    let (rescan, receipt_scans) = load_scan(&state, ScanType::Receipts, &scans).await?;
+    needs_rescan |= rescan;
     if needs_rescan {
-        //Rescan from block #1,318_639. This height can be increased later when chaincash is deployed
-        let _ = state.node.endpoints().wallet()?.rescan(1_318_639).await;
+        let _ = state
+            .node
+            .endpoints()
+            .wallet()?
+            .rescan(state.network.scan_start_height)
+            .await;
     }
     tokio::spawn(reserve_scanner(state.clone(), reserve_scans));
     tokio::spawn(note_scanner(state.clone(), note_scans));
+    tokio::spawn(receipt_scanner(state.clone(), receipt_scans));
     Ok(())
 }
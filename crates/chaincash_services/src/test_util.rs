@@ -0,0 +1,69 @@
+use chaincash_mock_node::{MockChain, MockNode};
+use chaincash_offchain::{network, transactions::box_selection, transactions::fee::FeePolicy};
+use chaincash_predicate::Combination;
+use chaincash_store::ChainCashStore;
+use ergo_lib::{
+    chain::ergo_box::box_builder::ErgoBoxCandidateBuilder,
+    ergo_chain_types::ec_point::generator,
+    ergotree_ir::{
+        chain::{
+            address::{Address, AddressEncoder, NetworkPrefix},
+            ergo_box::{box_value::BoxValue, ErgoBox, NonMandatoryRegisterId},
+            token::{Token, TokenAmount, TokenId},
+        },
+        ergo_tree::ErgoTree,
+    },
+};
+
+use crate::{parameters::ParametersClient, ServerState};
+
+// Pre-compiled buyback address since we need node to compile contract otherwise
+pub const BUYBACK_ADDRESS: &str = "43xhWcMKGeNA8eJ1oFuJGzcCyiBH84t5ViuMLx5BddqmAew1JjhEXkiL5bJsdBktBhvFkpyFb5WJ5m24jT1Kz978h8Mb9Z61feYbCZjLTuF2skSCfQWvsrXpcsTGY2pnVpZn4fe32CijGymD2M4UKdc1YMU8fh6TRJJUfHCra9xkX98cajwsrWUE6aFC7Ck7y4rKA9vNSNsBr1sCFt2i5dj7Cci5Ez3F8QZ8xMkZioAGh6MCvupHuPefnkPXtqBtZxwZ6ve5Nk24oudcRagnepk3ipTMRyxA57sfaNwXAij98doHk2mBU7Li51TD3REpjifjUPmA6DX1U3UMCnuDgAZzdSkdgxanPWzRbxbxEoDyxrieN7TQyirHK9dBpp6iJjaE6ave1Y8TZWCizBvWUsXcEuLWGZczDumdstzDs6zvLk4f8MWTVRSkqorwFfvDRSYK26TbsUbD5dLUw4QW8QCYVzER542syvMKp1MNUyM8bo9kRD2Vtb75V3eZV1Camr1h46nMQLLw3bUq2T9P1wRqCHb7kfqKpR1hDLMqmpEeNNig4WJ6BVnBqzfT7MVesYinRco881NDvJnVi22JNx7CXdFKjWm88fG3UcjmBpG8LWedKiybSv3GxZdf9reZrigTZpMyBgwc5YWN4uF9pKWVr25ZHMnqZK8r5FdbrW5x4k8bW1hoLCe5aXLKXzBYyBNt1wZk33qCE4gJ5qu4dyD9eR1F32vY787vBYbSXrsNiF5iN1MhSJysFy4pQVvePMrxePQTEuqYytJCDGFxbaMiU3mai1GwJVTgnQysQWceSE959HgYBxALsTGisWGCjjfYN7QxnjPbbD36ybsei9VhYXu6";
+
+/// Gold price of the oracle box added by [`add_redemption_boxes`], 1 ERG per gram
+pub const NANOERG_PER_KG: i64 = 1_000_000_000_000;
+
+pub fn server_state(node: &MockNode) -> ServerState {
+    ServerState::new(
+        node.client(),
+        ParametersClient::new(&node.url()).unwrap(),
+        ChainCashStore::open_in_memory().unwrap(),
+        vec![],
+        Combination::default(),
+        network::Config::mainnet(),
+        FeePolicy::default(),
+        box_selection::Config::default(),
+    )
+    .unwrap()
+}
+
+/// Add oracle and buyback boxes holding mainnet NFTs, which redeeming notes and receipts needs
+pub fn add_redemption_boxes(chain: &mut MockChain) -> (ErgoBox, ErgoBox) {
+    let network = network::Config::mainnet();
+    // script doesn't matter since oracle box is only used for data input
+    let mut oracle_box = nft_box_builder(
+        Address::P2Pk(generator().into()).script().unwrap(),
+        network.oracle_nft,
+        chain.height(),
+    );
+    oracle_box.set_register_value(NonMandatoryRegisterId::R4, NANOERG_PER_KG.into());
+    let buyback_tree = AddressEncoder::new(NetworkPrefix::Mainnet)
+        .parse_address_from_str(BUYBACK_ADDRESS)
+        .unwrap()
+        .script()
+        .unwrap();
+    let buyback_box = nft_box_builder(buyback_tree, network.buyback_nft, chain.height());
+    (
+        chain.add_box(oracle_box.build().unwrap()),
+        chain.add_box(buyback_box.build().unwrap()),
+    )
+}
+
+fn nft_box_builder(tree: ErgoTree, nft: TokenId, creation_height: u32) -> ErgoBoxCandidateBuilder {
+    let mut builder = ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, tree, creation_height);
+    builder.add_token(Token {
+        token_id: nft,
+        amount: TokenAmount::try_from(1).unwrap(),
+    });
+    builder
+}
@@ -1,35 +1,52 @@
-use chaincash_offchain::oracle::{buyback_nft, oracle_nft};
+use chaincash_offchain::boxes::{Note, ReserveBoxSpec};
+use chaincash_offchain::eip12::{Eip12Error, Eip12UnsignedTransaction};
+use chaincash_offchain::network;
+use chaincash_offchain::oracle::{OracleBoxSpec, MAX_ORACLE_AGE};
+use chaincash_offchain::transactions::box_selection::{exclude_protected_boxes, WalletBoxSelector};
+use chaincash_offchain::transactions::fee::FeePolicy;
 use chaincash_offchain::transactions::notes::{
-    mint_note_transaction, redeem_note, spend_note_transaction, MintNoteRequest, MintNoteResponse,
-    SignedMintNoteResponse, SignedSpendNoteResponse, SpendNoteResponse,
+    mint_note_transaction, redeem_note, redemption_quote, spend_note_split_transactions,
+    MintNoteRequest, MintNoteResponse, NoteOutput, RedemptionQuote, SignedMintNoteResponse,
+    SignedSpendNoteResponse, SpendNoteResponse,
 };
+use chaincash_offchain::transactions::receipts::redeem_receipt;
 use chaincash_offchain::transactions::reserves::{
+    cancel_refund_transaction, complete_refund_transaction, initiate_refund_transaction,
     mint_reserve_transaction, top_up_reserve_transaction, MintReserveRequest, ReserveResponse,
     SignedReserveResponse,
 };
+use chaincash_offchain::transactions::validation::{validate_inputs, ValidationError};
 use chaincash_offchain::transactions::{TransactionError, TxContext};
 use chaincash_store::ChainCashStore;
 use ergo_client::node::endpoints::blockchain::IndexQuery;
 use ergo_client::node::NodeClient;
-use ergo_lib::chain::transaction::Transaction;
-use ergo_lib::ergo_chain_types::EcPoint;
+use ergo_lib::chain::ergo_state_context::ErgoStateContext;
+use ergo_lib::chain::transaction::ergo_transaction::ErgoTransaction;
+use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+use ergo_lib::chain::transaction::{Transaction, TxId};
+use ergo_lib::ergo_chain_types::{EcPoint, Header, PreHeader};
+use ergo_lib::ergotree_ir::chain::address::{Address, NetworkAddress};
 use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
-use ergo_lib::ergotree_ir::chain::ergo_box::{box_value::BoxValueError, ErgoBox};
-use ergo_lib::ergotree_ir::chain::token::{TokenAmount, TokenId};
-use ergo_lib::wallet::box_selector::{
-    BoxSelection, BoxSelector, BoxSelectorError, SimpleBoxSelector,
-};
-use ergo_lib::wallet::tx_builder::SUGGESTED_TX_FEE;
+use ergo_lib::ergotree_ir::chain::ergo_box::{box_value::BoxValueError, BoxId, ErgoBox};
+use ergo_lib::ergotree_ir::chain::token::TokenId;
+use ergo_lib::wallet::box_selector::{BoxSelection, BoxSelectorError};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 use crate::compiler::Compiler;
+use crate::parameters::{ParametersClient, ParametersError};
 
 #[derive(Debug, Error)]
 pub enum TransactionServiceError {
     #[error("Change address not set in wallet")]
     ChangeAddressNotSet,
 
+    #[error("Wallet change address {0} is not on configured network")]
+    ChangeAddressNetwork(String),
+
     #[error("An error occurred while building transaction: {0}")]
     TransactionBuilding(#[from] TransactionError),
 
@@ -42,11 +59,71 @@ pub enum TransactionServiceError {
     #[error("Node operation failed: {0}")]
     Node(#[from] ergo_client::node::NodeError),
 
+    #[error(transparent)]
+    Parameters(#[from] ParametersError),
+
     #[error("Store error: {0}")]
     Store(#[from] chaincash_store::Error),
 
     #[error("Reserve Box not found")]
     ReserveBoxNotFound,
+
+    #[error("No unspent box holds token {0:?}")]
+    MissingBox(TokenId),
+
+    #[error(
+        "Note owner key {0:?} is not held by node wallet, it is needed to sign the note history"
+    )]
+    OwnerKeyNotInWallet(EcPoint),
+
+    #[error("Both recipient_pubkey and amount must be set to pay a single recipient")]
+    IncompleteRecipient,
+
+    #[error("Transaction failed validation: {0}")]
+    Validation(#[from] ValidationError),
+
+    #[error("Node returned {0} block headers, 10 are needed to validate transactions")]
+    MissingHeaders(usize),
+
+    #[error("Failed to export unsigned transaction: {0}")]
+    Eip12(#[from] Eip12Error),
+
+    #[error("Wallet only holds boxes with tokens of notes, reserves or receipts tracked in store ({0} boxes), refusing to spend them as plain inputs")]
+    OnlyProtectedBoxes(usize),
+
+    #[error("Not enough funds after excluding {count} wallet boxes worth {value} nanoERG holding tokens of notes, reserves or receipts tracked in store, list their tokens in allowed_tokens to spend them: {source}")]
+    ProtectedBoxesExcluded {
+        count: usize,
+        value: u64,
+        source: Box<TransactionServiceError>,
+    },
+}
+
+/// Wallet boxes left out of input selection because they hold tokens tracked in store
+#[derive(Debug, Clone, Copy, Default)]
+struct ProtectedBoxes {
+    count: usize,
+    value: u64,
+}
+
+impl ProtectedBoxes {
+    // A box selection failure may be caused by excluded boxes rather than an empty wallet, report them along with the error
+    fn explain(&self, error: impl Into<TransactionServiceError>) -> TransactionServiceError {
+        let error = error.into();
+        match error {
+            TransactionServiceError::BoxSelection(_)
+            | TransactionServiceError::TransactionBuilding(TransactionError::BoxSelection(_))
+                if self.count > 0 =>
+            {
+                TransactionServiceError::ProtectedBoxesExcluded {
+                    count: self.count,
+                    value: self.value,
+                    source: Box::new(error),
+                }
+            }
+            error => error,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -54,8 +131,33 @@ pub struct SpendNoteRequest {
     /// ID of note in database
     note_id: i32,
     reserve_id: TokenId,
-    recipient_pubkey: EcPoint,
-    amount: TokenAmount,
+    /// Recipients of new notes and amounts, one transaction is created per recipient
+    #[serde(default)]
+    outputs: Vec<NoteOutput>,
+    /// Single recipient, accepted for compatibility with requests made before `outputs`. Paid before `outputs` if set
+    recipient_pubkey: Option<EcPoint>,
+    /// Amount sent to `recipient_pubkey`
+    amount: Option<u64>,
+    /// Owner of the change note, defaults to current owner of note
+    change_pubkey: Option<EcPoint>,
+    /// Tokens of tracked notes, reserves or receipts that wallet boxes funding the transaction may hold
+    #[serde(default)]
+    allowed_tokens: Vec<TokenId>,
+}
+
+impl SpendNoteRequest {
+    // Recipients of request, including the single recipient form
+    fn outputs(&self) -> Result<Vec<NoteOutput>, TransactionServiceError> {
+        let single = match (&self.recipient_pubkey, self.amount) {
+            (Some(recipient_pubkey), Some(amount)) => Some(NoteOutput {
+                recipient_pubkey: recipient_pubkey.clone(),
+                amount,
+            }),
+            (None, None) => None,
+            _ => return Err(TransactionServiceError::IncompleteRecipient),
+        };
+        Ok(single.into_iter().chain(self.outputs.clone()).collect())
+    }
 }
 
 #[derive(Deserialize)]
@@ -63,47 +165,170 @@ pub struct TopUpReserveRequest {
     /// ID of note in database
     reserve_id: TokenId,
     top_up_amount: u64,
+    /// Tokens of tracked notes, reserves or receipts that wallet boxes funding the transaction may hold
+    #[serde(default)]
+    allowed_tokens: Vec<TokenId>,
+}
+
+#[derive(Deserialize)]
+pub struct ReserveRefundRequest {
+    reserve_id: TokenId,
+    /// Tokens of tracked notes, reserves or receipts that wallet boxes funding the transaction may hold
+    #[serde(default)]
+    allowed_tokens: Vec<TokenId>,
+}
+
+#[derive(Deserialize)]
+pub struct CompleteRefundRequest {
+    reserve_id: TokenId,
+    /// Amount of nanoERG to withdraw from reserve
+    refund_amount: u64,
+    /// Tokens of tracked notes, reserves or receipts that wallet boxes funding the transaction may hold
+    #[serde(default)]
+    allowed_tokens: Vec<TokenId>,
 }
 
 #[derive(Deserialize)]
 pub struct RedeemNoteRequest {
     note_id: i32,
     reserve_id: TokenId,
+    /// Tokens of tracked notes, reserves or receipts that wallet boxes funding the transaction may hold
+    #[serde(default)]
+    allowed_tokens: Vec<TokenId>,
+}
+
+#[derive(Deserialize)]
+pub struct RedemptionQuoteRequest {
+    note_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct RedeemReceiptRequest {
+    /// ID of receipt in database
+    receipt_id: i32,
+    reserve_id: TokenId,
+    /// Tokens of tracked notes, reserves or receipts that wallet boxes funding the transaction may hold
+    #[serde(default)]
+    allowed_tokens: Vec<TokenId>,
+}
+
+/// Change to store made once a transaction is submitted
+pub enum StoreUpdate {
+    AddReserve(ReserveBoxSpec),
+    AddNote(Note),
+    /// Note box spent by the transaction, removed from store if tracked. Box ID is used since the note may be added by an earlier
+    /// transaction of the same operation, after the update was created
+    DeleteNote(BoxId),
+}
+
+/// How long store updates of an exported transaction are kept waiting for the signed transaction
+pub const PENDING_TRANSACTION_TTL: Duration = Duration::from_secs(60 * 60);
+/// Maximum number of exported transactions waiting to be submitted, oldest are dropped first
+pub const MAX_PENDING_TRANSACTIONS: usize = 1000;
+
+/// Store updates of transactions exported for external signing, keyed by transaction ID and applied once the signed transaction is submitted.
+/// Entries expire after [`PENDING_TRANSACTION_TTL`] and at most [`MAX_PENDING_TRANSACTIONS`] are kept. Updates of a transaction
+/// submitted after its entry was dropped are not applied, the scanners pick up its boxes instead
+#[derive(Default)]
+pub struct PendingTransactions {
+    transactions: Mutex<HashMap<TxId, (Instant, Vec<StoreUpdate>)>>,
+}
+
+impl PendingTransactions {
+    fn insert(&self, tx_id: TxId, updates: Vec<StoreUpdate>) {
+        let mut transactions = self.transactions.lock().unwrap();
+        let now = Instant::now();
+        transactions
+            .retain(|_, (created, _)| now.duration_since(*created) < PENDING_TRANSACTION_TTL);
+        while transactions.len() >= MAX_PENDING_TRANSACTIONS {
+            let oldest = transactions
+                .iter()
+                .min_by_key(|(_, (created, _))| *created)
+                .map(|(tx_id, _)| *tx_id);
+            match oldest {
+                Some(oldest) => transactions.remove(&oldest),
+                None => break,
+            };
+        }
+        transactions.insert(tx_id, (now, updates));
+    }
+
+    fn remove(&self, tx_id: &TxId) -> Option<Vec<StoreUpdate>> {
+        self.transactions
+            .lock()
+            .unwrap()
+            .remove(tx_id)
+            .filter(|(created, _)| created.elapsed() < PENDING_TRANSACTION_TTL)
+            .map(|(_, updates)| updates)
+    }
 }
 
 #[derive(Clone)]
 pub struct TransactionService<'a> {
     node: &'a NodeClient,
+    parameters: &'a ParametersClient,
     compiler: &'a Compiler,
     store: &'a ChainCashStore,
+    network: &'a network::Config,
+    fee_policy: &'a FeePolicy,
+    box_selector: &'a Arc<dyn WalletBoxSelector>,
+    pending: &'a PendingTransactions,
 }
 
 impl<'a> TransactionService<'a> {
-    pub fn new(node: &'a NodeClient, store: &'a ChainCashStore, compiler: &'a Compiler) -> Self {
+    pub fn new(
+        node: &'a NodeClient,
+        parameters: &'a ParametersClient,
+        store: &'a ChainCashStore,
+        compiler: &'a Compiler,
+        network: &'a network::Config,
+        fee_policy: &'a FeePolicy,
+        box_selector: &'a Arc<dyn WalletBoxSelector>,
+        pending: &'a PendingTransactions,
+    ) -> Self {
         Self {
             node,
+            parameters,
             store,
             compiler,
+            network,
+            fee_policy,
+            box_selector,
+            pending,
         }
     }
 
+    /// Unspent wallet boxes, excluding boxes holding tokens of notes, reserves or receipts tracked in store unless listed in `allowed_tokens`
+    async fn wallet_boxes(
+        &self,
+        allowed_tokens: &[TokenId],
+    ) -> Result<(Vec<ErgoBox>, ProtectedBoxes), TransactionServiceError> {
+        let wallet_boxes = self.node.extensions().get_utxos().await?;
+        let tracked = self.store.tracked_token_ids()?;
+        let (spendable, protected) =
+            exclude_protected_boxes(wallet_boxes, &tracked, allowed_tokens);
+        if spendable.is_empty() && !protected.is_empty() {
+            return Err(TransactionServiceError::OnlyProtectedBoxes(protected.len()));
+        }
+        let protected = ProtectedBoxes {
+            count: protected.len(),
+            value: protected.iter().map(|b| *b.value.as_u64()).sum(),
+        };
+        Ok((spendable, protected))
+    }
+
     async fn box_selection_with_amount(
         &self,
         amount: u64,
     ) -> Result<BoxSelection<ErgoBox>, TransactionServiceError> {
-        let inputs = self
-            .node
-            .extensions()
-            .get_utxos_summing_amount(amount)
-            .await?;
-        // kinda irrelevant since we already have suitable boxes but box selectors required by ergo-lib txbuilder
-        Ok(SimpleBoxSelector::new()
+        let (wallet_boxes, protected) = self.wallet_boxes(&[]).await?;
+        self.box_selector
             .select(
-                inputs,
+                wallet_boxes,
                 amount.try_into().map_err(TransactionServiceError::from)?,
                 &[],
             )
-            .map_err(TransactionServiceError::from)?)
+            .map_err(|e| protected.explain(e))
     }
 
     async fn get_tx_ctx(&self) -> Result<TxContext, TransactionServiceError> {
@@ -112,29 +337,257 @@ impl<'a> TransactionService<'a> {
 
         if wallet_status.change_address.is_empty() {
             Err(TransactionServiceError::ChangeAddressNotSet)?
+        } else if NetworkAddress::try_from(wallet_status.change_address.clone())
+            .map_err(TransactionError::from)?
+            .network()
+            != self.network.address_prefix.into()
+        {
+            Err(TransactionServiceError::ChangeAddressNetwork(
+                wallet_status.change_address,
+            ))?
         } else {
             Ok(TxContext {
                 current_height: info.full_height as u32,
                 change_address: wallet_status.change_address,
-                fee: *SUGGESTED_TX_FEE().as_u64(),
+                fee: self.fee_policy.initial_fee(),
+                box_selector: self.box_selector.clone(),
             })
         }
     }
 
-    /// Create a mint reserve transaction and add minted reserve box to DB
-    pub async fn mint_reserve(
+    // Apply store changes of a submitted transaction
+    fn apply_store_updates(
+        &self,
+        updates: Vec<StoreUpdate>,
+    ) -> Result<(), TransactionServiceError> {
+        for update in updates {
+            match update {
+                StoreUpdate::AddReserve(reserve_box) => {
+                    self.store.reserves().add_or_update(&reserve_box)?;
+                }
+                StoreUpdate::AddNote(note) => {
+                    self.store.notes().add_note(&note)?;
+                }
+                StoreUpdate::DeleteNote(box_id) => {
+                    if let Some((note_id, _)) = self.store.notes().get_by_box_id(&box_id)? {
+                        self.store.notes().delete_note(note_id)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Fetch state context scripts are evaluated against, as if transactions were included in the next block
+    async fn state_context(&self) -> Result<ErgoStateContext, TransactionServiceError> {
+        let mut headers = self.node.endpoints().blocks()?.last_headers(10).await?;
+        // Node returns headers oldest first, state context expects latest first
+        headers.reverse();
+        let headers: [Header; 10] = headers.try_into().map_err(|headers: Vec<Header>| {
+            TransactionServiceError::MissingHeaders(headers.len())
+        })?;
+        let mut pre_header = PreHeader::from(headers[0].clone());
+        pre_header.parent_id = headers[0].id;
+        pre_header.height = headers[0].height + 1;
+        let parameters = self.parameters.get().await?;
+        Ok(ErgoStateContext::new(pre_header, headers, parameters))
+    }
+
+    // Evaluate input scripts of transactions locally so contract failures are reported before anything is submitted
+    async fn validate(
+        &self,
+        transactions: &[&UnsignedTransaction],
+        boxes: &[ErgoBox],
+    ) -> Result<(), TransactionServiceError> {
+        let state_context = self.state_context().await?;
+        for transaction in transactions {
+            validate_inputs(
+                transaction,
+                boxes,
+                &state_context,
+                self.compiler.contracts(),
+            )?;
+        }
+        Ok(())
+    }
+
+    // Convert transaction to EIP-12 format and remember its store updates until the signed transaction is submitted
+    fn export_unsigned(
+        &self,
+        transaction: &UnsignedTransaction,
+        boxes: &[ErgoBox],
+        updates: Vec<StoreUpdate>,
+    ) -> Result<Eip12UnsignedTransaction, TransactionServiceError> {
+        let eip12 = Eip12UnsignedTransaction::new(transaction, boxes)?;
+        self.pending.insert(transaction.id(), updates);
+        Ok(eip12)
+    }
+
+    /// Submit a transaction signed externally and apply store updates of the unsigned transaction it was exported from, if any
+    pub async fn submit(&self, transaction: Transaction) -> Result<TxId, TransactionServiceError> {
+        self.node
+            .endpoints()
+            .transactions()?
+            .submit(&transaction)
+            .await?;
+        let updates = self.pending.remove(&transaction.id());
+        if let Some(updates) = updates {
+            self.apply_store_updates(updates)?;
+        }
+        Ok(transaction.id())
+    }
+
+    async fn build_mint_reserve(
         &self,
         request: MintReserveRequest,
-    ) -> Result<SignedReserveResponse, TransactionServiceError> {
-        let ctx = self.get_tx_ctx().await?;
+    ) -> Result<(ReserveResponse<UnsignedTransaction>, Vec<ErgoBox>), TransactionServiceError> {
+        let mut ctx = self.get_tx_ctx().await?;
+        let reserve_tree = self.compiler.reserve_contract().clone();
+        // Inputs are selected for a specific fee, so estimate fee from a first build and select inputs again for it
         let selected_inputs = self
             .box_selection_with_amount(request.amount + ctx.fee)
             .await?;
-        let reserve_tree = self.compiler.reserve_contract().await?.clone();
+        let estimate = mint_reserve_transaction(
+            request.clone(),
+            reserve_tree.clone(),
+            selected_inputs,
+            ctx.clone(),
+        )?;
+        ctx.fee = self.fee_policy.fee_for(&estimate.transaction);
+        let selected_inputs = self
+            .box_selection_with_amount(request.amount + ctx.fee)
+            .await?;
+        let boxes = selected_inputs.boxes.iter().cloned().collect();
+        Ok((
+            mint_reserve_transaction(request, reserve_tree, selected_inputs, ctx)?,
+            boxes,
+        ))
+    }
+
+    /// Create a mint reserve transaction and add minted reserve box to DB
+    pub async fn mint_reserve(
+        &self,
+        request: MintReserveRequest,
+    ) -> Result<SignedReserveResponse, TransactionServiceError> {
+        let (
+            ReserveResponse {
+                reserve_box,
+                transaction,
+            },
+            boxes,
+        ) = self.build_mint_reserve(request).await?;
+        self.validate(&[&transaction], &boxes).await?;
+        let submitted_tx = self.node.extensions().sign_and_submit(transaction).await?;
+        self.store.reserves().add_or_update(&reserve_box)?;
+        Ok(SignedReserveResponse {
+            reserve_box,
+            transaction: submitted_tx,
+        })
+    }
+
+    /// Create a mint reserve transaction for signing by an external wallet
+    pub async fn mint_reserve_unsigned(
+        &self,
+        request: MintReserveRequest,
+    ) -> Result<Eip12UnsignedTransaction, TransactionServiceError> {
+        let (response, boxes) = self.build_mint_reserve(request).await?;
+        self.validate(&[&response.transaction], &boxes).await?;
+        self.export_unsigned(
+            &response.transaction,
+            &boxes,
+            vec![StoreUpdate::AddReserve(response.reserve_box)],
+        )
+    }
+
+    async fn build_top_up_reserve(
+        &self,
+        request: TopUpReserveRequest,
+    ) -> Result<(ReserveResponse<UnsignedTransaction>, Vec<ErgoBox>), TransactionServiceError> {
+        let mut ctx = self.get_tx_ctx().await?;
+        let (wallet_boxes, protected) = self.wallet_boxes(&request.allowed_tokens).await?;
+        let reserve = self
+            .store
+            .reserves()
+            .get_reserve_by_identifier(&request.reserve_id)?;
+        let response = self
+            .fee_policy
+            .build(
+                &mut ctx,
+                |ctx| {
+                    top_up_reserve_transaction(
+                        &reserve,
+                        wallet_boxes.clone(),
+                        request.top_up_amount,
+                        ctx,
+                    )
+                },
+                |response| vec![&response.transaction],
+            )
+            .map_err(|e| protected.explain(e))?;
+        let mut boxes = wallet_boxes;
+        boxes.push(reserve.ergo_box().clone());
+        Ok((response, boxes))
+    }
+
+    pub async fn top_up_reserve(
+        &self,
+        request: TopUpReserveRequest,
+    ) -> Result<SignedReserveResponse, TransactionServiceError> {
+        let (
+            ReserveResponse {
+                reserve_box,
+                transaction,
+            },
+            boxes,
+        ) = self.build_top_up_reserve(request).await?;
+        self.validate(&[&transaction], &boxes).await?;
+        let submitted_tx = self.node.extensions().sign_and_submit(transaction).await?;
+        self.store.reserves().add_or_update(&reserve_box)?;
+        Ok(SignedReserveResponse {
+            reserve_box,
+            transaction: submitted_tx,
+        })
+    }
+
+    /// Create a reserve top-up transaction for signing by an external wallet
+    pub async fn top_up_reserve_unsigned(
+        &self,
+        request: TopUpReserveRequest,
+    ) -> Result<Eip12UnsignedTransaction, TransactionServiceError> {
+        let (response, boxes) = self.build_top_up_reserve(request).await?;
+        self.validate(&[&response.transaction], &boxes).await?;
+        self.export_unsigned(
+            &response.transaction,
+            &boxes,
+            vec![StoreUpdate::AddReserve(response.reserve_box)],
+        )
+    }
+
+    /// Start refunding a reserve. ERG can be withdrawn after the refund delay has passed
+    pub async fn initiate_refund(
+        &self,
+        request: ReserveRefundRequest,
+    ) -> Result<SignedReserveResponse, TransactionServiceError> {
+        let mut ctx = self.get_tx_ctx().await?;
+        let (wallet_boxes, protected) = self.wallet_boxes(&request.allowed_tokens).await?;
+        let reserve = self
+            .store
+            .reserves()
+            .get_reserve_by_identifier(&request.reserve_id)?;
         let ReserveResponse {
             reserve_box,
             transaction,
-        } = mint_reserve_transaction(request, reserve_tree, selected_inputs, ctx)?;
+        } = self
+            .fee_policy
+            .build(
+                &mut ctx,
+                |ctx| initiate_refund_transaction(&reserve, wallet_boxes.clone(), ctx),
+                |response| vec![&response.transaction],
+            )
+            .map_err(|e| protected.explain(e))?;
+        let mut boxes = wallet_boxes;
+        boxes.push(reserve.ergo_box().clone());
+        self.validate(&[&transaction], &boxes).await?;
         let submitted_tx = self.node.extensions().sign_and_submit(transaction).await?;
         self.store.reserves().add_or_update(&reserve_box)?;
         Ok(SignedReserveResponse {
@@ -143,12 +596,12 @@ impl<'a> TransactionService<'a> {
         })
     }
 
-    pub async fn top_up_reserve(
+    pub async fn cancel_refund(
         &self,
-        request: TopUpReserveRequest,
+        request: ReserveRefundRequest,
     ) -> Result<SignedReserveResponse, TransactionServiceError> {
-        let ctx = self.get_tx_ctx().await?;
-        let wallet_boxes = self.node.extensions().get_utxos().await?;
+        let mut ctx = self.get_tx_ctx().await?;
+        let (wallet_boxes, protected) = self.wallet_boxes(&request.allowed_tokens).await?;
         let reserve = self
             .store
             .reserves()
@@ -156,7 +609,17 @@ impl<'a> TransactionService<'a> {
         let ReserveResponse {
             reserve_box,
             transaction,
-        } = top_up_reserve_transaction(&reserve, wallet_boxes, request.top_up_amount, &ctx)?;
+        } = self
+            .fee_policy
+            .build(
+                &mut ctx,
+                |ctx| cancel_refund_transaction(&reserve, wallet_boxes.clone(), ctx),
+                |response| vec![&response.transaction],
+            )
+            .map_err(|e| protected.explain(e))?;
+        let mut boxes = wallet_boxes;
+        boxes.push(reserve.ergo_box().clone());
+        self.validate(&[&transaction], &boxes).await?;
         let submitted_tx = self.node.extensions().sign_and_submit(transaction).await?;
         self.store.reserves().add_or_update(&reserve_box)?;
         Ok(SignedReserveResponse {
@@ -165,17 +628,80 @@ impl<'a> TransactionService<'a> {
         })
     }
 
-    pub async fn mint_note(
+    /// Withdraw refund amount from a reserve whose refund delay has passed
+    pub async fn complete_refund(
+        &self,
+        request: CompleteRefundRequest,
+    ) -> Result<SignedReserveResponse, TransactionServiceError> {
+        let mut ctx = self.get_tx_ctx().await?;
+        let (wallet_boxes, protected) = self.wallet_boxes(&request.allowed_tokens).await?;
+        let reserve = self
+            .store
+            .reserves()
+            .get_reserve_by_identifier(&request.reserve_id)?;
+        let ReserveResponse {
+            reserve_box,
+            transaction,
+        } = self
+            .fee_policy
+            .build(
+                &mut ctx,
+                |ctx| {
+                    complete_refund_transaction(
+                        &reserve,
+                        wallet_boxes.clone(),
+                        request.refund_amount,
+                        ctx,
+                    )
+                },
+                |response| vec![&response.transaction],
+            )
+            .map_err(|e| protected.explain(e))?;
+        let mut boxes = wallet_boxes;
+        boxes.push(reserve.ergo_box().clone());
+        self.validate(&[&transaction], &boxes).await?;
+        let submitted_tx = self.node.extensions().sign_and_submit(transaction).await?;
+        self.store.reserves().add_or_update(&reserve_box)?;
+        Ok(SignedReserveResponse {
+            reserve_box,
+            transaction: submitted_tx,
+        })
+    }
+
+    async fn build_mint_note(
         &self,
         request: MintNoteRequest,
-    ) -> Result<SignedMintNoteResponse, TransactionServiceError> {
-        let ctx = self.get_tx_ctx().await?;
+    ) -> Result<(MintNoteResponse<UnsignedTransaction>, Vec<ErgoBox>), TransactionServiceError>
+    {
+        let mut ctx = self.get_tx_ctx().await?;
+        let note_tree = self.compiler.note_contract().clone();
+        // Inputs are selected for a specific fee, so estimate fee from a first build and select inputs again for it
+        let selected_inputs = self
+            .box_selection_with_amount(BoxValue::SAFE_USER_MIN.as_u64() + ctx.fee)
+            .await?;
+        let estimate = mint_note_transaction(
+            request.clone(),
+            note_tree.clone(),
+            selected_inputs,
+            ctx.clone(),
+        )?;
+        ctx.fee = self.fee_policy.fee_for(&estimate.transaction);
         let selected_inputs = self
             .box_selection_with_amount(BoxValue::SAFE_USER_MIN.as_u64() + ctx.fee)
             .await?;
-        let note_tree = self.compiler.note_contract().await?.clone();
-        let MintNoteResponse { note, transaction } =
-            mint_note_transaction(request, note_tree, selected_inputs, ctx)?;
+        let boxes = selected_inputs.boxes.iter().cloned().collect();
+        Ok((
+            mint_note_transaction(request, note_tree, selected_inputs, ctx)?,
+            boxes,
+        ))
+    }
+
+    pub async fn mint_note(
+        &self,
+        request: MintNoteRequest,
+    ) -> Result<SignedMintNoteResponse, TransactionServiceError> {
+        let (MintNoteResponse { note, transaction }, boxes) = self.build_mint_note(request).await?;
+        self.validate(&[&transaction], &boxes).await?;
         let submitted_tx = self.node.extensions().sign_and_submit(transaction).await?;
         self.store.notes().add_note(&note)?;
         Ok(SignedMintNoteResponse {
@@ -184,105 +710,643 @@ impl<'a> TransactionService<'a> {
         })
     }
 
-    pub async fn spend_note(
+    /// Create a mint note transaction for signing by an external wallet
+    pub async fn mint_note_unsigned(
         &self,
-        request: SpendNoteRequest,
-    ) -> Result<SignedSpendNoteResponse, TransactionServiceError> {
+        request: MintNoteRequest,
+    ) -> Result<Eip12UnsignedTransaction, TransactionServiceError> {
+        let (response, boxes) = self.build_mint_note(request).await?;
+        self.validate(&[&response.transaction], &boxes).await?;
+        self.export_unsigned(
+            &response.transaction,
+            &boxes,
+            vec![StoreUpdate::AddNote(response.note)],
+        )
+    }
+
+    async fn build_spend_note(
+        &self,
+        request: &SpendNoteRequest,
+    ) -> Result<(Vec<SpendNoteResponse<UnsignedTransaction>>, Vec<ErgoBox>), TransactionServiceError>
+    {
+        let outputs = request.outputs()?;
         let note = self.store.notes().get_note_box(request.note_id)?;
         let reserve = self
             .store
             .reserves()
             .get_reserve_by_identifier(&request.reserve_id)?;
+        // Ownership entry of note history is signed by the note owner, even if inputs are signed by an external wallet
+        let owner_in_wallet = self
+            .node
+            .endpoints()
+            .wallet()?
+            .get_addresses()
+            .await?
+            .iter()
+            .any(|addr| matches!(addr.address(), Address::P2Pk(provedlog) if *provedlog.h == note.owner));
+        if !owner_in_wallet {
+            return Err(TransactionServiceError::OwnerKeyNotInWallet(note.owner));
+        }
         let private_key = self
             .node
             .extensions()
             .get_private_key(note.owner.clone())
             .await?
             .w;
-        let wallet_boxes = self.node.extensions().get_utxos().await?;
-        let tx_context = self.get_tx_ctx().await?;
-        let SpendNoteResponse {
-            transaction,
-            recipient_note,
-            change_note,
-        } = spend_note_transaction(
-            &note,
-            &reserve,
-            private_key,
-            request.recipient_pubkey,
-            *request.amount.as_u64(),
-            wallet_boxes,
-            &tx_context,
-        )?;
-
-        let transaction = self.node.extensions().sign_and_submit(transaction).await?;
-        self.store.notes().delete_note(request.note_id)?;
-        if let Some(ref change_note) = change_note {
-            self.store.notes().add_note(change_note)?;
-        }
+        let (wallet_boxes, protected) = self.wallet_boxes(&request.allowed_tokens).await?;
+        let mut tx_context = self.get_tx_ctx().await?;
+        let responses = self
+            .fee_policy
+            .build(
+                &mut tx_context,
+                |tx_context| {
+                    spend_note_split_transactions(
+                        &note,
+                        &reserve,
+                        private_key.clone(),
+                        &outputs,
+                        request.change_pubkey.clone(),
+                        wallet_boxes.clone(),
+                        tx_context,
+                    )
+                },
+                |responses| {
+                    responses
+                        .iter()
+                        .map(|response| &response.transaction)
+                        .collect()
+                },
+            )
+            .map_err(|e| protected.explain(e))?;
+        // Later transactions spend change notes created by earlier ones
+        let mut boxes = wallet_boxes;
+        boxes.push(note.ergo_box().clone());
+        boxes.push(reserve.ergo_box().clone());
+        boxes.extend(responses.iter().flat_map(|response| {
+            response
+                .transaction
+                .outputs()
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        }));
+        Ok((responses, boxes))
+    }
 
-        Ok(SignedSpendNoteResponse {
+    /// Spend note to recipients in request. Returns a transaction for each recipient, in order of submission.
+    /// Store is updated after each transaction is submitted, so if a later one fails store still tracks the change note that is unspent
+    pub async fn spend_note(
+        &self,
+        request: SpendNoteRequest,
+    ) -> Result<Vec<SignedSpendNoteResponse>, TransactionServiceError> {
+        let (responses, boxes) = self.build_spend_note(&request).await?;
+        self.validate(
+            &responses
+                .iter()
+                .map(|response| &response.transaction)
+                .collect::<Vec<_>>(),
+            &boxes,
+        )
+        .await?;
+        let mut signed_responses = Vec::with_capacity(responses.len());
+        // Intermediate change notes are added to store and removed again once the next transaction spends them
+        let mut spent_note_id = request.note_id;
+        for SpendNoteResponse {
             transaction,
             recipient_note,
             change_note,
-        })
+        } in responses
+        {
+            let transaction = self.node.extensions().sign_and_submit(transaction).await?;
+            self.store.notes().delete_note(spent_note_id)?;
+            if let Some(change_note) = &change_note {
+                spent_note_id = self.store.notes().add_note(change_note)?.id;
+            }
+            signed_responses.push(SignedSpendNoteResponse {
+                transaction,
+                recipient_note,
+                change_note,
+            });
+        }
+        Ok(signed_responses)
     }
 
-    pub async fn redeem_note(
+    /// Create note spending transactions for signing by an external wallet, in order of submission.
+    /// The ownership signature in note history is still made with the note owner's key, so the note owner must be a key of the
+    /// node wallet and only inputs funding the transactions may be signed externally
+    pub async fn spend_note_unsigned(
         &self,
-        request: RedeemNoteRequest,
-    ) -> Result<Transaction, TransactionServiceError> {
-        let note_box = self.store.notes().get_note_box(request.note_id)?;
-        let reserve_box = self
+        request: SpendNoteRequest,
+    ) -> Result<Vec<Eip12UnsignedTransaction>, TransactionServiceError> {
+        let (responses, boxes) = self.build_spend_note(&request).await?;
+        self.validate(
+            &responses
+                .iter()
+                .map(|response| &response.transaction)
+                .collect::<Vec<_>>(),
+            &boxes,
+        )
+        .await?;
+        // Each transaction replaces the note it spends with its change note, so store follows the chain as transactions are submitted
+        let mut spent_box_id = self
             .store
-            .reserves()
-            .get_reserve_by_identifier(&request.reserve_id)?;
-        let receipt_contract = self.compiler.receipt_contract().await?;
-        let is_mainnet = self.node.endpoints().root()?.info().await?.network == "mainnet";
-        let buyback_box = &self
+            .notes()
+            .get_note_box(request.note_id)?
+            .ergo_box()
+            .box_id();
+        responses
+            .into_iter()
+            .map(|response| {
+                let mut updates = vec![StoreUpdate::DeleteNote(spent_box_id)];
+                if let Some(change_note) = response.change_note {
+                    spent_box_id = change_note.ergo_box().box_id();
+                    updates.push(StoreUpdate::AddNote(change_note));
+                }
+                self.export_unsigned(&response.transaction, &boxes, updates)
+            })
+            .collect()
+    }
+
+    // Fetch gold oracle box, rejecting it if it is malformed or its price is stale
+    async fn oracle_box(
+        &self,
+        current_height: u32,
+    ) -> Result<OracleBoxSpec, TransactionServiceError> {
+        let oracle_nft = self.network.oracle_nft;
+        let oracle_box = self.unspent_box_by_token_id(oracle_nft).await?;
+        let oracle_box =
+            OracleBoxSpec::new(&oracle_box, oracle_nft).map_err(TransactionError::from)?;
+        oracle_box
+            .check_age(current_height, MAX_ORACLE_AGE)
+            .map_err(TransactionError::from)?;
+        Ok(oracle_box)
+    }
+
+    /// Unspent boxes holding `token_id`, latest first. Includes boxes of unconfirmed transactions
+    pub(crate) async fn unspent_boxes_by_token_id(
+        &self,
+        token_id: TokenId,
+    ) -> Result<Vec<ErgoBox>, TransactionServiceError> {
+        Ok(self
             .node
             .endpoints()
             .blockchain()?
             .get_unspent_boxes_by_token_id(
-                &String::from(buyback_nft(is_mainnet)),
+                &String::from(token_id),
                 IndexQuery {
                     offset: 0,
-                    limit: 1,
+                    limit: 100,
                     sort_direction:
                         ergo_client::node::endpoints::blockchain::SortDirection::Descending,
                     include_unconfirmed: true,
                 },
             )
-            .await?[0]
-            .ergo_box;
-        let wallet_boxes = self.node.extensions().get_utxos().await?;
-        let tx_context = self.get_tx_ctx().await?;
-        let oracle_box = &self
-            .node
-            .endpoints()
-            .blockchain()?
-            .get_unspent_boxes_by_token_id(
-                &String::from(oracle_nft(is_mainnet)),
-                IndexQuery {
-                    offset: 0,
-                    limit: 1,
-                    sort_direction:
-                        ergo_client::node::endpoints::blockchain::SortDirection::Descending,
-                    include_unconfirmed: true,
+            .await?
+            .into_iter()
+            .map(|indexed_box| indexed_box.ergo_box)
+            .collect())
+    }
+
+    /// Find the latest unspent box holding `token_id`, used for fetching oracle, buyback and reserve boxes
+    pub(crate) async fn unspent_box_by_token_id(
+        &self,
+        token_id: TokenId,
+    ) -> Result<ErgoBox, TransactionServiceError> {
+        // Box may be briefly missing while the transaction spending it is unconfirmed
+        self.unspent_boxes_by_token_id(token_id)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(TransactionServiceError::MissingBox(token_id))
+    }
+
+    async fn build_redeem_note(
+        &self,
+        request: RedeemNoteRequest,
+    ) -> Result<(UnsignedTransaction, Vec<ErgoBox>, Vec<StoreUpdate>), TransactionServiceError>
+    {
+        let note_box = self.store.notes().get_note_box(request.note_id)?;
+        let reserve_box = self
+            .store
+            .reserves()
+            .get_reserve_by_identifier(&request.reserve_id)?;
+        let receipt_contract = self.compiler.receipt_contract();
+        let buyback_box = self
+            .unspent_box_by_token_id(self.network.buyback_nft)
+            .await?;
+        let (wallet_boxes, protected) = self.wallet_boxes(&request.allowed_tokens).await?;
+        let mut tx_context = self.get_tx_ctx().await?;
+        let oracle_box = self.oracle_box(tx_context.current_height).await?;
+        let tx = self
+            .fee_policy
+            .build(
+                &mut tx_context,
+                |tx_context| {
+                    redeem_note(
+                        &note_box,
+                        &reserve_box,
+                        &oracle_box,
+                        &buyback_box,
+                        receipt_contract,
+                        wallet_boxes.clone(),
+                        tx_context,
+                    )
                 },
+                |tx| vec![tx],
             )
-            .await?[0]
-            .ergo_box;
-        let tx = redeem_note(
-            &note_box,
-            &reserve_box,
-            &oracle_box,
-            &buyback_box,
-            receipt_contract,
-            wallet_boxes,
-            &tx_context,
-        )?;
+            .map_err(|e| protected.explain(e))?;
+        let mut boxes = wallet_boxes;
+        boxes.extend([
+            note_box.ergo_box().clone(),
+            reserve_box.ergo_box().clone(),
+            oracle_box.ergo_box().clone(),
+            buyback_box,
+        ]);
+        let updates = Self::redemption_updates(&tx, note_box.ergo_box().box_id())?;
+        Ok((tx, boxes, updates))
+    }
+
+    // Redeemed note is spent and reserve box is replaced by the first output. Receipt created by the redemption is added by the receipt scanner
+    fn redemption_updates(
+        tx: &UnsignedTransaction,
+        note_box_id: BoxId,
+    ) -> Result<Vec<StoreUpdate>, TransactionServiceError> {
+        let reserve_box =
+            ReserveBoxSpec::try_from(tx.outputs().first()).map_err(TransactionError::from)?;
+        Ok(vec![
+            StoreUpdate::DeleteNote(note_box_id),
+            StoreUpdate::AddReserve(reserve_box),
+        ])
+    }
+
+    pub async fn redeem_note(
+        &self,
+        request: RedeemNoteRequest,
+    ) -> Result<Transaction, TransactionServiceError> {
+        let (tx, boxes, updates) = self.build_redeem_note(request).await?;
+        self.validate(&[&tx], &boxes).await?;
         let tx = self.node.extensions().sign_and_submit(tx).await?;
+        self.apply_store_updates(updates)?;
         Ok(tx)
     }
+
+    /// Create a note redemption transaction for signing by an external wallet
+    pub async fn redeem_note_unsigned(
+        &self,
+        request: RedeemNoteRequest,
+    ) -> Result<Eip12UnsignedTransaction, TransactionServiceError> {
+        let (tx, boxes, updates) = self.build_redeem_note(request).await?;
+        self.validate(&[&tx], &boxes).await?;
+        self.export_unsigned(&tx, &boxes, updates)
+    }
+
+    /// Current gold price in nanoERG per mg from the oracle box
+    pub async fn nanoerg_per_mg(&self) -> Result<u64, TransactionServiceError> {
+        let current_height = self.node.endpoints().root()?.info().await?.full_height as u32;
+        Ok(self.oracle_box(current_height).await?.nanoerg_per_mg() as u64)
+    }
+
+    /// Quote redemption of note against every reserve in its history, without building a transaction
+    pub async fn redemption_quotes(
+        &self,
+        request: RedemptionQuoteRequest,
+    ) -> Result<Vec<RedemptionQuote>, TransactionServiceError> {
+        let note_box = self.store.notes().get_note_box(request.note_id)?;
+        let current_height = self.node.endpoints().root()?.info().await?.full_height as u32;
+        let oracle_box = self.oracle_box(current_height).await?;
+        let mut reserve_ids: Vec<TokenId> = vec![];
+        for entry in note_box.history.ownership_entries() {
+            if !reserve_ids.contains(&entry.reserve_id) {
+                reserve_ids.push(entry.reserve_id);
+            }
+        }
+        reserve_ids
+            .iter()
+            .map(|reserve_id| -> Result<_, TransactionServiceError> {
+                let reserve_box = self
+                    .store
+                    .reserves()
+                    .get_reserve_by_identifier(reserve_id)?;
+                Ok(redemption_quote(&note_box, &reserve_box, &oracle_box)?)
+            })
+            .collect()
+    }
+
+    /// Redeem a receipt against the reserve of an earlier signer in the note's history
+    pub async fn redeem_receipt(
+        &self,
+        request: RedeemReceiptRequest,
+    ) -> Result<Transaction, TransactionServiceError> {
+        let receipt = self.store.receipts().get_receipt_box(request.receipt_id)?;
+        let reserve_box = self
+            .store
+            .reserves()
+            .get_reserve_by_identifier(&request.reserve_id)?;
+        let receipt_contract = self.compiler.receipt_contract();
+        let buyback_box = self
+            .unspent_box_by_token_id(self.network.buyback_nft)
+            .await?;
+        let (wallet_boxes, protected) = self.wallet_boxes(&request.allowed_tokens).await?;
+        let mut tx_context = self.get_tx_ctx().await?;
+        let oracle_box = self.oracle_box(tx_context.current_height).await?;
+        let tx = self
+            .fee_policy
+            .build(
+                &mut tx_context,
+                |tx_context| {
+                    redeem_receipt(
+                        &receipt,
+                        &reserve_box,
+                        &oracle_box,
+                        &buyback_box,
+                        receipt_contract,
+                        wallet_boxes.clone(),
+                        tx_context,
+                    )
+                },
+                |tx| vec![tx],
+            )
+            .map_err(|e| protected.explain(e))?;
+        let mut boxes = wallet_boxes;
+        boxes.extend([
+            receipt.ergo_box().clone(),
+            reserve_box.ergo_box().clone(),
+            oracle_box.ergo_box().clone(),
+            buyback_box,
+        ]);
+        self.validate(&[&tx], &boxes).await?;
+        let tx = self.node.extensions().sign_and_submit(tx).await?;
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use chaincash_mock_node::MockNode;
+    use chaincash_offchain::boxes::{Note, ReserveBoxSpec};
+    use chaincash_offchain::transactions::{
+        notes::MintNoteRequest, notes::NoteOutput, reserves::MintReserveRequest,
+    };
+    use ergo_lib::chain::transaction::{ergo_transaction::ErgoTransaction, Transaction};
+    use ergo_lib::ergo_chain_types::EcPoint;
+    use ergo_lib::ergotree_ir::chain::address::NetworkPrefix;
+
+    use super::{RedeemNoteRequest, SpendNoteRequest};
+    use crate::scanner::start_scanner;
+    use crate::test_util::{add_redemption_boxes, server_state, NANOERG_PER_KG};
+    use crate::ServerState;
+
+    // Mint a reserve owned by `owner` and a 100mg note, spend 30mg of it to `recipient` and redeem the 70mg change note
+    // against the reserve. Returns the reserve before redemption, the recipient note and the redemption transaction
+    async fn redeem_change_note(
+        state: &ServerState,
+        owner: EcPoint,
+        recipient: EcPoint,
+    ) -> (ReserveBoxSpec, Note, Transaction) {
+        let service = state.tx_service();
+        let reserve = service
+            .mint_reserve(MintReserveRequest {
+                public_key_hex: String::from(owner.clone()),
+                amount: 10_000_000_000,
+            })
+            .await
+            .unwrap()
+            .reserve_box;
+        let note = service
+            .mint_note(MintNoteRequest {
+                owner_public_key_hex: String::from(owner),
+                gold_amount_mg: 100,
+            })
+            .await
+            .unwrap()
+            .note;
+        let (note_id, _) = state
+            .store
+            .notes()
+            .get_by_box_id(&note.ergo_box().box_id())
+            .unwrap()
+            .unwrap();
+        let spent = service
+            .spend_note(SpendNoteRequest {
+                note_id,
+                reserve_id: reserve.identifier,
+                outputs: vec![],
+                recipient_pubkey: Some(recipient),
+                amount: Some(30),
+                change_pubkey: None,
+                allowed_tokens: vec![],
+            })
+            .await
+            .unwrap()
+            .remove(0);
+        let (change_note_id, _) = state
+            .store
+            .notes()
+            .get_by_box_id(&spent.change_note.unwrap().ergo_box().box_id())
+            .unwrap()
+            .unwrap();
+        let redemption = service
+            .redeem_note(RedeemNoteRequest {
+                note_id: change_note_id,
+                reserve_id: reserve.identifier,
+                allowed_tokens: vec![],
+            })
+            .await
+            .unwrap();
+        (reserve, spent.recipient_note, redemption)
+    }
+
+    // Mint a reserve and a note and spend it through the service against a mock node, checking store follows the chain
+    #[tokio::test]
+    async fn test_mint_and_spend_note() {
+        let node = MockNode::start(NetworkPrefix::Mainnet).await.unwrap();
+        let (owner, recipient) = {
+            let mut chain = node.chain();
+            chain.add_wallet_box(100_000_000_000, vec![]);
+            (chain.wallet_public_key(), chain.add_wallet_key())
+        };
+        let state = server_state(&node);
+        let service = state.tx_service();
+
+        let reserve = service
+            .mint_reserve(MintReserveRequest {
+                public_key_hex: String::from(owner.clone()),
+                amount: 10_000_000_000,
+            })
+            .await
+            .unwrap()
+            .reserve_box;
+        assert!(node.chain().box_by_id(&reserve.box_id()).is_ok());
+        let note = service
+            .mint_note(MintNoteRequest {
+                owner_public_key_hex: String::from(owner.clone()),
+                gold_amount_mg: 100,
+            })
+            .await
+            .unwrap()
+            .note;
+        let (note_id, _) = state
+            .store
+            .notes()
+            .get_by_box_id(&note.ergo_box().box_id())
+            .unwrap()
+            .unwrap();
+
+        let responses = service
+            .spend_note(SpendNoteRequest {
+                note_id,
+                reserve_id: reserve.identifier,
+                outputs: vec![],
+                recipient_pubkey: Some(recipient.clone()),
+                amount: Some(30),
+                change_pubkey: None,
+                allowed_tokens: vec![],
+            })
+            .await
+            .unwrap();
+        assert_eq!(responses.len(), 1);
+        let recipient_note = &responses[0].recipient_note;
+        let change_note = responses[0].change_note.as_ref().unwrap();
+        assert_eq!(
+            (
+                recipient_note.owner.clone(),
+                *recipient_note.amount.as_u64()
+            ),
+            (recipient, 30)
+        );
+        assert_eq!(
+            (change_note.owner.clone(), *change_note.amount.as_u64()),
+            (owner, 70)
+        );
+        assert_eq!(recipient_note.history.ownership_entries().len(), 1);
+        for created in [recipient_note, change_note] {
+            assert!(node.chain().box_by_id(&created.ergo_box().box_id()).is_ok());
+        }
+        // Spent note is replaced by change note in store
+        let notes = state.store.notes();
+        assert!(notes
+            .get_by_box_id(&note.ergo_box().box_id())
+            .unwrap()
+            .is_none());
+        assert!(notes
+            .get_by_box_id(&change_note.ergo_box().box_id())
+            .unwrap()
+            .is_some());
+    }
+
+    // Redeem a note through the service with oracle and buyback boxes on chain, checking reserve pays out the note value
+    #[tokio::test]
+    async fn test_redeem_note() {
+        let node = MockNode::start(NetworkPrefix::Mainnet).await.unwrap();
+        let (owner, recipient) = {
+            let mut chain = node.chain();
+            chain.add_wallet_box(100_000_000_000, vec![]);
+            add_redemption_boxes(&mut chain);
+            (chain.wallet_public_key(), chain.add_wallet_key())
+        };
+        let state = server_state(&node);
+        let (reserve, _, redemption) = redeem_change_note(&state, owner, recipient).await;
+
+        assert!(node
+            .chain()
+            .box_by_id(&redemption.outputs()[1].box_id())
+            .is_ok());
+        // 70mg at oracle price after 2% haircut
+        let redeemable = (70 * NANOERG_PER_KG as u64 / 1_000_000) * 98 / 100;
+        let reserve_after = state
+            .store
+            .reserves()
+            .get_reserve_by_identifier(&reserve.identifier)
+            .unwrap();
+        assert_eq!(
+            reserve_after.ergo_box().box_id(),
+            redemption.outputs()[0].box_id()
+        );
+        assert_eq!(
+            reserve_after.ergo_box().value.as_u64(),
+            &(reserve.ergo_box().value.as_u64() - redeemable)
+        );
+        // Redeemed note is removed from store, only the receipt scanner tracks the receipt
+        assert!(state
+            .store
+            .notes()
+            .get_by_box_id(&redemption.inputs.first().box_id)
+            .unwrap()
+            .is_none());
+    }
+
+    // Clear the store after minting, spending and redeeming, and check scanners rebuild it from the mock node's scans
+    #[tokio::test]
+    async fn test_rescan() {
+        let node = MockNode::start(NetworkPrefix::Mainnet).await.unwrap();
+        let (owner, recipient) = {
+            let mut chain = node.chain();
+            chain.add_wallet_box(100_000_000_000, vec![]);
+            add_redemption_boxes(&mut chain);
+            (chain.wallet_public_key(), chain.add_wallet_key())
+        };
+        let state = Arc::new(server_state(&node));
+        let (reserve, recipient_note, redemption) =
+            redeem_change_note(&state, owner, recipient).await;
+        let receipt_box_id = redemption.outputs()[1].box_id();
+
+        let store = &state.store;
+        store.notes().delete_not_in(std::iter::empty()).unwrap();
+        store.reserves().delete_not_in(std::iter::empty()).unwrap();
+        store.receipts().delete_not_in(std::iter::empty()).unwrap();
+        assert!(store.receipts().receipts().unwrap().is_empty());
+        assert!(store
+            .reserves()
+            .get_reserve_by_identifier(&reserve.identifier)
+            .is_err());
+
+        start_scanner(state.clone()).await.unwrap();
+        let mut rebuilt = false;
+        for _ in 0..50 {
+            rebuilt = store
+                .reserves()
+                .get_reserve_by_identifier(&reserve.identifier)
+                .is_ok()
+                && store
+                    .notes()
+                    .get_by_box_id(&recipient_note.ergo_box().box_id())
+                    .unwrap()
+                    .is_some()
+                && store
+                    .receipts()
+                    .get_by_box_id(&receipt_box_id)
+                    .unwrap()
+                    .is_some();
+            if rebuilt {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(rebuilt, "scanners didn't rebuild store");
+
+        let reserve_box = store
+            .reserves()
+            .get_reserve_by_identifier(&reserve.identifier)
+            .unwrap();
+        assert_eq!(
+            reserve_box.ergo_box().box_id(),
+            redemption.outputs()[0].box_id()
+        );
+        // Note history and receipt history are recovered from the spend and redemption transactions
+        let (_, note) = store
+            .notes()
+            .get_by_box_id(&recipient_note.ergo_box().box_id())
+            .unwrap()
+            .unwrap();
+        assert_eq!(note.history.digest(), recipient_note.history.digest());
+        let (_, receipt) = store
+            .receipts()
+            .get_by_box_id(&receipt_box_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.receipt_box.reserve_owner, reserve.owner);
+        assert_eq!(receipt.history.ownership_entries().len(), 1);
+    }
 }
@@ -0,0 +1,2 @@
+DROP INDEX receipt_reserve_owner_idx;
+DROP TABLE receipts;
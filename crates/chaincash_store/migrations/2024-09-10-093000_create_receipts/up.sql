@@ -0,0 +1,13 @@
+CREATE TABLE receipts (
+  id INTEGER PRIMARY KEY NOT NULL,
+  identifier CHAR(32) NOT NULL,
+  box_id INTEGER NOT NULL,
+  amount BIGINT NOT NULL,
+  position BIGINT NOT NULL,
+  height INTEGER NOT NULL,
+  reserve_owner CHAR(32) NOT NULL,
+  FOREIGN KEY (box_id) REFERENCES ergo_boxes (id)
+    ON DELETE CASCADE
+);
+
+CREATE INDEX receipt_reserve_owner_idx ON receipts(reserve_owner);
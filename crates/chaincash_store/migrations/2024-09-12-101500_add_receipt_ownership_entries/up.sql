@@ -0,0 +1,22 @@
+-- Ownership entries belong to either a note or a receipt
+CREATE TEMPORARY TABLE ownership_entries_temp AS SELECT id, note_id, amount, position, reserve_nft_id, signature FROM ownership_entries;
+
+DROP TABLE ownership_entries;
+CREATE TABLE ownership_entries (
+  id INTEGER PRIMARY KEY NOT NULL,
+  note_id INTEGER,
+  receipt_id INTEGER,
+  amount BIGINT NOT NULL,
+  position BIGINT NOT NULL,
+  reserve_nft_id CHAR(32) NOT NULL,
+  signature BLOB NOT NULL,
+  FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE,
+  FOREIGN KEY (receipt_id) REFERENCES receipts (id) ON DELETE CASCADE,
+  CHECK ((note_id IS NULL) <> (receipt_id IS NULL))
+);
+
+INSERT INTO ownership_entries (id, note_id, amount, position, reserve_nft_id, signature) SELECT id, note_id, amount, position, reserve_nft_id, signature FROM ownership_entries_temp;
+DROP TABLE ownership_entries_temp;
+
+-- Receipts scanned before this migration have no history, delete them so they're picked up again by the scanner
+DELETE FROM ergo_boxes WHERE id IN (SELECT box_id FROM receipts);
@@ -0,0 +1 @@
+ALTER TABLE reserves DROP COLUMN refund_height;
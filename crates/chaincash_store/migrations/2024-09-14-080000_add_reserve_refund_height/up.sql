@@ -0,0 +1 @@
+ALTER TABLE reserves ADD COLUMN refund_height BIGINT;
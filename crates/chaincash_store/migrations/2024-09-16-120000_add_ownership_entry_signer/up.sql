@@ -0,0 +1,17 @@
+-- Signer of ownership entries can't be recovered from existing rows, delete scanned notes and receipts so their history is rebuilt by the scanner
+DELETE FROM ergo_boxes WHERE id IN (SELECT box_id FROM notes UNION SELECT box_id FROM receipts);
+
+DROP TABLE ownership_entries;
+CREATE TABLE ownership_entries (
+  id INTEGER PRIMARY KEY NOT NULL,
+  note_id INTEGER,
+  receipt_id INTEGER,
+  amount BIGINT NOT NULL,
+  position BIGINT NOT NULL,
+  reserve_nft_id CHAR(32) NOT NULL,
+  signature BLOB NOT NULL,
+  signer CHAR(33) NOT NULL,
+  FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE,
+  FOREIGN KEY (receipt_id) REFERENCES receipts (id) ON DELETE CASCADE,
+  CHECK ((note_id IS NULL) <> (receipt_id IS NULL))
+);
@@ -14,3 +14,10 @@ pub enum Error {
     #[error("Failed to extract spec from box")]
     BoxSpec(#[from] chaincash_offchain::boxes::Error),
 }
+
+impl Error {
+    /// Whether the requested row doesn't exist
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::Diesel(diesel::result::Error::NotFound))
+    }
+}
@@ -1,20 +1,26 @@
 pub mod ergo_boxes;
 pub mod error;
 pub mod notes;
+pub mod receipts;
 pub mod reserves;
 pub mod scans;
 pub mod schema;
 
 use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, CustomizeConnection, ManageConnection, Pool};
 use diesel::SqliteConnection;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use ergo_boxes::ErgoBoxRepository;
+use ergo_lib::ergo_chain_types::Digest32;
+use ergo_lib::ergotree_ir::chain::token::TokenId;
 pub use error::Error;
 use notes::NoteRepository;
+use receipts::ReceiptRepository;
 use reserves::ReserveRepository;
 use scans::ScanRepository;
 use std::borrow::BorrowMut;
+use std::collections::HashSet;
 
 #[derive(serde::Deserialize, Debug)]
 pub struct Config {
@@ -69,6 +75,10 @@ impl ChainCashStore {
         ReserveRepository::new(self.pool.clone())
     }
 
+    pub fn receipts(&self) -> ReceiptRepository {
+        ReceiptRepository::new(self.pool.clone())
+    }
+
     pub fn ergo_boxes(&self) -> ErgoBoxRepository {
         ErgoBoxRepository::new(self.pool.clone())
     }
@@ -76,6 +86,30 @@ impl ChainCashStore {
     pub fn scans(&self) -> ScanRepository {
         ScanRepository::new(self.pool.clone())
     }
+
+    /// Token ids of all notes, reserves and receipts in store
+    pub fn tracked_token_ids(&self) -> Result<HashSet<TokenId>, Error> {
+        let mut conn = self.pool.get()?;
+        let notes = schema::notes::table
+            .select(schema::notes::identifier)
+            .load::<String>(&mut conn)?;
+        let reserves = schema::reserves::table
+            .select(schema::reserves::identifier)
+            .load::<String>(&mut conn)?;
+        let receipts = schema::receipts::table
+            .select(schema::receipts::identifier)
+            .load::<String>(&mut conn)?;
+        Ok(notes
+            .into_iter()
+            .chain(reserves)
+            .chain(receipts)
+            .map(|identifier| {
+                Digest32::try_from(identifier)
+                    .expect("Failed to parse token id from database")
+                    .into()
+            })
+            .collect())
+    }
 }
 
 impl Update for ChainCashStore {
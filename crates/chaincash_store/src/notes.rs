@@ -10,13 +10,14 @@ use diesel::{
     Selectable, SelectableHelper,
 };
 use ergo_lib::{
-    ergo_chain_types::EcPoint,
+    ergo_chain_types::{ADDigest, EcPoint},
     ergotree_ir::chain::{self, ergo_box::BoxId, token::TokenId},
 };
 use serde::Serialize;
 
 use crate::{
     ergo_boxes::{ErgoBox, ErgoBoxRepository},
+    receipts::Receipt,
     schema, ConnectionPool, ConnectionType, Error,
 };
 
@@ -46,16 +47,28 @@ struct NewNote<'a> {
 #[derive(Queryable, Identifiable, Selectable, Associations, PartialEq, Serialize)]
 #[diesel(table_name = schema::ownership_entries)]
 #[diesel(belongs_to(Note))]
+#[diesel(belongs_to(Receipt))]
 pub struct OwnershipEntry {
     #[serde(skip)]
     id: i32,
     #[serde(skip)]
-    note_id: i32,
+    note_id: Option<i32>,
+    #[serde(skip)]
+    receipt_id: Option<i32>,
     amount: i64,
     position: i64,
     reserve_nft_id: String,
     #[serde(skip)]
     signature: Vec<u8>,
+    /// Owner of the note that signed this entry, GE encoded as hex string.
+    signer: String,
+}
+
+impl OwnershipEntry {
+    /// Owner of the note that signed this entry, GE encoded as hex string.
+    pub fn signer(&self) -> &str {
+        &self.signer
+    }
 }
 
 impl TryInto<chaincash_offchain::note_history::OwnershipEntry> for OwnershipEntry {
@@ -65,11 +78,13 @@ impl TryInto<chaincash_offchain::note_history::OwnershipEntry> for OwnershipEntr
         let signature = chaincash_offchain::note_history::Signature::try_from(&self.signature[..])?;
         // TODO: Add FromStr for TokenId and BoxId to avoid this hack
         let reserve_id = TokenId::from(BoxId::try_from(self.reserve_nft_id)?);
+        let signer = EcPoint::try_from(self.signer)?;
 
         Ok(chaincash_offchain::note_history::OwnershipEntry {
             reserve_id,
             amount: self.amount as u64,
             signature,
+            signer,
         })
     }
 }
@@ -78,11 +93,13 @@ impl TryInto<chaincash_offchain::note_history::OwnershipEntry> for OwnershipEntr
 #[diesel(table_name = schema::ownership_entries)]
 #[diesel(belongs_to(Note, foreign_key = note_id))]
 struct NewOwnershipEntry {
-    note_id: i32,
+    note_id: Option<i32>,
+    receipt_id: Option<i32>,
     amount: i64,
     position: i64,
     reserve_nft_id: String,
     signature: Vec<u8>,
+    signer: String,
 }
 
 /// Note with ownership entries. Used for listing notes. Unlike [`chaincash_offchain::boxes::Note`] this includes primary key to uniquely identify a Note
@@ -93,6 +110,83 @@ pub struct NoteWithHistory {
     pub history: Vec<OwnershipEntry>,
 }
 
+impl NoteWithHistory {
+    /// Issuer of the note, GE encoded as hex string. This is the signer of the first ownership entry, or the current owner if note was never spent
+    pub fn issuer(&self) -> &str {
+        self.history
+            .first()
+            .map(OwnershipEntry::signer)
+            .unwrap_or(&self.note.owner)
+    }
+
+    /// Signers of ownership entries in order of position
+    pub fn signers(&self) -> Vec<String> {
+        self.history
+            .iter()
+            .map(|entry| entry.signer.clone())
+            .collect()
+    }
+
+    /// Reserve NFT ids of ownership entries in order of position
+    pub fn reserves(&self) -> Vec<String> {
+        self.history
+            .iter()
+            .map(|entry| entry.reserve_nft_id.clone())
+            .collect()
+    }
+}
+
+/// Row that a note history belongs to
+pub(crate) enum HistoryOwner {
+    Note(i32),
+    Receipt(i32),
+}
+
+pub(crate) fn add_history(
+    conn: &mut ConnectionType,
+    owner: HistoryOwner,
+    note_history: &NoteHistory,
+) -> Result<(), Error> {
+    let (note_id, receipt_id) = match owner {
+        HistoryOwner::Note(id) => (Some(id), None),
+        HistoryOwner::Receipt(id) => (None, Some(id)),
+    };
+    let ownership_entries: Vec<NewOwnershipEntry> = note_history
+        .ownership_entries()
+        .iter()
+        .enumerate()
+        .map(|(i, ownership_entry)| NewOwnershipEntry {
+            note_id,
+            receipt_id,
+            amount: ownership_entry.amount as i64,
+            position: i as i64,
+            reserve_nft_id: ownership_entry.reserve_id.into(),
+            signature: ownership_entry.signature.serialize(),
+            signer: ownership_entry.signer.to_string(),
+        })
+        .collect();
+    diesel::insert_into(schema::ownership_entries::table)
+        .values(ownership_entries)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Load note history from ownership entries ordered by position and the digest committed to by the box holding it. The tree
+/// is only rebuilt if no prover for the digest is cached
+pub(crate) fn load_history(
+    ownership_entries: Vec<OwnershipEntry>,
+    digest: ADDigest,
+) -> NoteHistory {
+    NoteHistory::from_entries_with_digest(
+        ownership_entries.into_iter().map(|ownership_entry| {
+            ownership_entry
+                .try_into()
+                .expect("Failed to parse ownership entry from DB")
+        }),
+        digest,
+    )
+}
+
 pub struct NoteRepository {
     pool: ConnectionPool,
 }
@@ -102,30 +196,6 @@ impl NoteRepository {
         Self { pool }
     }
 
-    fn add_history(
-        &self,
-        conn: &mut ConnectionType,
-        note: &Note,
-        note_history: &chaincash_offchain::note_history::NoteHistory,
-    ) -> Result<(), Error> {
-        let ownership_entries: Vec<NewOwnershipEntry> = note_history
-            .ownership_entries()
-            .iter()
-            .enumerate()
-            .map(|(i, ownership_entry)| NewOwnershipEntry {
-                note_id: note.id,
-                amount: ownership_entry.amount as i64,
-                position: i as i64,
-                reserve_nft_id: ownership_entry.reserve_id.into(),
-                signature: ownership_entry.signature.serialize(),
-            })
-            .collect();
-        diesel::insert_into(schema::ownership_entries::table)
-            .values(ownership_entries)
-            .execute(conn)?;
-        Ok(())
-    }
-
     /// Attempt to load a Note box from database.
     pub fn get_note_box(&self, note_id: i32) -> Result<chaincash_offchain::boxes::Note, Error> {
         let mut conn = self.pool.get()?;
@@ -135,6 +205,7 @@ impl NoteRepository {
             .select((Note::as_select(), ErgoBox::as_select()))
             .first(conn.borrow_mut())?;
         let ownership_entries = OwnershipEntry::belonging_to(&note)
+            .order_by(schema::ownership_entries::position.asc())
             .select(OwnershipEntry::as_select())
             .load(conn.borrow_mut())?;
 
@@ -142,16 +213,9 @@ impl NoteRepository {
         let ergo_box: chain::ergo_box::ErgoBox = ergo_box
             .try_into()
             .expect("Failed to parse ergo box from DB");
-        let mut note_history = NoteHistory::new();
-        for ownership_entry in ownership_entries {
-            note_history
-                .add_commitment(
-                    ownership_entry
-                        .try_into()
-                        .expect("Failed to parse ownership entry from DB"),
-                )
-                .expect("Duplicate ADReserve Key when reading history from DB");
-        }
+        let digest = chaincash_offchain::boxes::Note::history_digest(&ergo_box)
+            .expect("Failed to parse note from DB");
+        let note_history = load_history(ownership_entries, digest);
 
         Ok(chaincash_offchain::boxes::Note::new(ergo_box, note_history)
             .expect("Failed to parse note from DB"))
@@ -173,15 +237,60 @@ impl NoteRepository {
             .transpose()
     }
 
+    pub fn get_by_identifier(
+        &self,
+        identifier: &TokenId,
+    ) -> Result<Option<(i32, chaincash_offchain::boxes::Note)>, Error> {
+        let mut conn = self.pool.get()?;
+        let note_id = schema::notes::table
+            .filter(schema::notes::identifier.eq(String::from(*identifier)))
+            .select(schema::notes::id)
+            .first::<i32>(&mut conn)
+            .optional()?;
+        note_id
+            .map(|id| Ok((id, self.get_note_box(id)?)))
+            .transpose()
+    }
+
     pub fn notes_by_pubkeys(&self, pubkeys: &[EcPoint]) -> Result<Vec<NoteWithHistory>, Error> {
         let mut conn = self.pool.get()?;
         let notes = schema::notes::table
             .filter(schema::notes::owner.eq_any(pubkeys.iter().cloned().map(String::from)))
             .select(Note::as_select())
             .load(conn.borrow_mut())?;
+        Self::with_history(&mut conn, notes)
+    }
+
+    /// Notes issued by `issuer`, see [`NoteWithHistory::issuer`]
+    pub fn notes_by_issuer(&self, issuer: &EcPoint) -> Result<Vec<NoteWithHistory>, Error> {
+        let mut conn = self.pool.get()?;
+        let issuer = String::from(issuer.clone());
+        let first_signed = schema::ownership_entries::table
+            .filter(schema::ownership_entries::position.eq(0))
+            .filter(schema::ownership_entries::signer.eq(&issuer))
+            .select(schema::ownership_entries::note_id);
+        // Entries of receipts have no note id, NOT IN would never match if NULL is in the subquery
+        let has_history = schema::ownership_entries::table
+            .filter(schema::ownership_entries::note_id.is_not_null())
+            .select(schema::ownership_entries::note_id);
+        let notes = schema::notes::table
+            .filter(schema::notes::id.nullable().eq_any(first_signed).or(
+                schema::notes::owner.eq(&issuer).and(diesel::dsl::not(
+                    schema::notes::id.nullable().eq_any(has_history),
+                )),
+            ))
+            .select(Note::as_select())
+            .load(conn.borrow_mut())?;
+        Self::with_history(&mut conn, notes)
+    }
+
+    fn with_history(
+        conn: &mut ConnectionType,
+        notes: Vec<Note>,
+    ) -> Result<Vec<NoteWithHistory>, Error> {
         Ok(OwnershipEntry::belonging_to(&notes)
             .order_by(schema::ownership_entries::position.asc())
-            .load(conn.borrow_mut())?
+            .load(conn)?
             .grouped_by(&notes)
             .into_iter()
             .zip(notes)
@@ -205,7 +314,11 @@ impl NoteRepository {
                 .values(&new_note)
                 .returning(Note::as_returning())
                 .get_result(conn.borrow_mut())?;
-            self.add_history(conn.borrow_mut(), &inserted_note, &note.history)?;
+            add_history(
+                conn.borrow_mut(),
+                HistoryOwner::Note(inserted_note.id),
+                &note.history,
+            )?;
             Ok(inserted_note)
         })
     }
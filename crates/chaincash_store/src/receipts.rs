@@ -0,0 +1,152 @@
+use crate::ergo_boxes::ErgoBox;
+use crate::ergo_boxes::ErgoBoxRepository;
+use crate::notes::{add_history, load_history, HistoryOwner, OwnershipEntry};
+use crate::schema;
+use crate::ConnectionPool;
+use crate::Error;
+use chaincash_offchain::boxes::{Receipt as ReceiptBox, ReceiptBoxSpec};
+use diesel::dsl::delete;
+use diesel::prelude::*;
+use ergo_lib::ergo_chain_types::EcPoint;
+use ergo_lib::ergotree_ir::chain;
+use ergo_lib::ergotree_ir::chain::ergo_box::BoxId;
+use serde::Serialize;
+use std::borrow::BorrowMut;
+
+#[derive(Queryable, Identifiable, Selectable, Associations, Serialize)]
+#[diesel(belongs_to(ErgoBox, foreign_key = box_id))]
+#[diesel(table_name = schema::receipts)]
+pub struct Receipt {
+    pub id: i32,
+    #[serde(skip)]
+    pub box_id: i32,
+    /// Token ID of the redeemed note
+    pub identifier: String,
+    /// Amount of note tokens redeemed
+    pub amount: i64,
+    /// Position in note history of the last redeemed ownership entry
+    pub position: i64,
+    /// Height the receipt was created at
+    pub height: i32,
+    /// Owner of the reserve the note was redeemed against, GE encoded as hex string.
+    pub reserve_owner: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::receipts)]
+pub struct NewReceipt<'a> {
+    pub box_id: i32,
+    pub identifier: &'a str,
+    pub amount: i64,
+    pub position: i64,
+    pub height: i32,
+    pub reserve_owner: &'a str,
+}
+
+pub struct ReceiptRepository {
+    pool: ConnectionPool,
+}
+
+impl ReceiptRepository {
+    pub(crate) fn new(pool: ConnectionPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn add(&self, receipt: &ReceiptBox) -> Result<Receipt, Error> {
+        let mut conn = self.pool.get()?;
+        conn.transaction(|conn| {
+            let receipt_box = &receipt.receipt_box;
+            let created_box =
+                ErgoBoxRepository::add_with_conn(conn.borrow_mut(), receipt_box.ergo_box())?;
+            let new_receipt = NewReceipt {
+                box_id: created_box.id,
+                identifier: &String::from(receipt_box.note_id),
+                amount: receipt_box.amount.into(),
+                position: receipt_box.position,
+                height: receipt_box.height,
+                reserve_owner: &receipt_box.reserve_owner.to_string(),
+            };
+            let inserted_receipt = diesel::insert_into(schema::receipts::table)
+                .values(&new_receipt)
+                .returning(Receipt::as_returning())
+                .get_result(conn.borrow_mut())?;
+            add_history(
+                conn.borrow_mut(),
+                HistoryOwner::Receipt(inserted_receipt.id),
+                &receipt.history,
+            )?;
+            Ok(inserted_receipt)
+        })
+    }
+
+    /// Attempt to load a Receipt box and its note history from database.
+    pub fn get_receipt_box(&self, receipt_id: i32) -> Result<ReceiptBox, Error> {
+        let mut conn = self.pool.get()?;
+        let (receipt, ergo_box) = schema::receipts::table
+            .inner_join(schema::ergo_boxes::table)
+            .filter(schema::receipts::id.eq(receipt_id))
+            .select((Receipt::as_select(), ErgoBox::as_select()))
+            .first(conn.borrow_mut())?;
+        let ownership_entries = OwnershipEntry::belonging_to(&receipt)
+            .order_by(schema::ownership_entries::position.asc())
+            .select(OwnershipEntry::as_select())
+            .load(conn.borrow_mut())?;
+        let receipt_box = ReceiptBoxSpec::try_from(
+            &chain::ergo_box::ErgoBox::try_from(ergo_box)
+                .expect("Failed to parse ErgoBox from database"),
+        )
+        .expect("Failed to parse ReceiptBoxSpec from database");
+        let digest = receipt_box.history.digest;
+        Ok(
+            ReceiptBox::new(receipt_box, load_history(ownership_entries, digest))
+                .expect("Failed to parse receipt from DB"),
+        )
+    }
+
+    pub fn get_by_box_id(&self, box_id: &BoxId) -> Result<Option<(i32, ReceiptBox)>, Error> {
+        let mut conn = self.pool.get()?;
+        let receipt_id = schema::ergo_boxes::table
+            .inner_join(schema::receipts::table)
+            .filter(schema::ergo_boxes::ergo_id.eq(&box_id.to_string()))
+            .select(schema::receipts::id)
+            .first::<i32>(&mut conn)
+            .optional()?;
+        receipt_id
+            .map(|id| Ok((id, self.get_receipt_box(id)?)))
+            .transpose()
+    }
+
+    /// List receipts created by redeeming notes against reserves owned by `pubkeys`
+    pub fn receipts_by_reserve_owners(&self, pubkeys: &[EcPoint]) -> Result<Vec<Receipt>, Error> {
+        let mut conn = self.pool.get()?;
+        Ok(schema::receipts::table
+            .filter(
+                schema::receipts::reserve_owner.eq_any(pubkeys.iter().cloned().map(String::from)),
+            )
+            .select(Receipt::as_select())
+            .load(&mut conn)?)
+    }
+
+    /// List all known receipts
+    pub fn receipts(&self) -> Result<Vec<Receipt>, Error> {
+        let mut conn = self.pool.get()?;
+        Ok(schema::receipts::table
+            .select(Receipt::as_select())
+            .load(&mut conn)?)
+    }
+
+    /// Delete boxes that are not in latest scan (spent)
+    pub fn delete_not_in(&self, ids: impl Iterator<Item = BoxId>) -> Result<Vec<String>, Error> {
+        let mut conn = self.pool.get()?;
+        let ids = ids.map(|id| id.to_string());
+        let spent_boxes = schema::receipts::table
+            .inner_join(schema::ergo_boxes::table)
+            .filter(diesel::dsl::not(schema::ergo_boxes::ergo_id.eq_any(ids)))
+            .select(schema::ergo_boxes::id)
+            .into_boxed();
+        let query = delete(schema::ergo_boxes::table)
+            .filter(schema::ergo_boxes::id.eq_any(spent_boxes))
+            .returning(schema::ergo_boxes::ergo_id);
+        query.load(&mut conn).map_err(Into::into)
+    }
+}
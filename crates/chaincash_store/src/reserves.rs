@@ -23,6 +23,8 @@ pub struct Reserve {
     pub identifier: String,
     /// Owner of the reserve, GE encoded as hex string.
     pub owner: String,
+    /// Height refund was initiated at, if reserve is being refunded.
+    pub refund_height: Option<i64>,
 }
 
 #[derive(Insertable)]
@@ -32,6 +34,7 @@ pub struct NewReserve<'a> {
     pub denomination_id: Option<i32>,
     pub identifier: &'a str,
     pub owner: &'a str,
+    pub refund_height: Option<i64>,
 }
 
 pub struct ReserveRepository {
@@ -62,6 +65,7 @@ impl ReserveRepository {
             denomination_id: None, // TODO, allow setting different denominations, should be auto detected by inspecting the ErgoBox
             owner: &reserve_box.owner.to_string(),
             identifier: &String::from(reserve_box.identifier),
+            refund_height: reserve_box.refund_height,
         };
         let query = diesel::insert_into(schema::reserves::table)
             .values(&new_reserve)
@@ -31,11 +31,25 @@ diesel::table! {
 diesel::table! {
     ownership_entries (id) {
         id -> Integer,
-        note_id -> Integer,
+        note_id -> Nullable<Integer>,
+        receipt_id -> Nullable<Integer>,
         amount -> BigInt,
         position -> BigInt,
         reserve_nft_id -> Text,
         signature -> Binary,
+        signer -> Text,
+    }
+}
+
+diesel::table! {
+    receipts (id) {
+        id -> Integer,
+        identifier -> Text,
+        box_id -> Integer,
+        amount -> BigInt,
+        position -> BigInt,
+        height -> Integer,
+        reserve_owner -> Text,
     }
 }
 
@@ -46,6 +60,7 @@ diesel::table! {
         owner -> Text,
         box_id -> Integer,
         denomination_id -> Nullable<Integer>,
+        refund_height -> Nullable<BigInt>,
     }
 }
 
@@ -60,6 +75,8 @@ diesel::table! {
 diesel::joinable!(notes -> denominations (denomination_id));
 diesel::joinable!(notes -> ergo_boxes (box_id));
 diesel::joinable!(ownership_entries -> notes (note_id));
+diesel::joinable!(ownership_entries -> receipts (receipt_id));
+diesel::joinable!(receipts -> ergo_boxes (box_id));
 diesel::joinable!(reserves -> denominations (denomination_id));
 diesel::joinable!(reserves -> ergo_boxes (box_id));
 
@@ -68,6 +85,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     ergo_boxes,
     notes,
     ownership_entries,
+    receipts,
     reserves,
     scans,
 );